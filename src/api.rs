@@ -0,0 +1,650 @@
+use anyhow::{Context, Result};
+use async_graphql::http::GraphiQLSource;
+use async_graphql_axum::{GraphQLRequest, GraphQLResponse};
+use axum::{
+    body::Bytes,
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        ConnectInfo, Path, Query, Request, State,
+    },
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    middleware::{self, Next},
+    response::{
+        sse::{Event as SseEvent, KeepAlive, Sse},
+        Html, IntoResponse, Response,
+    },
+    routing::get,
+    Extension, Json, Router,
+};
+use futures::{Stream, StreamExt};
+use serde::Deserialize;
+use serde_json::json;
+use std::env;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tracing::{error, info, warn};
+use utoipa::{IntoParams, ToSchema};
+use utoipa_axum::{router::OpenApiRouter, routes};
+
+use crate::auth::{self, ApiKey, Client, Decision, RateLimitConfig, RateLimiter, UsageCounter};
+use crate::feed::{FeedFilter, FeedItem};
+use crate::graphql::{self, LaunchpadSchema};
+use crate::health::{check_readiness, Readiness, ReadinessConfig};
+use crate::leaderboards::Leaderboards;
+use crate::rest;
+use crate::shutdown::{self, Shutdown};
+use crate::webhooks::{
+    self, CreatedSubscription, Delivery, NewSubscription, Subscription, WebhookConfig,
+};
+use crate::{env_or, Indexer};
+
+#[derive(Clone)]
+pub(crate) struct ApiState {
+    pub(crate) indexer: Indexer,
+    readiness: Arc<ReadinessConfig>,
+    limiter: Arc<RateLimiter>,
+    usage: Arc<UsageCounter>,
+    pub(crate) leaderboards: Arc<Leaderboards>,
+    webhooks: Arc<WebhookConfig>,
+    schema: LaunchpadSchema,
+    // Redoc bundle served with /docs, if installed
+    redoc: Option<Bytes>,
+    // Ends open streams when the server shuts down
+    shutdown: Shutdown,
+}
+
+// Routes any key, or the unauthenticated tier, may use
+fn keyed_routes() -> OpenApiRouter<ApiState> {
+    rest::router()
+        .routes(routes!(feed_ws))
+        .routes(routes!(feed_sse))
+        .routes(routes!(graphiql, graphql_query))
+}
+
+// Management routes, which need an admin key on top of the usual checks
+fn admin_routes() -> OpenApiRouter<ApiState> {
+    rest::admin_router()
+        .routes(routes!(list_webhooks, create_webhook))
+        .routes(routes!(delete_webhook))
+        .routes(routes!(webhook_deliveries))
+}
+
+// Probes and scrapers never need a key
+fn open_routes() -> OpenApiRouter<ApiState> {
+    OpenApiRouter::new()
+        .routes(routes!(metrics))
+        .routes(routes!(healthz))
+        .routes(routes!(readyz))
+}
+
+/// The OpenAPI document for every route the API serves.
+pub fn openapi() -> utoipa::openapi::OpenApi {
+    let mut spec = keyed_routes().split_for_parts().1;
+    spec.merge(admin_routes().split_for_parts().1);
+    spec.merge(open_routes().split_for_parts().1);
+    spec
+}
+
+fn router(state: ApiState) -> Router {
+    let (keyed, _) = keyed_routes().split_for_parts();
+    let (admin, _) = admin_routes().split_for_parts();
+    let (open, _) = open_routes().split_for_parts();
+    let spec = openapi();
+    // The API docs never need a key either
+    let open = open
+        .route("/openapi.json", get(move || async move { Json(spec) }))
+        .route("/docs", get(docs))
+        .route("/docs/redoc.standalone.js", get(redoc));
+    keyed
+        .merge(admin.route_layer(middleware::from_fn(require_admin)))
+        .route_layer(middleware::from_fn_with_state(state.clone(), authorize))
+        .merge(open)
+        .with_state(state)
+}
+
+pub async fn serve(indexer: Indexer, shutdown: Shutdown) -> Result<()> {
+    let addr = env::var("HTTP_BIND_ADDR").unwrap_or_else(|_| "0.0.0.0:8080".to_string());
+    let listener = TcpListener::bind(&addr).await?;
+    info!("Serving HTTP endpoints on {}", addr);
+    serve_on(listener, indexer, shutdown).await
+}
+
+// Serve on an already bound listener, e.g. an ephemeral port in tests
+pub async fn serve_on(listener: TcpListener, indexer: Indexer, shutdown: Shutdown) -> Result<()> {
+    serve_with_limits(listener, indexer, shutdown, RateLimitConfig::from_env()).await
+}
+
+pub async fn serve_with_limits(
+    listener: TcpListener,
+    indexer: Indexer,
+    mut shutdown: Shutdown,
+    limits: RateLimitConfig,
+) -> Result<()> {
+    let usage = Arc::new(UsageCounter::default());
+    let flush_interval = Duration::from_millis(env_or("API_USAGE_FLUSH_MS", 1_000));
+    let flushing = tokio::spawn(flush_usage(
+        indexer.clone(),
+        usage.clone(),
+        flush_interval,
+        shutdown.clone(),
+    ));
+    let state = ApiState {
+        indexer: indexer.clone(),
+        readiness: Arc::new(ReadinessConfig::from_env()),
+        limiter: Arc::new(RateLimiter::new(limits)),
+        usage,
+        leaderboards: Arc::new(Leaderboards::new(
+            indexer.db().clone(),
+            Duration::from_secs(env_or("LEADERBOARD_TTL_SECS", 60)),
+        )),
+        webhooks: Arc::new(WebhookConfig::from_env()),
+        schema: graphql::schema(indexer.db().clone()),
+        redoc: load_redoc_bundle()?,
+        shutdown: shutdown.clone(),
+    };
+    let app = router(state).into_make_service_with_connect_info::<SocketAddr>();
+    axum::serve(listener, app)
+        .with_graceful_shutdown(async move { shutdown::requested(&mut shutdown).await })
+        .await?;
+    // Writes out the usage counted since its last flush
+    flushing.await?;
+    Ok(())
+}
+
+// Write counted key usage out every `interval`, and once more on shutdown
+async fn flush_usage(
+    indexer: Indexer,
+    usage: Arc<UsageCounter>,
+    interval: Duration,
+    mut stopped: Shutdown,
+) {
+    let mut ticks = tokio::time::interval(interval);
+    loop {
+        let stopping = tokio::select! {
+            biased;
+            _ = shutdown::requested(&mut stopped) => true,
+            _ = ticks.tick() => false,
+        };
+        if let Err(e) = usage.flush(indexer.db()).await {
+            error!(error = %e, "Failed to record API key usage");
+        }
+        if stopping {
+            return;
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    tag = "operations",
+    responses(
+        (
+            status = 200,
+            description = "Prometheus metrics",
+            body = String,
+            content_type = "text/plain"
+        ),
+        (status = 500, description = "Metrics could not be rendered")
+    )
+)]
+async fn metrics(State(state): State<ApiState>) -> Response {
+    let indexer = &state.indexer;
+    match indexer.metrics.render(&indexer.queue_depth) {
+        Ok(body) => (
+            StatusCode::OK,
+            [(header::CONTENT_TYPE, prometheus::TEXT_FORMAT)],
+            body,
+        )
+            .into_response(),
+        Err(e) => {
+            error!("Failed to render metrics: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+// Liveness: the process is up and serving requests
+#[utoipa::path(
+    get,
+    path = "/healthz",
+    tag = "operations",
+    responses((
+        status = 200,
+        description = "The process is up",
+        body = String,
+        content_type = "text/plain"
+    ))
+)]
+async fn healthz() -> &'static str {
+    "ok"
+}
+
+#[utoipa::path(
+    get,
+    path = "/readyz",
+    tag = "operations",
+    responses(
+        (status = 200, description = "Ready to serve", body = Readiness),
+        (status = 503, description = "A check failed", body = Readiness)
+    )
+)]
+async fn readyz(State(state): State<ApiState>) -> Response {
+    let readiness = check_readiness(&state.indexer, &state.readiness).await;
+    let status = if readiness.ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(readiness)).into_response()
+}
+
+// The key a request presents, if any
+fn presented_key(request: &Request) -> Option<String> {
+    let headers = request.headers();
+    if let Some(key) = headers.get(auth::API_KEY_HEADER) {
+        return key.to_str().ok().map(str::to_string);
+    }
+    if let Some(bearer) = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+    {
+        return Some(bearer.to_string());
+    }
+    request.uri().query().and_then(|query| {
+        query.split('&').find_map(|pair| {
+            let (name, value) = pair.split_once('=')?;
+            (name == auth::API_KEY_PARAM).then(|| value.to_string())
+        })
+    })
+}
+
+// Authenticate the request's key, if any, and charge its rate limit
+async fn authorize(
+    State(state): State<ApiState>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let db = state.indexer.db();
+    let limiter = &state.limiter;
+    let key = match presented_key(&request) {
+        Some(presented) => {
+            // Addresses that keep presenting bad keys aren't looked up again
+            // until their bucket refills
+            let failed = Client::FailedKey(peer.ip());
+            let failed_limit = limiter.config.failed_key_per_minute;
+            if let Decision::Limited { retry_after } = limiter.peek(&failed, failed_limit) {
+                return too_many_requests(retry_after);
+            }
+            match auth::authenticate(db, &presented).await {
+                Ok(Some(key)) => Some(key),
+                Ok(None) => {
+                    limiter.check(failed, failed_limit);
+                    return (StatusCode::UNAUTHORIZED, "invalid or revoked API key")
+                        .into_response();
+                }
+                Err(e) => return internal_error(e),
+            }
+        }
+        None if limiter.config.anonymous_per_minute == 0 => {
+            return (StatusCode::UNAUTHORIZED, "API key required").into_response()
+        }
+        None => None,
+    };
+
+    let limit = limiter.limit(key.as_ref());
+    let client = match &key {
+        Some(key) => Client::Key(key.id.clone()),
+        None => Client::Anonymous(peer.ip()),
+    };
+    let decision = limiter.check(client, limit);
+    if let Some(key) = &key {
+        state
+            .usage
+            .record(&key.id, matches!(decision, Decision::Allowed { .. }));
+    }
+
+    let (mut response, remaining) = match decision {
+        Decision::Allowed { remaining } => {
+            if let Some(key) = key {
+                request.extensions_mut().insert(key);
+            }
+            (next.run(request).await, remaining)
+        }
+        Decision::Limited { retry_after } => (too_many_requests(retry_after), 0),
+    };
+    let headers = response.headers_mut();
+    headers.insert("x-ratelimit-limit", HeaderValue::from(limit));
+    headers.insert("x-ratelimit-remaining", HeaderValue::from(remaining));
+    response
+}
+
+fn too_many_requests(retry_after: Duration) -> Response {
+    let mut response = (StatusCode::TOO_MANY_REQUESTS, "rate limit exceeded").into_response();
+    let seconds = retry_after.as_secs_f64().ceil() as u64;
+    response
+        .headers_mut()
+        .insert(header::RETRY_AFTER, HeaderValue::from(seconds.max(1)));
+    response
+}
+
+// Let only admin keys through; runs after `authorize` has put the
+// request's key, if any, in its extensions
+async fn require_admin(key: Option<Extension<ApiKey>>, request: Request, next: Next) -> Response {
+    match key {
+        Some(Extension(key)) if key.admin => next.run(request).await,
+        Some(_) => (StatusCode::FORBIDDEN, "admin API key required").into_response(),
+        None => (StatusCode::UNAUTHORIZED, "admin API key required").into_response(),
+    }
+}
+
+// Log a failed query and hide its details from the client
+pub(crate) fn internal_error(e: anyhow::Error) -> Response {
+    error!("API request failed: {}", e);
+    StatusCode::INTERNAL_SERVER_ERROR.into_response()
+}
+
+#[utoipa::path(
+    post,
+    path = "/webhooks",
+    tag = "webhooks",
+    request_body = NewSubscription,
+    responses(
+        (status = 201, description = "The subscription, with its secret", body = CreatedSubscription),
+        (status = 400, description = "Invalid or non-public URL"),
+        (status = 401, description = "No API key"),
+        (status = 403, description = "Not an admin API key"),
+        (status = 500, description = "Database error")
+    )
+)]
+async fn create_webhook(
+    State(state): State<ApiState>,
+    Json(new): Json<NewSubscription>,
+) -> Response {
+    if let Err(e) = new.validate(&state.webhooks).await {
+        return (StatusCode::BAD_REQUEST, e.to_string()).into_response();
+    }
+    match webhooks::subscribe(state.indexer.db(), new, &state.webhooks).await {
+        Ok(created) => (StatusCode::CREATED, Json(created)).into_response(),
+        Err(e) => internal_error(e),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/webhooks",
+    tag = "webhooks",
+    responses(
+        (status = 200, description = "Subscriptions, oldest first", body = [Subscription]),
+        (status = 401, description = "No API key"),
+        (status = 403, description = "Not an admin API key"),
+        (status = 500, description = "Database error")
+    )
+)]
+async fn list_webhooks(State(state): State<ApiState>) -> Response {
+    match webhooks::subscriptions(state.indexer.db()).await {
+        Ok(subscriptions) => Json(subscriptions).into_response(),
+        Err(e) => internal_error(e),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/webhooks/{id}",
+    tag = "webhooks",
+    params(("id" = String, Path, description = "Subscription id")),
+    responses(
+        (status = 204, description = "Unsubscribed"),
+        (status = 401, description = "No API key"),
+        (status = 403, description = "Not an admin API key"),
+        (status = 404, description = "No such subscription"),
+        (status = 500, description = "Database error")
+    )
+)]
+async fn delete_webhook(State(state): State<ApiState>, Path(id): Path<String>) -> Response {
+    match webhooks::unsubscribe(state.indexer.db(), &id).await {
+        Ok(true) => StatusCode::NO_CONTENT.into_response(),
+        Ok(false) => StatusCode::NOT_FOUND.into_response(),
+        Err(e) => internal_error(e),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/webhooks/{id}/deliveries",
+    tag = "webhooks",
+    params(("id" = String, Path, description = "Subscription id")),
+    responses(
+        (status = 200, description = "Deliveries, oldest first", body = [Delivery]),
+        (status = 401, description = "No API key"),
+        (status = 403, description = "Not an admin API key"),
+        (status = 500, description = "Database error")
+    )
+)]
+async fn webhook_deliveries(State(state): State<ApiState>, Path(id): Path<String>) -> Response {
+    match webhooks::deliveries(state.indexer.db(), &id).await {
+        Ok(deliveries) => Json(deliveries).into_response(),
+        Err(e) => internal_error(e),
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct FeedQuery {
+    /// Only events of this launchpad, including trades resolved to it.
+    launchpad_id: Option<String>,
+    /// Only events involving this wallet.
+    wallet: Option<String>,
+    /// Resume after this event id.
+    after: Option<u64>,
+}
+
+impl FeedQuery {
+    fn filter(&self) -> FeedFilter {
+        FeedFilter {
+            launchpad_id: self.launchpad_id.clone(),
+            wallet: self.wallet.clone(),
+        }
+    }
+}
+
+// Feed items for `query` until the server shuts down
+fn feed_items(
+    state: &ApiState,
+    query: &FeedQuery,
+    after: Option<u64>,
+) -> impl Stream<Item = FeedItem> {
+    let mut shutdown = state.shutdown.clone();
+    state
+        .indexer
+        .feed()
+        .subscribe(query.filter(), after)
+        .take_until(async move { shutdown::requested(&mut shutdown).await })
+}
+
+#[utoipa::path(
+    get,
+    path = "/feed/ws",
+    tag = "feed",
+    params(FeedQuery),
+    responses(
+        (status = 101, description = "WebSocket of `event` and `gap` messages as JSON text"),
+        (status = 400, description = "Not a WebSocket upgrade")
+    )
+)]
+async fn feed_ws(
+    State(state): State<ApiState>,
+    Query(query): Query<FeedQuery>,
+    ws: WebSocketUpgrade,
+) -> Response {
+    ws.on_upgrade(move |socket| stream_feed(socket, state, query))
+}
+
+async fn stream_feed(mut socket: WebSocket, state: ApiState, query: FeedQuery) {
+    let items = feed_items(&state, &query, query.after);
+    tokio::pin!(items);
+    loop {
+        let item = tokio::select! {
+            item = items.next() => match item {
+                Some(item) => item,
+                None => break,
+            },
+            // Clients have nothing to say, but reading notices when they leave
+            message = socket.recv() => match message {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return,
+                Some(Ok(_)) => continue,
+            },
+        };
+        let message = match item {
+            FeedItem::Event(event) => json!({ "type": "event", "event": &*event }),
+            FeedItem::Gap { after } => json!({ "type": "gap", "after": after }),
+        };
+        if socket
+            .send(Message::Text(message.to_string()))
+            .await
+            .is_err()
+        {
+            return;
+        }
+    }
+    let _ = socket.send(Message::Close(None)).await;
+}
+
+// Resumes from `after` or, as browsers send on reconnect, `Last-Event-ID`
+#[utoipa::path(
+    get,
+    path = "/feed/sse",
+    tag = "feed",
+    params(FeedQuery),
+    responses((
+        status = 200,
+        description = "Stream of `event` and `gap` events",
+        body = String,
+        content_type = "text/event-stream"
+    ))
+)]
+async fn feed_sse(
+    State(state): State<ApiState>,
+    Query(query): Query<FeedQuery>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    let after = query.after.or_else(|| {
+        headers
+            .get("last-event-id")
+            .and_then(|id| id.to_str().ok())
+            .and_then(|id| id.parse().ok())
+    });
+    let events = feed_items(&state, &query, after).map(|item| match item {
+        FeedItem::Event(event) => SseEvent::default()
+            .id(event.id.to_string())
+            .event("event")
+            .json_data(&*event),
+        FeedItem::Gap { after } => Ok(SseEvent::default().event("gap").data(after.to_string())),
+    });
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
+
+#[utoipa::path(
+    get,
+    path = "/graphql",
+    tag = "graphql",
+    responses((
+        status = 200,
+        description = "GraphiQL explorer",
+        body = String,
+        content_type = "text/html"
+    ))
+)]
+async fn graphiql() -> Html<String> {
+    Html(GraphiQLSource::build().endpoint("/graphql").finish())
+}
+
+// Shapes of GraphQL requests and responses, for the spec only
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[allow(dead_code)]
+struct GraphQlBody {
+    query: String,
+    #[schema(value_type = Option<Object>)]
+    variables: Option<serde_json::Value>,
+    operation_name: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[allow(dead_code)]
+struct GraphQlResult {
+    #[schema(value_type = Option<Object>)]
+    data: Option<serde_json::Value>,
+    #[schema(value_type = Option<Vec<Object>>)]
+    errors: Option<Vec<serde_json::Value>>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/graphql",
+    tag = "graphql",
+    request_body = GraphQlBody,
+    responses((status = 200, description = "Result of the query", body = GraphQlResult))
+)]
+async fn graphql_query(State(state): State<ApiState>, request: GraphQLRequest) -> GraphQLResponse {
+    state.schema.execute(request.into_inner()).await.into()
+}
+
+// Redoc reads the spec served alongside it. Its bundle is served from disk
+// rather than a CDN, so the page runs no third-party script.
+async fn docs() -> Html<&'static str> {
+    Html(
+        r#"<!DOCTYPE html>
+<html>
+  <head>
+    <title>Launchpad indexer API</title>
+    <meta charset="utf-8"/>
+  </head>
+  <body>
+    <redoc spec-url="/openapi.json"></redoc>
+    <script src="/docs/redoc.standalone.js"></script>
+  </body>
+</html>"#,
+    )
+}
+
+// The Redoc 2.1.5 standalone bundle isn't checked in; fetch it with
+//
+//   curl -fsSL -o assets/redoc.standalone.js --create-dirs \
+//     https://cdn.jsdelivr.net/npm/redoc@2.1.5/bundles/redoc.standalone.js
+//
+// or point REDOC_BUNDLE_PATH at a copy.
+const DEFAULT_REDOC_BUNDLE: &str = "assets/redoc.standalone.js";
+
+// Read the Redoc bundle once at startup. A REDOC_BUNDLE_PATH that can't be
+// read stops the server; a missing default bundle only leaves /docs blank.
+fn load_redoc_bundle() -> Result<Option<Bytes>> {
+    if let Ok(path) = env::var("REDOC_BUNDLE_PATH") {
+        let bundle = std::fs::read(&path)
+            .with_context(|| format!("failed to read Redoc bundle {}", path))?;
+        return Ok(Some(bundle.into()));
+    }
+    match std::fs::read(DEFAULT_REDOC_BUNDLE) {
+        Ok(bundle) => Ok(Some(bundle.into())),
+        Err(e) => {
+            warn!(
+                "No Redoc bundle at {} ({}), /docs will not render; see README",
+                DEFAULT_REDOC_BUNDLE, e
+            );
+            Ok(None)
+        }
+    }
+}
+
+async fn redoc(State(state): State<ApiState>) -> Response {
+    match state.redoc {
+        Some(bundle) => {
+            ([(header::CONTENT_TYPE, "application/javascript")], bundle).into_response()
+        }
+        None => (StatusCode::NOT_FOUND, "Redoc bundle not installed").into_response(),
+    }
+}
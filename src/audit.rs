@@ -0,0 +1,290 @@
+//! Audit trail of privileged actions.
+//!
+//! Admin transfers, fee changes, pool pauses, whitelist updates and
+//! liquidity deployments and moves are gathered from their tables with the
+//! sender of each transaction, followed through the row's link to
+//! `transactions_meta`. Each action is checked against the admin at the
+//! time, rebuilt from `admin_transfers`: the admin before the first transfer
+//! is that transfer's `previous_admin`, or `ADMIN_ADDRESS` when no transfer
+//! is indexed. A transfer is judged against the admin it replaced. Pages and
+//! the flagged filter are applied by the database, so a page only reads its
+//! own rows.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::env;
+use std::fmt;
+use std::str::FromStr;
+use surrealdb::{engine::any::Any, Surreal};
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AdminAction {
+    AdminTransferred,
+    FeeUpdated,
+    PoolPaused,
+    PoolUnpaused,
+    LiquidityDeployed,
+    LiquidityMoved,
+    WhitelistUpdated,
+}
+
+// Tables holding each privileged action
+const ACTIONS: [(AdminAction, &str); 7] = [
+    (AdminAction::AdminTransferred, "admin_transfers"),
+    (AdminAction::FeeUpdated, "fee_updates"),
+    (AdminAction::PoolPaused, "pool_pauses"),
+    (AdminAction::PoolUnpaused, "pool_unpauses"),
+    (AdminAction::LiquidityDeployed, "liquidity_deployments"),
+    (AdminAction::LiquidityMoved, "liquidity_moves"),
+    (AdminAction::WhitelistUpdated, "whitelist_updates"),
+];
+
+/// One privileged action.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct AuditEntry {
+    pub action: AdminAction,
+    pub launchpad_id: Option<String>,
+    /// Sender of the transaction, if it is known.
+    pub sender: Option<String>,
+    /// Admin at the time of the action, if it is known.
+    pub admin: Option<String>,
+    /// Whether the sender was the admin; none when either is unknown.
+    pub by_admin: Option<bool>,
+    /// Milliseconds since the epoch.
+    pub timestamp: Option<u64>,
+    pub tx_digest: String,
+    /// Pass as `before` for the entries after this one.
+    pub cursor: String,
+    /// The event's remaining fields.
+    #[schema(value_type = Object)]
+    pub details: Value,
+}
+
+#[derive(Deserialize)]
+struct Transfer {
+    previous_admin: String,
+    new_admin: String,
+    timestamp: u64,
+}
+
+/// Who held the admin role over time.
+#[derive(Debug, Clone)]
+pub struct AdminHistory {
+    initial: Option<String>,
+    // (timestamp, new admin), oldest first
+    transfers: Vec<(u64, String)>,
+}
+
+impl AdminHistory {
+    pub async fn load(db: &Surreal<Any>, initial_admin: Option<String>) -> Result<Self> {
+        let transfers: Vec<Transfer> = db
+            .query(
+                "SELECT previous_admin, new_admin, timestamp FROM admin_transfers \
+                 WHERE timestamp != NONE ORDER BY timestamp ASC",
+            )
+            .await?
+            .take(0)?;
+        Ok(Self {
+            initial: transfers
+                .first()
+                .map(|t| t.previous_admin.clone())
+                .or(initial_admin),
+            transfers: transfers
+                .into_iter()
+                .map(|t| (t.timestamp, t.new_admin))
+                .collect(),
+        })
+    }
+
+    /// Admin at `timestamp`, counting transfers made at that time.
+    pub fn admin_at(&self, timestamp: u64) -> Option<&str> {
+        self.transfers
+            .iter()
+            .rev()
+            .find(|(at, _)| *at <= timestamp)
+            .map(|(_, admin)| admin.as_str())
+            .or(self.initial.as_deref())
+    }
+
+    // Condition matching rows whose sender wasn't the admin at their
+    // timestamp, with the values it binds
+    fn not_admin(&self) -> (String, Vec<(String, Value)>) {
+        let mut periods = Vec::new();
+        let mut binds = Vec::new();
+        if let Some(initial) = &self.initial {
+            let until = self.transfers.first().map(|(at, _)| *at);
+            periods.push("(timestamp < $until_0 AND transaction.sender != $admin_0)".to_string());
+            binds.push((
+                "until_0".to_string(),
+                json!(until.unwrap_or(i64::MAX as u64)),
+            ));
+            binds.push(("admin_0".to_string(), json!(initial)));
+        }
+        for (i, (from, admin)) in self.transfers.iter().enumerate() {
+            let n = i + 1;
+            let until = self.transfers.get(n).map_or(i64::MAX as u64, |(at, _)| *at);
+            periods.push(format!(
+                "(timestamp >= $from_{0} AND timestamp < $until_{0} \
+                 AND transaction.sender != $admin_{0})",
+                n
+            ));
+            binds.push((format!("from_{}", n), json!(from)));
+            binds.push((format!("until_{}", n), json!(until)));
+            binds.push((format!("admin_{}", n), json!(admin)));
+        }
+        let condition = if periods.is_empty() {
+            "false".to_string()
+        } else {
+            format!("({})", periods.join(" OR "))
+        };
+        (condition, binds)
+    }
+}
+
+/// Position of an entry in the trail, newest first: its timestamp, then its
+/// row key to break ties.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cursor {
+    timestamp: u64,
+    key: String,
+}
+
+impl fmt::Display for Cursor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.timestamp, self.key)
+    }
+}
+
+impl FromStr for Cursor {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (timestamp, key) = s.split_once(':').context("cursor is not timestamp:key")?;
+        Ok(Self {
+            timestamp: timestamp
+                .parse()
+                .context("cursor timestamp is not a number")?,
+            key: key.to_string(),
+        })
+    }
+}
+
+/// Admin before any indexed transfer, from `ADMIN_ADDRESS`.
+pub fn initial_admin() -> Option<String> {
+    env::var("ADMIN_ADDRESS").ok()
+}
+
+// Split a stored row into its common columns and the event's own fields
+fn entry(action: AdminAction, mut row: serde_json::Map<String, Value>) -> (AuditEntry, String) {
+    let mut take_string = |key: &str| match row.remove(key) {
+        Some(Value::String(s)) => Some(s),
+        _ => None,
+    };
+    let tx_digest = take_string("tx_digest").unwrap_or_default();
+    let launchpad_id = take_string("launchpad_id");
+    let sender = take_string("sender");
+    let key = take_string("key").unwrap_or_default();
+    let timestamp = row.remove("timestamp").and_then(|t| t.as_u64());
+    let cursor = Cursor {
+        timestamp: timestamp.unwrap_or(0),
+        key: key.clone(),
+    };
+    let entry = AuditEntry {
+        action,
+        launchpad_id,
+        sender,
+        admin: None,
+        by_admin: None,
+        timestamp,
+        tx_digest,
+        cursor: cursor.to_string(),
+        details: Value::Object(row),
+    };
+    (entry, key)
+}
+
+/// Up to `limit` privileged actions after `before`, newest first,
+/// optionally only those not made by the admin of the time.
+pub async fn audit_trail(
+    db: &Surreal<Any>,
+    history: &AdminHistory,
+    flagged_only: bool,
+    before: Option<&Cursor>,
+    limit: usize,
+) -> Result<Vec<AuditEntry>> {
+    let (not_admin, mut binds) = if flagged_only {
+        history.not_admin()
+    } else {
+        (String::new(), Vec::new())
+    };
+    let mut conditions: Vec<&str> = Vec::new();
+    if let Some(before) = before {
+        conditions.push(
+            "((timestamp ?? 0) < $before_order \
+             OR ((timestamp ?? 0) = $before_order AND <string> meta::id(id) < $before_key))",
+        );
+        binds.push(("before_order".to_string(), json!(before.timestamp)));
+        binds.push(("before_key".to_string(), json!(before.key)));
+    }
+    if flagged_only {
+        conditions.push("transaction.sender != NONE AND timestamp != NONE");
+    }
+    let statements: Vec<String> = ACTIONS
+        .iter()
+        .map(|(action, table)| {
+            let mut conditions = conditions.clone();
+            if flagged_only {
+                // A transfer is judged against the admin it replaced
+                conditions.push(match action {
+                    AdminAction::AdminTransferred => "transaction.sender != previous_admin",
+                    _ => not_admin.as_str(),
+                });
+            }
+            let condition = if conditions.is_empty() {
+                String::new()
+            } else {
+                format!("WHERE {}", conditions.join(" AND "))
+            };
+            format!(
+                "SELECT *, transaction.sender AS sender, <string> meta::id(id) AS key \
+                 OMIT id, transaction FROM {} {} ORDER BY timestamp DESC, key DESC LIMIT {}",
+                table, condition, limit
+            )
+        })
+        .collect();
+    let mut query = db.query(statements.join(";"));
+    for bind in binds {
+        query = query.bind(bind);
+    }
+    let mut response = query.await?;
+
+    let mut entries = Vec::new();
+    for (i, (action, _)) in ACTIONS.iter().enumerate() {
+        let rows: Vec<serde_json::Map<String, Value>> = response.take(i)?;
+        entries.extend(rows.into_iter().map(|row| entry(*action, row)));
+    }
+    entries.sort_by(|(a, a_key), (b, b_key)| {
+        b.timestamp.cmp(&a.timestamp).then_with(|| b_key.cmp(a_key))
+    });
+    entries.truncate(limit);
+
+    let mut entries: Vec<AuditEntry> = entries.into_iter().map(|(entry, _)| entry).collect();
+    for entry in &mut entries {
+        entry.admin = match (entry.action, entry.timestamp) {
+            (AdminAction::AdminTransferred, _) => {
+                entry.details["previous_admin"].as_str().map(str::to_string)
+            }
+            (_, Some(timestamp)) => history.admin_at(timestamp).map(str::to_string),
+            (_, None) => None,
+        };
+        entry.by_admin = match (&entry.sender, &entry.admin) {
+            (Some(sender), Some(admin)) => Some(sender == admin),
+            _ => None,
+        };
+    }
+
+    Ok(entries)
+}
@@ -0,0 +1,332 @@
+//! API keys and per-client rate limiting for the HTTP API.
+//!
+//! Clients present a key in the `X-API-Key` header, as a bearer token, or in
+//! the `api_key` query parameter for browser WebSocket and EventSource
+//! clients that can't set headers. Only a SHA-256 hash of each key is
+//! stored. Every client draws from a token bucket refilled at its
+//! per-minute limit: keys use their own limit or the keyed tier's, and
+//! requests without a key share the unauthenticated tier per IP address.
+//! Unknown or revoked keys are charged to a separate bucket per IP address,
+//! checked before the key is looked up, so guessing keys is throttled too.
+//! Key usage is counted in memory and written out periodically.
+//! Management routes, like webhook subscriptions, also need an admin key.
+
+use anyhow::{bail, Result};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::iter;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use surrealdb::{engine::any::Any, Surreal};
+use tracing::info;
+
+use crate::env_or;
+
+pub const API_KEY_HEADER: &str = "X-API-Key";
+pub const API_KEY_PARAM: &str = "api_key";
+
+// Buckets kept before idle, then least recently used, ones are dropped
+const MAX_BUCKETS: usize = 100_000;
+
+#[derive(Debug, Clone)]
+pub struct RateLimitConfig {
+    // Requests per minute per IP without a key; 0 requires a key
+    pub anonymous_per_minute: u32,
+    // Requests per minute for keys without their own limit
+    pub key_per_minute: u32,
+    // Unknown or revoked keys tried per minute per IP
+    pub failed_key_per_minute: u32,
+}
+
+impl RateLimitConfig {
+    pub fn from_env() -> Self {
+        Self {
+            anonymous_per_minute: env_or("RATE_LIMIT_ANONYMOUS_PER_MINUTE", 60),
+            key_per_minute: env_or("RATE_LIMIT_KEY_PER_MINUTE", 600),
+            failed_key_per_minute: env_or("RATE_LIMIT_FAILED_KEY_PER_MINUTE", 10),
+        }
+    }
+}
+
+/// A stored API key, without the key itself.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApiKey {
+    pub id: String,
+    pub name: String,
+    // First characters of the key, to tell keys apart in listings
+    pub prefix: String,
+    // Overrides the keyed tier's limit
+    pub rate_per_minute: Option<u32>,
+    // Allowed on management routes
+    #[serde(default)]
+    pub admin: bool,
+    pub requests: u64,
+    pub rejected: u64,
+    pub created_at: i64,
+    pub last_used_at: Option<i64>,
+    pub revoked_at: Option<i64>,
+}
+
+const KEY_FIELDS: &str = "meta::id(id) AS id, name, prefix, rate_per_minute, admin, \
+    requests, rejected, created_at, last_used_at, revoked_at";
+
+fn hash(key: &str) -> String {
+    hex::encode(Sha256::digest(key.as_bytes()))
+}
+
+/// Create a key, returning it with the only copy of the key itself.
+pub async fn create_key(
+    db: &Surreal<Any>,
+    name: &str,
+    rate_per_minute: Option<u32>,
+    admin: bool,
+) -> Result<(ApiKey, String)> {
+    let random: Option<String> = db.query("RETURN rand::string(40)").await?.take(0)?;
+    let Some(random) = random else {
+        bail!("could not generate an API key");
+    };
+    let key = format!("lpk_{}", random);
+    let mut response = db
+        .query(
+            "LET $created = CREATE ONLY api_keys SET name = $name, key_hash = $key_hash, \
+             prefix = $prefix, rate_per_minute = $rate_per_minute, admin = $admin, \
+             requests = 0, rejected = 0, created_at = $now",
+        )
+        .query(format!("SELECT {} FROM $created.id", KEY_FIELDS))
+        .bind(("name", name.to_string()))
+        .bind(("key_hash", hash(&key)))
+        .bind(("prefix", key[..12].to_string()))
+        .bind(("rate_per_minute", rate_per_minute))
+        .bind(("admin", admin))
+        .bind(("now", Utc::now().timestamp_millis()))
+        .await?;
+    let created: Option<ApiKey> = response.take(1)?;
+    match created {
+        Some(api_key) => {
+            info!(id = %api_key.id, name = %api_key.name, "Created API key");
+            Ok((api_key, key))
+        }
+        None => bail!("API key was not created"),
+    }
+}
+
+pub async fn keys(db: &Surreal<Any>) -> Result<Vec<ApiKey>> {
+    let keys = db
+        .query(format!(
+            "SELECT {} FROM api_keys ORDER BY created_at",
+            KEY_FIELDS
+        ))
+        .await?
+        .take(0)?;
+    Ok(keys)
+}
+
+// Returns whether an active key was revoked
+pub async fn revoke_key(db: &Surreal<Any>, id: &str) -> Result<bool> {
+    let revoked: Vec<String> = db
+        .query(
+            "UPDATE type::thing('api_keys', $id) SET revoked_at = $now \
+             WHERE revoked_at = NONE RETURN VALUE meta::id(id)",
+        )
+        .bind(("id", id.to_string()))
+        .bind(("now", Utc::now().timestamp_millis()))
+        .await?
+        .take(0)?;
+    if !revoked.is_empty() {
+        info!(id, "Revoked API key");
+    }
+    Ok(!revoked.is_empty())
+}
+
+/// The active key matching `key`, if any.
+pub async fn authenticate(db: &Surreal<Any>, key: &str) -> Result<Option<ApiKey>> {
+    let found = db
+        .query(format!(
+            "SELECT {} FROM api_keys WHERE key_hash = $key_hash AND revoked_at = NONE LIMIT 1",
+            KEY_FIELDS
+        ))
+        .bind(("key_hash", hash(key)))
+        .await?
+        .take(0)?;
+    Ok(found)
+}
+
+#[derive(Default)]
+struct Usage {
+    requests: u64,
+    rejected: u64,
+    last_used_at: i64,
+}
+
+/// Key usage counted since it was last written to the database.
+#[derive(Default)]
+pub struct UsageCounter {
+    pending: Mutex<HashMap<String, Usage>>,
+}
+
+impl UsageCounter {
+    pub fn record(&self, id: &str, allowed: bool) {
+        let mut pending = self.pending.lock().unwrap();
+        let usage = pending.entry(id.to_string()).or_default();
+        if allowed {
+            usage.requests += 1;
+        } else {
+            usage.rejected += 1;
+        }
+        usage.last_used_at = Utc::now().timestamp_millis();
+    }
+
+    /// Add the counted usage to the stored keys, keeping whatever could not
+    /// be written for the next flush.
+    pub async fn flush(&self, db: &Surreal<Any>) -> Result<()> {
+        let pending = std::mem::take(&mut *self.pending.lock().unwrap());
+        let mut pending = pending.into_iter();
+        while let Some((id, usage)) = pending.next() {
+            if let Err(e) = write_usage(db, &id, &usage).await {
+                self.restore(iter::once((id, usage)).chain(pending));
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+
+    fn restore(&self, unwritten: impl Iterator<Item = (String, Usage)>) {
+        let mut pending = self.pending.lock().unwrap();
+        for (id, usage) in unwritten {
+            let merged = pending.entry(id).or_default();
+            merged.requests += usage.requests;
+            merged.rejected += usage.rejected;
+            merged.last_used_at = merged.last_used_at.max(usage.last_used_at);
+        }
+    }
+}
+
+async fn write_usage(db: &Surreal<Any>, id: &str, usage: &Usage) -> Result<()> {
+    db.query(
+        "UPDATE type::thing('api_keys', $id) SET requests += $requests, \
+         rejected += $rejected, last_used_at = $last_used_at",
+    )
+    .bind(("id", id.to_string()))
+    .bind(("requests", usage.requests))
+    .bind(("rejected", usage.rejected))
+    .bind(("last_used_at", usage.last_used_at))
+    .await?
+    .check()?;
+    Ok(())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Client {
+    Key(String),
+    Anonymous(IpAddr),
+    // Unknown or revoked keys presented from an address
+    FailedKey(IpAddr),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decision {
+    Allowed { remaining: u32 },
+    Limited { retry_after: Duration },
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Token buckets holding up to a minute's worth of requests per client.
+pub struct RateLimiter {
+    pub config: RateLimitConfig,
+    buckets: Mutex<HashMap<Client, Bucket>>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            config,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // The limit for `key`, or for the unauthenticated tier
+    pub fn limit(&self, key: Option<&ApiKey>) -> u32 {
+        match key {
+            Some(key) => key.rate_per_minute.unwrap_or(self.config.key_per_minute),
+            None => self.config.anonymous_per_minute,
+        }
+    }
+
+    pub fn check(&self, client: Client, per_minute: u32) -> Decision {
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap();
+        if buckets.len() >= MAX_BUCKETS && !buckets.contains_key(&client) {
+            make_room(&mut buckets, now);
+        }
+        buckets
+            .entry(client)
+            .or_insert(Bucket {
+                tokens: f64::from(per_minute),
+                updated: now,
+            })
+            .take(per_minute, now, true)
+    }
+
+    // What `check` would decide for `client`, without charging it
+    pub fn peek(&self, client: &Client, per_minute: u32) -> Decision {
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap();
+        match buckets.get_mut(client) {
+            Some(bucket) => bucket.take(per_minute, now, false),
+            None => Bucket {
+                tokens: f64::from(per_minute),
+                updated: now,
+            }
+            .take(per_minute, now, false),
+        }
+    }
+}
+
+impl Bucket {
+    // Refill for the time since the last request, then take a token if
+    // `charge` and one is left
+    fn take(&mut self, per_minute: u32, now: Instant, charge: bool) -> Decision {
+        let capacity = f64::from(per_minute);
+        let per_ms = capacity / 60_000.0;
+        let elapsed = now.duration_since(self.updated).as_millis() as f64;
+        self.tokens = (self.tokens + elapsed * per_ms).min(capacity);
+        self.updated = now;
+        if self.tokens >= 1.0 {
+            if charge {
+                self.tokens -= 1.0;
+            }
+            Decision::Allowed {
+                remaining: self.tokens as u32,
+            }
+        } else if per_ms > 0.0 {
+            let wait = ((1.0 - self.tokens) / per_ms).ceil() as u64;
+            Decision::Limited {
+                retry_after: Duration::from_millis(wait),
+            }
+        } else {
+            Decision::Limited {
+                retry_after: Duration::from_secs(60),
+            }
+        }
+    }
+}
+
+// Drop buckets idle for a minute, which are full again the same as a new
+// one, and if that frees nothing the least recently used tenth
+fn make_room(buckets: &mut HashMap<Client, Bucket>, now: Instant) {
+    buckets.retain(|_, b| now.duration_since(b.updated) < Duration::from_secs(60));
+    if buckets.len() < MAX_BUCKETS {
+        return;
+    }
+    let mut updated: Vec<Instant> = buckets.values().map(|b| b.updated).collect();
+    let (_, cutoff, _) = updated.select_nth_unstable(MAX_BUCKETS / 10);
+    let cutoff = *cutoff;
+    buckets.retain(|_, b| b.updated > cutoff);
+}
@@ -0,0 +1,105 @@
+use anyhow::{Context, Result};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use sui_sdk::rpc_types::SuiEvent;
+use tokio::fs::{File, OpenOptions};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, BufWriter};
+use tracing::{error, info};
+
+use crate::Indexer;
+
+// One line of a capture file
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CapturedEvent {
+    pub received_at: DateTime<Utc>,
+    pub event: SuiEvent,
+    // Raw event contents, kept separately so a capture stays replayable even
+    // if the JSON encoding of `SuiEvent` changes between SDK versions
+    pub bcs_base64: String,
+}
+
+// Appends every raw event received to a JSONL capture file
+pub struct Recorder {
+    writer: BufWriter<File>,
+}
+
+impl Recorder {
+    pub async fn create(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .await
+            .with_context(|| format!("opening capture file {}", path.display()))?;
+        info!("Recording events to {}", path.display());
+        Ok(Self {
+            writer: BufWriter::new(file),
+        })
+    }
+
+    pub async fn record(&mut self, event: &SuiEvent) -> Result<()> {
+        let captured = CapturedEvent {
+            received_at: Utc::now(),
+            event: event.clone(),
+            bcs_base64: STANDARD.encode(&event.bcs),
+        };
+        let mut line = serde_json::to_vec(&captured)?;
+        line.push(b'\n');
+        self.writer.write_all(&line).await?;
+        Ok(())
+    }
+
+    pub async fn flush(&mut self) -> Result<()> {
+        self.writer.flush().await?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ReplaySummary {
+    pub events: usize,
+    pub failed: usize,
+}
+
+// Feed every event of a capture file through `handle_event`, in order.
+// Events that fail are logged with their line number and counted.
+pub async fn replay(indexer: &Indexer, path: impl AsRef<Path>) -> Result<ReplaySummary> {
+    let path = path.as_ref();
+    let file = File::open(path)
+        .await
+        .with_context(|| format!("opening capture file {}", path.display()))?;
+    let mut lines = BufReader::new(file).lines();
+    let mut summary = ReplaySummary::default();
+    let mut line_number = 0;
+
+    while let Some(line) = lines.next_line().await? {
+        line_number += 1;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let captured: CapturedEvent = serde_json::from_str(&line).with_context(|| {
+            format!("{}:{}: invalid capture record", path.display(), line_number)
+        })?;
+        let mut event = captured.event;
+        event.bcs = STANDARD
+            .decode(&captured.bcs_base64)
+            .with_context(|| format!("{}:{}: invalid bcs_base64", path.display(), line_number))?;
+
+        summary.events += 1;
+        if let Err(e) = indexer.handle_event(event).await {
+            summary.failed += 1;
+            error!(line = line_number, error = %e, "Failed to replay event");
+        }
+    }
+
+    info!(
+        "Replayed {} events from {} ({} failed)",
+        summary.events,
+        path.display(),
+        summary.failed
+    );
+    Ok(summary)
+}
@@ -0,0 +1,109 @@
+//! Turning raw `SuiEvent`s into typed [`LaunchpadEvent`]s.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use sui_sdk::{rpc_types::SuiEvent, types::base_types::ObjectID};
+use tracing::error;
+
+use crate::events::{self, ContractEvent, LaunchpadEvent};
+
+/// Where an event came from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventMetadata {
+    pub tx_digest: String,
+    pub event_seq: u64,
+    pub sender: String,
+    /// Checkpoint of the emitting transaction. Events don't carry it, so it
+    /// is `None` unless the transaction has been looked up through a
+    /// [`TransactionSource`](crate::transactions::TransactionSource).
+    pub checkpoint: Option<u64>,
+    /// Checkpoint timestamp in milliseconds.
+    pub timestamp: Option<u64>,
+    /// Launchpad the event's transaction traded on. Events that don't name
+    /// one, like `TokensPurchased`, take it from the transaction's
+    /// `BalanceUpdate`.
+    pub launchpad_id: Option<String>,
+}
+
+/// A decoded event together with where it came from.
+#[derive(Debug, Clone)]
+pub struct DecodedEvent {
+    pub metadata: EventMetadata,
+    pub event: LaunchpadEvent,
+}
+
+type Decoder = fn(&[u8]) -> Result<LaunchpadEvent>;
+
+/// Decoders for the launchpad module's events, keyed by the `name` of the
+/// event's `StructTag`.
+#[derive(Default)]
+pub struct Registry {
+    decoders: HashMap<&'static str, Decoder>,
+}
+
+impl Registry {
+    /// Decode events named `E::NAME` as `E`.
+    pub fn register<E: ContractEvent>(&mut self) {
+        fn decode<E: ContractEvent>(bytes: &[u8]) -> Result<LaunchpadEvent> {
+            Ok(bcs::from_bytes::<E>(bytes)?.into())
+        }
+        self.decoders.insert(E::NAME, decode::<E>);
+    }
+
+    pub fn decode(&self, name: &str, bytes: &[u8]) -> Result<Option<LaunchpadEvent>> {
+        self.decoders
+            .get(name)
+            .map(|decode| decode(bytes))
+            .transpose()
+    }
+}
+
+/// The registry of every event in [`crate::events`].
+pub fn registry() -> &'static Registry {
+    static REGISTRY: OnceLock<Registry> = OnceLock::new();
+    REGISTRY.get_or_init(|| {
+        let mut registry = Registry::default();
+        events::register_all(&mut registry);
+        registry
+    })
+}
+
+impl LaunchpadEvent {
+    /// Decode the BCS contents of the launchpad module event `name`.
+    /// Returns `None` for names the indexer does not know.
+    pub fn decode(name: &str, bytes: &[u8]) -> Result<Option<Self>> {
+        registry().decode(name, bytes)
+    }
+}
+
+/// Decode an event emitted by the `launchpad` module of `package_id`.
+/// Returns `None`, after logging it, for any other event type.
+pub fn decode_event(package_id: &ObjectID, event: &SuiEvent) -> Result<Option<DecodedEvent>> {
+    let tag = &event.type_;
+    let ours = ObjectID::from(tag.address) == *package_id && tag.module.as_str() == "launchpad";
+    let decoded = match ours
+        .then(|| LaunchpadEvent::decode(tag.name.as_str(), &event.bcs))
+        .transpose()?
+        .flatten()
+    {
+        Some(decoded) => decoded,
+        None => {
+            error!("Unknown event type: {}", event.type_);
+            return Ok(None);
+        }
+    };
+
+    Ok(Some(DecodedEvent {
+        metadata: EventMetadata {
+            tx_digest: event.id.tx_digest.to_string(),
+            event_seq: event.id.event_seq,
+            sender: event.sender.to_string(),
+            checkpoint: None,
+            timestamp: event.timestamp_ms,
+            launchpad_id: decoded.launchpad_id(),
+        },
+        event: decoded,
+    }))
+}
@@ -0,0 +1,237 @@
+//! Event structs of the `launchpad` Move module.
+//!
+//! Field order and types match the Move declarations, so each struct decodes
+//! directly from the event's BCS bytes: `address` fields are [`SuiAddress`]
+//! and `ID` fields [`ObjectID`], both 32 raw bytes on chain, and both written
+//! as `0x`-prefixed hex in JSON. Supporting a new contract event means adding
+//! its struct here and one line to the `launchpad_events!` invocation below.
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sui_sdk::types::base_types::{ObjectID, SuiAddress};
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct TokensPurchased {
+    pub buyer: SuiAddress,
+    pub amount: u64,
+    pub timestamp: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct TokensTransferred {
+    pub from: SuiAddress,
+    pub to: SuiAddress,
+    pub amount: u64,
+    pub timestamp: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct PriceUpdate {
+    pub new_price: u64,
+    pub tokens_sold: u64,
+    pub timestamp: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct LiquidityDeployed {
+    pub launchpad_id: ObjectID,
+    pub sui_amount: u64,
+    pub timestamp: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct PoolPaused {
+    pub launchpad_id: ObjectID,
+    pub timestamp: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct PoolUnpaused {
+    pub launchpad_id: ObjectID,
+    pub timestamp: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct LaunchpadCreated {
+    pub launchpad_id: ObjectID,
+    pub creator: SuiAddress,
+    pub name: String,
+    pub description: String,
+    pub token_supply: u64,
+    pub initial_price: u64,
+    pub price_increment: u64,
+    pub website_url: String,
+    pub timestamp: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct VestingClaimed {
+    pub user: SuiAddress,
+    pub amount: u64,
+    pub timestamp: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct FeeUpdated {
+    pub previous_fee: u64,
+    pub new_fee: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct AdminTransferred {
+    pub previous_admin: SuiAddress,
+    pub new_admin: SuiAddress,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct BalanceUpdate {
+    pub launchpad_id: ObjectID,
+    pub holder: SuiAddress,
+    pub balance: u64,
+    pub timestamp: u64,
+}
+
+// The layouts of WhitelistUpdated and LiquidityMoved are assumed, not taken
+// from the Move package, which isn't in this repository; their fixtures were
+// built from these structs rather than captured from the chain. BCS has no
+// field names, so a different field order or type only shows up as decoding
+// errors or wrong values once real events arrive.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct WhitelistUpdated {
+    pub launchpad_id: ObjectID,
+    pub address: SuiAddress,
+    /// Whether the address is on the whitelist after the update.
+    pub whitelisted: bool,
+    pub timestamp: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct LiquidityMoved {
+    pub launchpad_id: ObjectID,
+    pub recipient: SuiAddress,
+    pub sui_amount: u64,
+    pub timestamp: u64,
+}
+
+/// A Move event struct the indexer knows how to decode and store.
+pub trait ContractEvent: DeserializeOwned + Serialize + Into<LaunchpadEvent> {
+    /// Name of the Move struct, i.e. the `name` of its `StructTag`.
+    const NAME: &'static str;
+    /// Table the event's rows are written to.
+    const TABLE: &'static str;
+
+    /// Launchpad the event belongs to, for the events that carry one.
+    fn launchpad_id(&self) -> Option<ObjectID> {
+        None
+    }
+}
+
+// Declares `LaunchpadEvent` with one variant per registered struct, along
+// with the `ContractEvent` impls and the dispatch every variant shares
+macro_rules! launchpad_events {
+    ($($event:ident => $table:literal $(, launchpad_id: $field:ident)?;)*) => {
+        /// Any event emitted by the launchpad module, decoded.
+        #[derive(Debug, Clone, PartialEq, Eq)]
+        pub enum LaunchpadEvent {
+            $($event($event),)*
+        }
+
+        $(
+            impl ContractEvent for $event {
+                const NAME: &'static str = stringify!($event);
+                const TABLE: &'static str = $table;
+                $(
+                    fn launchpad_id(&self) -> Option<ObjectID> {
+                        Some(self.$field)
+                    }
+                )?
+            }
+
+            impl From<$event> for LaunchpadEvent {
+                fn from(event: $event) -> Self {
+                    LaunchpadEvent::$event(event)
+                }
+            }
+        )*
+
+        impl LaunchpadEvent {
+            /// Name of the Move struct, e.g. `TokensPurchased`.
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $(LaunchpadEvent::$event(_) => $event::NAME,)*
+                }
+            }
+
+            /// Table the event is stored in.
+            pub fn table(&self) -> &'static str {
+                match self {
+                    $(LaunchpadEvent::$event(_) => $event::TABLE,)*
+                }
+            }
+
+            /// Launchpad the event belongs to, for the events that carry
+            /// one, as stored: `0x`-prefixed hex.
+            pub fn launchpad_id(&self) -> Option<String> {
+                let launchpad_id = match self {
+                    $(LaunchpadEvent::$event(e) => e.launchpad_id(),)*
+                };
+                launchpad_id.map(|id| id.to_string())
+            }
+
+            /// The event's fields as a JSON object, in declaration order.
+            pub fn fields(&self) -> serde_json::Result<serde_json::Value> {
+                match self {
+                    $(LaunchpadEvent::$event(e) => serde_json::to_value(e),)*
+                }
+            }
+        }
+
+        /// Table of every event struct, in registration order.
+        pub const TABLES: &[&str] = &[$($table),*];
+
+        /// Name of every event struct, in registration order.
+        pub const NAMES: &[&str] = &[$(stringify!($event)),*];
+
+        /// Every event struct the indexer handles, in registration order.
+        pub(crate) fn register_all(registry: &mut crate::decoding::Registry) {
+            $(registry.register::<$event>();)*
+        }
+    };
+}
+
+launchpad_events! {
+    TokensPurchased => "token_purchases";
+    TokensTransferred => "token_transfers";
+    PriceUpdate => "price_updates";
+    LiquidityDeployed => "liquidity_deployments", launchpad_id: launchpad_id;
+    PoolPaused => "pool_pauses", launchpad_id: launchpad_id;
+    PoolUnpaused => "pool_unpauses", launchpad_id: launchpad_id;
+    LaunchpadCreated => "launchpads", launchpad_id: launchpad_id;
+    VestingClaimed => "vesting_claims";
+    FeeUpdated => "fee_updates";
+    AdminTransferred => "admin_transfers";
+    BalanceUpdate => "balance_updates", launchpad_id: launchpad_id;
+    WhitelistUpdated => "whitelist_updates", launchpad_id: launchpad_id;
+    LiquidityMoved => "liquidity_moves", launchpad_id: launchpad_id;
+}
+
+impl LaunchpadEvent {
+    /// Wallet addresses the event involves, as stored: `0x`-prefixed hex.
+    pub fn wallets(&self) -> Vec<String> {
+        let wallets = match self {
+            LaunchpadEvent::TokensPurchased(e) => vec![e.buyer],
+            LaunchpadEvent::TokensTransferred(e) => vec![e.from, e.to],
+            LaunchpadEvent::LaunchpadCreated(e) => vec![e.creator],
+            LaunchpadEvent::VestingClaimed(e) => vec![e.user],
+            LaunchpadEvent::AdminTransferred(e) => vec![e.previous_admin, e.new_admin],
+            LaunchpadEvent::BalanceUpdate(e) => vec![e.holder],
+            LaunchpadEvent::WhitelistUpdated(e) => vec![e.address],
+            LaunchpadEvent::LiquidityMoved(e) => vec![e.recipient],
+            LaunchpadEvent::PriceUpdate(_)
+            | LaunchpadEvent::LiquidityDeployed(_)
+            | LaunchpadEvent::PoolPaused(_)
+            | LaunchpadEvent::PoolUnpaused(_)
+            | LaunchpadEvent::FeeUpdated(_) => Vec::new(),
+        };
+        wallets.iter().map(SuiAddress::to_string).collect()
+    }
+}
@@ -0,0 +1,322 @@
+//! Live feed of stored events for streaming clients.
+//!
+//! Each event gets an id once stored, increasing across runs: every event
+//! published is also written to `feed_events`, and a restart carries on
+//! after the highest id stored there. The most recent events are kept in
+//! memory for live fan-out; a reconnecting client resumes after the last id
+//! it saw from that buffer or, for ids older than it, from `feed_events`. If
+//! events after that id are missing from both the client is told about the
+//! gap.
+
+use anyhow::{bail, Result};
+use futures::{stream, Stream};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use surrealdb::{engine::any::Any, Surreal};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::{error, warn};
+
+use crate::decoding::EventMetadata;
+use crate::events::{self, LaunchpadEvent};
+
+// Stored events read per query while a client catches up
+const STORED_PAGE: usize = 500;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FeedEvent {
+    pub id: u64,
+    pub event_type: &'static str,
+    pub launchpad_id: Option<String>,
+    pub wallets: Vec<String>,
+    pub metadata: EventMetadata,
+    pub event: Value,
+}
+
+// `FeedEvent` as read back from `feed_events`
+#[derive(Deserialize)]
+struct StoredEvent {
+    id: u64,
+    event_type: String,
+    launchpad_id: Option<String>,
+    wallets: Vec<String>,
+    metadata: EventMetadata,
+    event: Value,
+}
+
+impl TryFrom<StoredEvent> for FeedEvent {
+    type Error = anyhow::Error;
+
+    fn try_from(stored: StoredEvent) -> Result<Self> {
+        let Some(event_type) = events::NAMES
+            .iter()
+            .copied()
+            .find(|n| *n == stored.event_type)
+        else {
+            bail!("unknown event type {}", stored.event_type);
+        };
+        Ok(FeedEvent {
+            id: stored.id,
+            event_type,
+            launchpad_id: stored.launchpad_id,
+            wallets: stored.wallets,
+            metadata: stored.metadata,
+            event: stored.event,
+        })
+    }
+}
+
+/// What a client wants to see. Missing filters match everything.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct FeedFilter {
+    pub launchpad_id: Option<String>,
+    pub wallet: Option<String>,
+}
+
+impl FeedFilter {
+    pub fn matches(&self, event: &FeedEvent) -> bool {
+        self.launchpad_id
+            .as_deref()
+            .is_none_or(|id| event.launchpad_id.as_deref() == Some(id))
+            && self
+                .wallet
+                .as_deref()
+                .is_none_or(|wallet| event.wallets.iter().any(|w| w == wallet))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FeedItem {
+    Event(Arc<FeedEvent>),
+    // Events after `after` are no longer available to the client
+    Gap { after: u64 },
+}
+
+struct Buffer {
+    next_id: u64,
+    events: VecDeque<Arc<FeedEvent>>,
+}
+
+impl Buffer {
+    // Lowest id still buffered, or the next one while nothing is
+    fn first_id(&self) -> u64 {
+        self.events.front().map_or(self.next_id, |first| first.id)
+    }
+
+    // Buffered events after `after`
+    fn since(&self, after: u64) -> Vec<Arc<FeedEvent>> {
+        self.events
+            .iter()
+            .filter(|e| e.id > after)
+            .cloned()
+            .collect()
+    }
+}
+
+#[derive(Clone)]
+pub struct Feed {
+    db: Surreal<Any>,
+    buffer: Arc<Mutex<Buffer>>,
+    sender: broadcast::Sender<Arc<FeedEvent>>,
+    capacity: usize,
+}
+
+impl Feed {
+    /// Feed numbering on from the events already stored in `db`.
+    pub async fn new(db: Surreal<Any>, capacity: usize) -> Result<Self> {
+        let capacity = capacity.max(1);
+        let last: Option<u64> = db
+            .query("SELECT VALUE seq FROM feed_events ORDER BY seq DESC LIMIT 1")
+            .await?
+            .take(0)?;
+        let (sender, _) = broadcast::channel(capacity);
+        Ok(Self {
+            db,
+            buffer: Arc::new(Mutex::new(Buffer {
+                next_id: last.map_or(1, |last| last + 1),
+                events: VecDeque::with_capacity(capacity),
+            })),
+            sender,
+            capacity,
+        })
+    }
+
+    pub(crate) async fn publish(&self, event: &LaunchpadEvent, metadata: &EventMetadata) {
+        let fields = match event.fields() {
+            Ok(fields) => fields,
+            Err(_) => return,
+        };
+        // Ids are handed out and broadcast under the lock, so a subscriber
+        // reading the buffer can't miss or reorder anything
+        let published = {
+            let mut buffer = self.buffer.lock().unwrap();
+            let published = Arc::new(FeedEvent {
+                id: buffer.next_id,
+                event_type: event.name(),
+                // Resolved through the transaction for events that don't name it
+                launchpad_id: metadata.launchpad_id.clone(),
+                wallets: event.wallets(),
+                metadata: metadata.clone(),
+                event: fields,
+            });
+            buffer.next_id += 1;
+            if buffer.events.len() == self.capacity {
+                buffer.events.pop_front();
+            }
+            buffer.events.push_back(published.clone());
+            // No receivers just means nobody is listening
+            let _ = self.sender.send(published.clone());
+            published
+        };
+        // A missing row only shows up as a gap to clients resuming from it
+        if let Err(e) = self.store(&published).await {
+            error!(id = published.id, error = %e, "Failed to store feed event");
+        }
+    }
+
+    async fn store(&self, event: &FeedEvent) -> Result<()> {
+        self.db
+            .query("UPSERT type::thing('feed_events', $seq) SET seq = $seq, payload = $payload")
+            .bind(("seq", event.id))
+            .bind(("payload", serde_json::to_string(event)?))
+            .await?
+            .check()?;
+        Ok(())
+    }
+
+    // Stored events after `after` and before `before`, oldest first
+    async fn stored(&self, after: u64, before: u64) -> Result<Vec<Arc<FeedEvent>>> {
+        let payloads: Vec<String> = self
+            .db
+            .query(
+                "SELECT VALUE payload FROM feed_events WHERE seq > $after AND seq < $before \
+                 ORDER BY seq LIMIT $limit",
+            )
+            .bind(("after", after))
+            .bind(("before", before))
+            .bind(("limit", STORED_PAGE))
+            .await?
+            .take(0)?;
+        payloads
+            .iter()
+            .map(|payload| {
+                let stored: StoredEvent = serde_json::from_str(payload)?;
+                Ok(Arc::new(stored.try_into()?))
+            })
+            .collect()
+    }
+
+    /// Events matching `filter` as they are stored, starting with the ones
+    /// after `after` when resuming.
+    pub fn subscribe(
+        &self,
+        filter: FeedFilter,
+        after: Option<u64>,
+    ) -> impl Stream<Item = FeedItem> + Send + 'static {
+        let buffer = self.buffer.lock().unwrap();
+        let receiver = self.sender.subscribe();
+        let mut cursor = Cursor {
+            feed: self.clone(),
+            receiver,
+            filter,
+            pending: VecDeque::new(),
+            last_id: buffer.next_id - 1,
+            catching_up: false,
+        };
+        if let Some(after) = after {
+            if after < buffer.next_id {
+                cursor.last_id = after;
+                cursor.catching_up = true;
+            } else {
+                // Not handed out yet, e.g. from a database since reset
+                cursor.pending.push_back(FeedItem::Gap { after });
+            }
+        }
+        drop(buffer);
+
+        stream::unfold(cursor, |mut cursor| async move {
+            let item = cursor.next().await?;
+            Some((item, cursor))
+        })
+    }
+}
+
+struct Cursor {
+    feed: Feed,
+    receiver: broadcast::Receiver<Arc<FeedEvent>>,
+    filter: FeedFilter,
+    pending: VecDeque<FeedItem>,
+    last_id: u64,
+    // Events after `last_id` have to be read from the buffer or the
+    // database before following the channel
+    catching_up: bool,
+}
+
+impl Cursor {
+    fn queue(&mut self, events: Vec<Arc<FeedEvent>>) {
+        for event in events {
+            self.last_id = self.last_id.max(event.id);
+            if self.filter.matches(&event) {
+                self.pending.push_back(FeedItem::Event(event));
+            }
+        }
+    }
+
+    fn gap(&mut self) {
+        self.pending.push_back(FeedItem::Gap {
+            after: self.last_id,
+        });
+    }
+
+    // Queue the next events after `last_id`: stored ones while it is older
+    // than the buffer, then the buffered ones
+    async fn catch_up(&mut self) {
+        let first_buffered = self.feed.buffer.lock().unwrap().first_id();
+        if self.last_id.saturating_add(1) >= first_buffered {
+            let events = self.feed.buffer.lock().unwrap().since(self.last_id);
+            self.queue(events);
+            self.catching_up = false;
+            return;
+        }
+
+        match self.feed.stored(self.last_id, first_buffered).await {
+            Ok(events) if !events.is_empty() => {
+                if events[0].id > self.last_id + 1 {
+                    self.gap();
+                }
+                self.queue(events);
+            }
+            // Nothing left between `last_id` and the buffer
+            result => {
+                if let Err(e) = result {
+                    warn!(after = self.last_id, error = %e, "Failed to read stored feed events");
+                }
+                self.gap();
+                self.last_id = first_buffered - 1;
+            }
+        }
+    }
+
+    async fn next(&mut self) -> Option<FeedItem> {
+        loop {
+            if let Some(item) = self.pending.pop_front() {
+                return Some(item);
+            }
+            if self.catching_up {
+                self.catch_up().await;
+                continue;
+            }
+            match self.receiver.recv().await {
+                Ok(event) if event.id > self.last_id => {
+                    self.queue(vec![event]);
+                }
+                Ok(_) => {}
+                // Too slow to keep up with the channel: catch up from the
+                // buffer, which holds as many events as the channel
+                Err(RecvError::Lagged(_)) => self.catching_up = true,
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    }
+}
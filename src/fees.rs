@@ -0,0 +1,174 @@
+//! Creation fee schedule and the revenue it brought in.
+//!
+//! The schedule is rebuilt from `fee_updates`: each FeeUpdated takes effect
+//! at its timestamp, and the fee before the first one is that update's
+//! `previous_fee`. With no updates indexed the fee is `CREATION_FEE`, the
+//! contract's 0.1 SUI by default. Revenue charges every launchpad the fee in
+//! effect when it was created. Updates stored before rows carried a
+//! timestamp can't be placed in time and are left out.
+
+use anyhow::Result;
+use chrono::{DateTime, Datelike, Duration, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use surrealdb::{engine::any::Any, Surreal};
+use utoipa::ToSchema;
+
+use crate::env_or;
+
+/// Creation fee of a freshly published package, in MIST.
+pub const DEFAULT_CREATION_FEE: u64 = 100_000_000;
+
+pub fn initial_fee() -> u64 {
+    env_or("CREATION_FEE", DEFAULT_CREATION_FEE)
+}
+
+/// A creation fee and when it applied.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct FeePeriod {
+    /// In MIST.
+    pub fee: u64,
+    /// When the fee took effect, in milliseconds; none for the initial fee.
+    pub effective_from: Option<u64>,
+    /// When the next fee took effect, if it has been replaced.
+    pub effective_until: Option<u64>,
+    /// Transaction of the FeeUpdated that set it.
+    pub tx_digest: Option<String>,
+}
+
+/// Creation fees collected over one period.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct Revenue {
+    /// Start of the period, in milliseconds.
+    pub start: u64,
+    pub launchpads: u64,
+    /// In MIST.
+    pub fees: u64,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Period {
+    Day,
+    /// Weeks start on Monday.
+    Week,
+    #[default]
+    Month,
+}
+
+impl Period {
+    // Start of the UTC period containing `timestamp`
+    fn start(self, timestamp: u64) -> u64 {
+        let date = DateTime::from_timestamp_millis(timestamp as i64)
+            .unwrap_or_default()
+            .date_naive();
+        let start = match self {
+            Period::Day => date,
+            Period::Week => date - Duration::days(date.weekday().num_days_from_monday().into()),
+            Period::Month => NaiveDate::from_ymd_opt(date.year(), date.month(), 1).unwrap_or(date),
+        };
+        start
+            .and_hms_opt(0, 0, 0)
+            .unwrap_or_default()
+            .and_utc()
+            .timestamp_millis() as u64
+    }
+}
+
+#[derive(Deserialize)]
+struct FeeChange {
+    previous_fee: u64,
+    new_fee: u64,
+    timestamp: u64,
+    tx_digest: String,
+}
+
+/// The creation fees in effect over time, oldest first.
+#[derive(Debug, Clone)]
+pub struct FeeSchedule {
+    periods: Vec<FeePeriod>,
+}
+
+impl FeeSchedule {
+    pub async fn load(db: &Surreal<Any>, initial_fee: u64) -> Result<Self> {
+        let changes: Vec<FeeChange> = db
+            .query(
+                "SELECT previous_fee, new_fee, timestamp, tx_digest FROM fee_updates \
+                 WHERE timestamp != NONE ORDER BY timestamp ASC",
+            )
+            .await?
+            .take(0)?;
+
+        let mut periods = vec![FeePeriod {
+            fee: changes.first().map_or(initial_fee, |c| c.previous_fee),
+            effective_from: None,
+            effective_until: None,
+            tx_digest: None,
+        }];
+        for change in changes {
+            if let Some(last) = periods.last_mut() {
+                last.effective_until = Some(change.timestamp);
+            }
+            periods.push(FeePeriod {
+                fee: change.new_fee,
+                effective_from: Some(change.timestamp),
+                effective_until: None,
+                tx_digest: Some(change.tx_digest),
+            });
+        }
+        Ok(Self { periods })
+    }
+
+    /// Fee in effect at `timestamp`. An update applies from its own timestamp.
+    pub fn fee_at(&self, timestamp: u64) -> u64 {
+        self.periods
+            .iter()
+            .rev()
+            .find(|p| p.effective_from.is_none_or(|from| from <= timestamp))
+            .map_or(0, |p| p.fee)
+    }
+
+    pub fn periods(&self) -> &[FeePeriod] {
+        &self.periods
+    }
+}
+
+/// Creation fees per `period` for launchpads created in `[since, until)`,
+/// oldest period first. Periods without launchpads are left out.
+pub async fn revenue(
+    db: &Surreal<Any>,
+    schedule: &FeeSchedule,
+    period: Period,
+    since: Option<u64>,
+    until: Option<u64>,
+) -> Result<Vec<Revenue>> {
+    let mut conditions = vec!["true"];
+    if since.is_some() {
+        conditions.push("timestamp >= $since");
+    }
+    if until.is_some() {
+        conditions.push("timestamp < $until");
+    }
+    let created: Vec<u64> = db
+        .query(format!(
+            "SELECT VALUE timestamp FROM launchpads WHERE {}",
+            conditions.join(" AND ")
+        ))
+        .bind(("since", since))
+        .bind(("until", until))
+        .await?
+        .take(0)?;
+
+    let mut periods: BTreeMap<u64, Revenue> = BTreeMap::new();
+    for timestamp in created {
+        let start = period.start(timestamp);
+        let revenue = periods.entry(start).or_insert(Revenue {
+            start,
+            launchpads: 0,
+            fees: 0,
+        });
+        revenue.launchpads += 1;
+        revenue.fees = revenue.fees.saturating_add(schedule.fee_at(timestamp));
+    }
+    Ok(periods.into_values().collect())
+}
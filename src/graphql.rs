@@ -0,0 +1,622 @@
+//! GraphQL API over the indexed tables.
+//!
+//! Only `LaunchpadCreated` and `BalanceUpdate` rows name their launchpad, so
+//! purchases, transfers, price updates and vesting claims are tied to a
+//! launchpad through a `BalanceUpdate` emitted in the same transaction.
+//! Holders are the latest `BalanceUpdate` per wallet and launchpad.
+//!
+//! Lists are Relay-style connections paged with `first`/`after`, newest
+//! first, or largest balance first for holders.
+//!
+//! Queries deeper than `GRAPHQL_MAX_DEPTH` or costlier than
+//! `GRAPHQL_MAX_COMPLEXITY` are rejected before they run; a list counts as
+//! its page size times the cost of its items. The launchpad of each row in a
+//! page is looked up in one batch per page rather than once per row.
+
+use async_graphql::connection::{self, Connection, Edge, OpaqueCursor};
+use async_graphql::dataloader::{DataLoader, Loader};
+use async_graphql::{ComplexObject, Context, EmptyMutation, EmptySubscription, Object, Schema};
+use async_graphql::{OutputType, Result, SimpleObject};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use surrealdb::{engine::any::Any, Surreal};
+
+use crate::env_or;
+use crate::storage::{self, Balance, LAUNCHPAD_TXS};
+
+pub type LaunchpadSchema = Schema<Query, EmptyMutation, EmptySubscription>;
+
+pub fn schema(db: Surreal<Any>) -> LaunchpadSchema {
+    Schema::build(Query, EmptyMutation, EmptySubscription)
+        .data(DataLoader::new(Launchpads(db.clone()), tokio::spawn))
+        .data(DataLoader::new(TxLaunchpads(db.clone()), tokio::spawn))
+        .data(db)
+        .limit_depth(env_or("GRAPHQL_MAX_DEPTH", 10))
+        .limit_complexity(env_or("GRAPHQL_MAX_COMPLEXITY", 2000))
+        .finish()
+}
+
+const DEFAULT_PAGE_SIZE: usize = 20;
+const MAX_PAGE_SIZE: usize = 100;
+
+// Cost of a page of up to `first` items costing `child` each
+fn page_complexity(child: usize, first: Option<i32>) -> usize {
+    let size = first.map_or(DEFAULT_PAGE_SIZE, |n| n.max(0) as usize);
+    size.min(MAX_PAGE_SIZE).max(1) * child
+}
+
+// Position in a list: the sort value, then the row key to break ties
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PageKey {
+    order: u64,
+    key: String,
+}
+
+type Page<T> = Connection<OpaqueCursor<PageKey>, T>;
+
+trait Paged {
+    fn page_key(&self) -> PageKey;
+}
+
+// A `SELECT` over one of the event tables, built from optional filters
+struct Select {
+    table: &'static str,
+    conditions: Vec<&'static str>,
+    binds: Vec<(&'static str, Value)>,
+}
+
+impl Select {
+    fn from(table: &'static str) -> Self {
+        Self {
+            table,
+            conditions: Vec::new(),
+            binds: Vec::new(),
+        }
+    }
+
+    fn filter(
+        mut self,
+        condition: &'static str,
+        name: &'static str,
+        value: Option<impl Into<Value>>,
+    ) -> Self {
+        if let Some(value) = value {
+            self.conditions.push(condition);
+            self.binds.push((name, value.into()));
+        }
+        self
+    }
+
+    fn launchpad(self, launchpad_id: Option<String>) -> Self {
+        self.filter(LAUNCHPAD_TXS, "launchpad_id", launchpad_id)
+    }
+
+    fn period(self, since: Option<u64>, until: Option<u64>) -> Self {
+        self.filter("timestamp >= $since", "since", since).filter(
+            "timestamp < $until",
+            "until",
+            until,
+        )
+    }
+
+    // One page, newest first
+    async fn page<T>(
+        mut self,
+        db: &Surreal<Any>,
+        after: Option<String>,
+        first: Option<i32>,
+    ) -> Result<Page<T>>
+    where
+        T: DeserializeOwned + OutputType + Paged,
+    {
+        connection::query(after, None, first, None, |after, _, first, _| async move {
+            let resumed = after.is_some();
+            if let Some(OpaqueCursor(after)) = after {
+                self.conditions.push(
+                    "(timestamp < $after_order \
+                     OR (timestamp = $after_order AND <string> meta::id(id) < $after_key))",
+                );
+                self.binds.push(("after_order", after.order.into()));
+                self.binds.push(("after_key", after.key.into()));
+            }
+            let limit = first.unwrap_or(DEFAULT_PAGE_SIZE).min(MAX_PAGE_SIZE);
+            let condition = if self.conditions.is_empty() {
+                String::new()
+            } else {
+                format!("WHERE {}", self.conditions.join(" AND "))
+            };
+            let mut query = db.query(format!(
+                "SELECT *, <string> meta::id(id) AS key OMIT id FROM {} {} \
+                 ORDER BY timestamp DESC, key DESC LIMIT {}",
+                self.table,
+                condition,
+                limit + 1
+            ));
+            for bind in self.binds {
+                query = query.bind(bind);
+            }
+            let rows: Vec<T> = query.await?.take(0)?;
+            Ok::<_, async_graphql::Error>(page(rows, limit, resumed))
+        })
+        .await
+    }
+}
+
+// Turn `limit + 1` fetched rows into a page of `limit`
+fn page<T: OutputType + Paged>(mut rows: Vec<T>, limit: usize, resumed: bool) -> Page<T> {
+    let has_next = rows.len() > limit;
+    rows.truncate(limit);
+    let mut page = Connection::new(resumed, has_next);
+    page.edges.extend(
+        rows.into_iter()
+            .map(|row| Edge::new(OpaqueCursor(row.page_key()), row)),
+    );
+    page
+}
+
+fn db<'a>(ctx: &Context<'a>) -> &'a Surreal<Any> {
+    ctx.data_unchecked::<Surreal<Any>>()
+}
+
+// Launchpads by id, batched across the rows of a response
+struct Launchpads(Surreal<Any>);
+
+impl Loader<String> for Launchpads {
+    type Value = Launchpad;
+    type Error = async_graphql::Error;
+
+    async fn load(&self, ids: &[String]) -> Result<HashMap<String, Launchpad>> {
+        let launchpads: Vec<Launchpad> = self
+            .0
+            .query(
+                "SELECT *, <string> meta::id(id) AS key OMIT id FROM launchpads \
+                 WHERE launchpad_id INSIDE $ids",
+            )
+            .bind(("ids", ids.to_vec()))
+            .await?
+            .take(0)?;
+        Ok(launchpads
+            .into_iter()
+            .map(|launchpad| (launchpad.launchpad_id.clone(), launchpad))
+            .collect())
+    }
+}
+
+// Launchpad id each transaction traded, via its balance updates
+struct TxLaunchpads(Surreal<Any>);
+
+impl Loader<String> for TxLaunchpads {
+    type Value = String;
+    type Error = async_graphql::Error;
+
+    async fn load(&self, tx_digests: &[String]) -> Result<HashMap<String, String>> {
+        Ok(storage::tx_launchpads(&self.0, tx_digests.to_vec()).await?)
+    }
+}
+
+async fn launchpad(ctx: &Context<'_>, launchpad_id: String) -> Result<Option<Launchpad>> {
+    ctx.data_unchecked::<DataLoader<Launchpads>>()
+        .load_one(launchpad_id)
+        .await
+}
+
+// Launchpad a transaction traded
+async fn launchpad_of(ctx: &Context<'_>, tx_digest: &str) -> Result<Option<Launchpad>> {
+    let launchpad_id = ctx
+        .data_unchecked::<DataLoader<TxLaunchpads>>()
+        .load_one(tx_digest.to_string())
+        .await?;
+    match launchpad_id {
+        Some(launchpad_id) => launchpad(ctx, launchpad_id).await,
+        None => Ok(None),
+    }
+}
+
+async fn holder_page(
+    db: &Surreal<Any>,
+    launchpad_id: &str,
+    min_balance: Option<u64>,
+    after: Option<String>,
+    first: Option<i32>,
+) -> Result<Page<Holder>> {
+    connection::query(after, None, first, None, |after, _, first, _| async move {
+        let resumed = after.is_some();
+        let after = after.map(|OpaqueCursor(after)| (after.order, after.key));
+        let limit = first.unwrap_or(DEFAULT_PAGE_SIZE).min(MAX_PAGE_SIZE);
+        let holders =
+            storage::holder_balances(db, launchpad_id, min_balance.unwrap_or(0), after, limit + 1)
+                .await?
+                .into_iter()
+                .map(Holder::from)
+                .collect();
+        Ok::<_, async_graphql::Error>(page(holders, limit, resumed))
+    })
+    .await
+}
+
+#[derive(Debug, Clone, SimpleObject, Deserialize)]
+#[graphql(complex)]
+pub struct Launchpad {
+    #[graphql(skip)]
+    key: String,
+    pub launchpad_id: String,
+    pub creator: String,
+    pub name: String,
+    pub description: String,
+    pub token_supply: u64,
+    pub initial_price: u64,
+    pub price_increment: u64,
+    pub website_url: String,
+    pub timestamp: u64,
+    pub tx_digest: String,
+}
+
+#[ComplexObject]
+impl Launchpad {
+    #[graphql(complexity = "page_complexity(child_complexity, first)")]
+    async fn purchases(
+        &self,
+        ctx: &Context<'_>,
+        buyer: Option<String>,
+        since: Option<u64>,
+        until: Option<u64>,
+        after: Option<String>,
+        first: Option<i32>,
+    ) -> Result<Page<Purchase>> {
+        Select::from("token_purchases")
+            .launchpad(Some(self.launchpad_id.clone()))
+            .filter("buyer = $buyer", "buyer", buyer)
+            .period(since, until)
+            .page(db(ctx), after, first)
+            .await
+    }
+
+    #[graphql(complexity = "page_complexity(child_complexity, first)")]
+    async fn transfers(
+        &self,
+        ctx: &Context<'_>,
+        after: Option<String>,
+        first: Option<i32>,
+    ) -> Result<Page<Transfer>> {
+        Select::from("token_transfers")
+            .launchpad(Some(self.launchpad_id.clone()))
+            .page(db(ctx), after, first)
+            .await
+    }
+
+    /// Price changes, newest first.
+    #[graphql(complexity = "page_complexity(child_complexity, first)")]
+    async fn price_history(
+        &self,
+        ctx: &Context<'_>,
+        since: Option<u64>,
+        until: Option<u64>,
+        after: Option<String>,
+        first: Option<i32>,
+    ) -> Result<Page<PriceUpdate>> {
+        Select::from("price_updates")
+            .launchpad(Some(self.launchpad_id.clone()))
+            .period(since, until)
+            .page(db(ctx), after, first)
+            .await
+    }
+
+    /// Holders by balance, largest first.
+    #[graphql(complexity = "page_complexity(child_complexity, first)")]
+    async fn holders(
+        &self,
+        ctx: &Context<'_>,
+        min_balance: Option<u64>,
+        after: Option<String>,
+        first: Option<i32>,
+    ) -> Result<Page<Holder>> {
+        holder_page(db(ctx), &self.launchpad_id, min_balance, after, first).await
+    }
+
+    #[graphql(complexity = "page_complexity(child_complexity, first)")]
+    async fn vesting_claims(
+        &self,
+        ctx: &Context<'_>,
+        after: Option<String>,
+        first: Option<i32>,
+    ) -> Result<Page<VestingClaim>> {
+        Select::from("vesting_claims")
+            .launchpad(Some(self.launchpad_id.clone()))
+            .page(db(ctx), after, first)
+            .await
+    }
+}
+
+impl Paged for Launchpad {
+    fn page_key(&self) -> PageKey {
+        PageKey {
+            order: self.timestamp,
+            key: self.key.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, SimpleObject, Deserialize)]
+#[graphql(complex)]
+pub struct Purchase {
+    #[graphql(skip)]
+    key: String,
+    pub buyer: String,
+    pub amount: u64,
+    pub timestamp: u64,
+    pub tx_digest: String,
+}
+
+#[ComplexObject]
+impl Purchase {
+    async fn launchpad(&self, ctx: &Context<'_>) -> Result<Option<Launchpad>> {
+        launchpad_of(ctx, &self.tx_digest).await
+    }
+
+    /// The buyer's current position in the launchpad bought from.
+    async fn holder(&self, ctx: &Context<'_>) -> Result<Option<Holder>> {
+        match self.launchpad(ctx).await? {
+            Some(launchpad) => {
+                let balance =
+                    storage::balance(db(ctx), &launchpad.launchpad_id, &self.buyer).await?;
+                Ok(balance.map(Holder::from))
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+#[derive(Debug, Clone, SimpleObject, Deserialize)]
+#[graphql(complex)]
+pub struct Transfer {
+    #[graphql(skip)]
+    key: String,
+    pub from: String,
+    pub to: String,
+    pub amount: u64,
+    pub timestamp: u64,
+    pub tx_digest: String,
+}
+
+#[ComplexObject]
+impl Transfer {
+    async fn launchpad(&self, ctx: &Context<'_>) -> Result<Option<Launchpad>> {
+        launchpad_of(ctx, &self.tx_digest).await
+    }
+}
+
+#[derive(Debug, Clone, SimpleObject, Deserialize)]
+#[graphql(complex)]
+pub struct PriceUpdate {
+    #[graphql(skip)]
+    key: String,
+    pub new_price: u64,
+    pub tokens_sold: u64,
+    pub timestamp: u64,
+    pub tx_digest: String,
+}
+
+#[ComplexObject]
+impl PriceUpdate {
+    async fn launchpad(&self, ctx: &Context<'_>) -> Result<Option<Launchpad>> {
+        launchpad_of(ctx, &self.tx_digest).await
+    }
+}
+
+#[derive(Debug, Clone, SimpleObject, Deserialize)]
+#[graphql(complex)]
+pub struct VestingClaim {
+    #[graphql(skip)]
+    key: String,
+    pub user: String,
+    pub amount: u64,
+    pub timestamp: u64,
+    pub tx_digest: String,
+}
+
+#[ComplexObject]
+impl VestingClaim {
+    async fn launchpad(&self, ctx: &Context<'_>) -> Result<Option<Launchpad>> {
+        launchpad_of(ctx, &self.tx_digest).await
+    }
+}
+
+/// A wallet's position in one launchpad.
+#[derive(Debug, Clone, SimpleObject)]
+#[graphql(complex)]
+pub struct Holder {
+    pub launchpad_id: String,
+    pub address: String,
+    pub balance: u64,
+    pub updated_at: u64,
+}
+
+#[ComplexObject]
+impl Holder {
+    async fn launchpad(&self, ctx: &Context<'_>) -> Result<Option<Launchpad>> {
+        launchpad(ctx, self.launchpad_id.clone()).await
+    }
+
+    #[graphql(complexity = "page_complexity(child_complexity, first)")]
+    async fn purchases(
+        &self,
+        ctx: &Context<'_>,
+        after: Option<String>,
+        first: Option<i32>,
+    ) -> Result<Page<Purchase>> {
+        Select::from("token_purchases")
+            .launchpad(Some(self.launchpad_id.clone()))
+            .filter("buyer = $buyer", "buyer", Some(self.address.clone()))
+            .page(db(ctx), after, first)
+            .await
+    }
+
+    #[graphql(complexity = "page_complexity(child_complexity, first)")]
+    async fn vesting_claims(
+        &self,
+        ctx: &Context<'_>,
+        after: Option<String>,
+        first: Option<i32>,
+    ) -> Result<Page<VestingClaim>> {
+        Select::from("vesting_claims")
+            .launchpad(Some(self.launchpad_id.clone()))
+            .filter("user = $user", "user", Some(self.address.clone()))
+            .page(db(ctx), after, first)
+            .await
+    }
+}
+
+impl From<Balance> for Holder {
+    fn from(balance: Balance) -> Self {
+        Self {
+            launchpad_id: balance.launchpad_id,
+            address: balance.holder,
+            balance: balance.balance,
+            updated_at: balance.updated_at,
+        }
+    }
+}
+
+impl Paged for Holder {
+    fn page_key(&self) -> PageKey {
+        PageKey {
+            order: self.balance,
+            key: self.address.clone(),
+        }
+    }
+}
+
+macro_rules! paged_by_timestamp {
+    ($($row:ty),*) => {
+        $(impl Paged for $row {
+            fn page_key(&self) -> PageKey {
+                PageKey {
+                    order: self.timestamp,
+                    key: self.key.clone(),
+                }
+            }
+        })*
+    };
+}
+
+paged_by_timestamp!(Purchase, Transfer, PriceUpdate, VestingClaim);
+
+pub struct Query;
+
+#[Object]
+impl Query {
+    async fn launchpad(&self, ctx: &Context<'_>, id: String) -> Result<Option<Launchpad>> {
+        launchpad(ctx, id).await
+    }
+
+    #[graphql(complexity = "page_complexity(child_complexity, first)")]
+    async fn launchpads(
+        &self,
+        ctx: &Context<'_>,
+        creator: Option<String>,
+        since: Option<u64>,
+        until: Option<u64>,
+        after: Option<String>,
+        first: Option<i32>,
+    ) -> Result<Page<Launchpad>> {
+        Select::from("launchpads")
+            .filter("creator = $creator", "creator", creator)
+            .period(since, until)
+            .page(db(ctx), after, first)
+            .await
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    #[graphql(complexity = "page_complexity(child_complexity, first)")]
+    async fn purchases(
+        &self,
+        ctx: &Context<'_>,
+        buyer: Option<String>,
+        launchpad_id: Option<String>,
+        since: Option<u64>,
+        until: Option<u64>,
+        after: Option<String>,
+        first: Option<i32>,
+    ) -> Result<Page<Purchase>> {
+        Select::from("token_purchases")
+            .filter("buyer = $buyer", "buyer", buyer)
+            .launchpad(launchpad_id)
+            .period(since, until)
+            .page(db(ctx), after, first)
+            .await
+    }
+
+    /// Transfers from or to `wallet`, if given.
+    #[graphql(complexity = "page_complexity(child_complexity, first)")]
+    async fn transfers(
+        &self,
+        ctx: &Context<'_>,
+        wallet: Option<String>,
+        launchpad_id: Option<String>,
+        after: Option<String>,
+        first: Option<i32>,
+    ) -> Result<Page<Transfer>> {
+        Select::from("token_transfers")
+            .filter("(`from` = $wallet OR `to` = $wallet)", "wallet", wallet)
+            .launchpad(launchpad_id)
+            .page(db(ctx), after, first)
+            .await
+    }
+
+    #[graphql(complexity = "page_complexity(child_complexity, first)")]
+    async fn price_updates(
+        &self,
+        ctx: &Context<'_>,
+        launchpad_id: Option<String>,
+        since: Option<u64>,
+        until: Option<u64>,
+        after: Option<String>,
+        first: Option<i32>,
+    ) -> Result<Page<PriceUpdate>> {
+        Select::from("price_updates")
+            .launchpad(launchpad_id)
+            .period(since, until)
+            .page(db(ctx), after, first)
+            .await
+    }
+
+    #[graphql(complexity = "page_complexity(child_complexity, first)")]
+    async fn holders(
+        &self,
+        ctx: &Context<'_>,
+        launchpad_id: String,
+        min_balance: Option<u64>,
+        after: Option<String>,
+        first: Option<i32>,
+    ) -> Result<Page<Holder>> {
+        holder_page(db(ctx), &launchpad_id, min_balance, after, first).await
+    }
+
+    async fn holder(
+        &self,
+        ctx: &Context<'_>,
+        launchpad_id: String,
+        address: String,
+    ) -> Result<Option<Holder>> {
+        let balance = storage::balance(db(ctx), &launchpad_id, &address).await?;
+        Ok(balance.map(Holder::from))
+    }
+
+    #[graphql(complexity = "page_complexity(child_complexity, first)")]
+    async fn vesting_claims(
+        &self,
+        ctx: &Context<'_>,
+        user: Option<String>,
+        launchpad_id: Option<String>,
+        after: Option<String>,
+        first: Option<i32>,
+    ) -> Result<Page<VestingClaim>> {
+        Select::from("vesting_claims")
+            .filter("user = $user", "user", user)
+            .launchpad(launchpad_id)
+            .page(db(ctx), after, first)
+            .await
+    }
+}
@@ -0,0 +1,58 @@
+//! Hooks for custom per-event processing.
+//!
+//! Handlers registered with [`Indexer::with_handler`](crate::Indexer::with_handler)
+//! see every event once it has been written to the database. A handler that
+//! fails, panics or times out is logged and counted, and never holds up
+//! ingestion or the other handlers.
+
+use anyhow::Result;
+use async_trait::async_trait;
+use futures::future::join_all;
+use futures::FutureExt;
+use std::panic::AssertUnwindSafe;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::error;
+
+use crate::decoding::EventMetadata;
+use crate::events::LaunchpadEvent;
+use crate::metrics::Metrics;
+
+/// Downstream processing of indexed events, e.g. alerts or analytics.
+#[async_trait]
+pub trait EventHandler: Send + Sync {
+    /// Name used in logs and the `handler_failures_total` metric.
+    fn name(&self) -> &str;
+
+    /// Called after `event` has been stored.
+    async fn handle(&self, event: &LaunchpadEvent, metadata: &EventMetadata) -> Result<()>;
+}
+
+// Run every handler on `event` concurrently, so a slow one only delays the
+// event by its own timeout rather than adding to the others'
+pub(crate) async fn dispatch(
+    handlers: &[Arc<dyn EventHandler>],
+    event: &LaunchpadEvent,
+    metadata: &EventMetadata,
+    timeout: Duration,
+    metrics: &Metrics,
+) {
+    join_all(handlers.iter().map(|handler| async move {
+        let handled = AssertUnwindSafe(handler.handle(event, metadata)).catch_unwind();
+        let failure = match tokio::time::timeout(timeout, handled).await {
+            Ok(Ok(Ok(()))) => return,
+            Ok(Ok(Err(e))) => e.to_string(),
+            Ok(Err(_)) => "handler panicked".to_string(),
+            Err(_) => format!("handler timed out after {:?}", timeout),
+        };
+        error!(
+            handler = handler.name(),
+            event_type = event.name(),
+            tx_digest = %metadata.tx_digest,
+            error = %failure,
+            "Event handler failed"
+        );
+        metrics.record_handler_failure(handler.name());
+    }))
+    .await;
+}
@@ -0,0 +1,134 @@
+use chrono::Utc;
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
+use std::sync::OnceLock;
+use std::time::Duration;
+use sui_sdk::SuiClient;
+use tokio::time::timeout;
+use utoipa::ToSchema;
+
+use crate::metrics::IngestMode;
+use crate::{env_or, Indexer};
+
+// Which checks /readyz runs and how strict they are
+#[derive(Debug, Clone)]
+pub struct ReadinessConfig {
+    pub check_db: bool,
+    pub check_rpc: bool,
+    pub max_event_age: Duration,
+    // Checkpoints the indexer may trail the node by while subscribed
+    pub max_checkpoint_lag: u64,
+    pub check_timeout: Duration,
+}
+
+impl ReadinessConfig {
+    pub fn from_env() -> Self {
+        Self {
+            check_db: env_or("READY_CHECK_DB", true),
+            check_rpc: env_or("READY_CHECK_RPC", true),
+            max_event_age: Duration::from_secs(env_or("READY_MAX_EVENT_AGE_SECS", 300)),
+            max_checkpoint_lag: env_or("READY_MAX_CHECKPOINT_LAG", 100),
+            check_timeout: Duration::from_millis(env_or("READY_CHECK_TIMEOUT_MS", 2000)),
+        }
+    }
+}
+
+// Progress signals reported by the pipeline and read by /readyz
+#[derive(Default)]
+pub struct Health {
+    last_event_ms: AtomicI64,
+    caught_up: AtomicBool,
+    sui_client: OnceLock<SuiClient>,
+}
+
+impl Health {
+    pub fn record_event(&self) {
+        self.last_event_ms
+            .store(Utc::now().timestamp_millis(), Ordering::Relaxed);
+    }
+
+    // True while the fetcher has nothing left to read: the WebSocket
+    // subscription is live, or the last poll returned the final page
+    pub fn set_caught_up(&self, caught_up: bool) {
+        self.caught_up.store(caught_up, Ordering::Relaxed);
+    }
+
+    pub fn caught_up(&self) -> bool {
+        self.caught_up.load(Ordering::Relaxed)
+    }
+
+    pub fn set_sui_client(&self, client: SuiClient) {
+        let _ = self.sui_client.set(client);
+    }
+
+    fn since_last_event(&self) -> Option<Duration> {
+        match self.last_event_ms.load(Ordering::Relaxed) {
+            0 => None,
+            last => {
+                let elapsed = Utc::now().timestamp_millis() - last;
+                Some(Duration::from_millis(elapsed.max(0) as u64))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct Readiness {
+    pub ready: bool,
+    // `None` when the check is disabled
+    pub database: Option<bool>,
+    pub rpc: Option<bool>,
+    pub caught_up: bool,
+    pub seconds_since_last_event: Option<u64>,
+    pub checkpoint_lag: Option<u64>,
+}
+
+pub async fn check_readiness(indexer: &Indexer, config: &ReadinessConfig) -> Readiness {
+    let database = if config.check_db {
+        Some(matches!(
+            timeout(config.check_timeout, indexer.db.health()).await,
+            Ok(Ok(()))
+        ))
+    } else {
+        None
+    };
+
+    let rpc = if config.check_rpc {
+        Some(match indexer.health.sui_client.get() {
+            Some(client) => matches!(
+                timeout(
+                    config.check_timeout,
+                    client.read_api().get_latest_checkpoint_sequence_number()
+                )
+                .await,
+                Ok(Ok(_))
+            ),
+            // The client is built once `start` runs; not reachable before that
+            None => false,
+        })
+    } else {
+        None
+    };
+
+    let caught_up = indexer.health.caught_up();
+    let since_last_event = indexer.health.since_last_event();
+    let checkpoint_lag = indexer.metrics.checkpoint_lag();
+    let fresh = match indexer.metrics.mode() {
+        // A live subscription says nothing about whether its events are
+        // being persisted; go by how far behind the node's checkpoint the
+        // indexer is instead
+        Some(IngestMode::WebSocket) => {
+            checkpoint_lag.is_some_and(|lag| lag <= config.max_checkpoint_lag)
+        }
+        _ => caught_up || since_last_event.is_some_and(|age| age <= config.max_event_age),
+    };
+
+    Readiness {
+        ready: database != Some(false) && rpc != Some(false) && fresh,
+        database,
+        rpc,
+        caught_up,
+        seconds_since_last_event: since_last_event.map(|age| age.as_secs()),
+        checkpoint_lag,
+    }
+}
@@ -0,0 +1,748 @@
+use anyhow::Result;
+use futures::StreamExt;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::env;
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use sui_sdk::{rpc_types::SuiEvent, types::event::EventID, SuiClient};
+use tokio::sync::{mpsc, watch};
+use tracing::{debug, error, info, info_span, Instrument};
+
+use crate::capture::Recorder;
+use crate::decoding::DecodedEvent;
+use crate::metrics::IngestMode;
+use crate::shutdown::{self, Shutdown};
+use crate::{env_or, storage, transactions, Indexer};
+
+// Most events taken from the subscription in one batch
+const MAX_STREAM_BATCH: usize = 256;
+
+// Transactions whose worker is remembered, so one split across batches
+// stays on the worker its first events went to
+const MAX_ROUTED_TXS: usize = 10_000;
+
+// Most transactions looked up per pass over rows stored without theirs
+const MAX_LINKS_PER_PASS: usize = 500;
+
+// Ingestion runs as three stages connected by bounded channels:
+//
+//   fetch  -> pages from the node (polling) or events as received (WebSocket)
+//   decode -> BCS decoding, routed to a persist worker by launchpad id
+//   persist -> DB writes, one task per worker
+//
+// A full channel blocks the stage before it, so a slow database throttles
+// fetching instead of buffering pages without bound. The cursor is saved
+// every few seconds, up to the last batch whose events have all been
+// persisted, so a crash replays at most the batches in flight; rows are
+// keyed by event id, which makes replaying them harmless. Events for the same
+// launchpad always land on the same worker, which keeps their order. Trades
+// don't name their launchpad, so a transaction is routed by the launchpad
+// any of its events names, and all its events go to one worker.
+
+pub struct PipelineConfig {
+    pub fetch_buffer: usize,
+    pub persist_buffer: usize,
+    pub persist_workers: usize,
+    pub poll_interval: Duration,
+    // How often the node's latest checkpoint is fetched for the lag metrics
+    pub checkpoint_poll_interval: Duration,
+    // How often the cursor of the persisted events is saved
+    pub cursor_save_interval: Duration,
+    // How often rows stored without their transaction are linked to it
+    pub link_retry_interval: Duration,
+    // Capture file every received event is appended to, if set
+    pub record_path: Option<PathBuf>,
+}
+
+impl PipelineConfig {
+    pub fn from_env() -> Self {
+        Self {
+            fetch_buffer: env_or("PIPELINE_FETCH_BUFFER", 4usize).max(1),
+            persist_buffer: env_or("PIPELINE_PERSIST_BUFFER", 256usize).max(1),
+            persist_workers: env_or("PIPELINE_PERSIST_WORKERS", 4usize).max(1),
+            poll_interval: Duration::from_millis(env_or("POLL_INTERVAL_MS", 1000)),
+            checkpoint_poll_interval: Duration::from_millis(env_or(
+                "CHECKPOINT_POLL_INTERVAL_MS",
+                10_000,
+            )),
+            cursor_save_interval: Duration::from_millis(env_or("CURSOR_SAVE_INTERVAL_MS", 1000)),
+            link_retry_interval: Duration::from_millis(env_or("LINK_RETRY_INTERVAL_MS", 60_000)),
+            record_path: env::var("RECORD_EVENTS").ok().map(PathBuf::from),
+        }
+    }
+}
+
+// Items waiting between stages
+#[derive(Debug, Default)]
+pub struct QueueDepth {
+    fetched: AtomicUsize,
+    decoded: AtomicUsize,
+}
+
+impl QueueDepth {
+    // Batches fetched from the node that the decoder has not picked up yet
+    pub fn fetched(&self) -> usize {
+        self.fetched.load(Ordering::Relaxed)
+    }
+
+    // Decoded events waiting for a persist worker
+    pub fn decoded(&self) -> usize {
+        self.decoded.load(Ordering::Relaxed)
+    }
+}
+
+// Events handed to the decoder together, and the cursor to resume from once
+// all of them have been persisted
+struct Batch {
+    events: Vec<SuiEvent>,
+    cursor: EventID,
+}
+
+// Events of a batch that have not been persisted yet
+type InFlight = Arc<AtomicUsize>;
+
+// Batches in fetch order with their events in flight. The cursor of a batch
+// is safe to save once it and every batch before it have drained.
+#[derive(Default)]
+struct Progress {
+    batches: Mutex<VecDeque<(EventID, InFlight)>>,
+}
+
+impl Progress {
+    fn start(&self, cursor: EventID, events: usize) -> InFlight {
+        let in_flight = Arc::new(AtomicUsize::new(events));
+        self.batches
+            .lock()
+            .unwrap()
+            .push_back((cursor, in_flight.clone()));
+        in_flight
+    }
+
+    // Forget the drained batches at the front, returning the cursor of the
+    // last of them
+    fn completed(&self) -> Option<EventID> {
+        let mut batches = self.batches.lock().unwrap();
+        let mut cursor = None;
+        while let Some((_, in_flight)) = batches.front() {
+            if in_flight.load(Ordering::Acquire) > 0 {
+                break;
+            }
+            cursor = batches.pop_front().map(|(cursor, _)| cursor);
+        }
+        cursor
+    }
+}
+
+pub async fn run(
+    indexer: Indexer,
+    sui_client: SuiClient,
+    config: PipelineConfig,
+    shutdown: Shutdown,
+) -> Result<()> {
+    let depth = indexer.queue_depth();
+    let progress = Arc::new(Progress::default());
+    let (batch_tx, batch_rx) = mpsc::channel(config.fetch_buffer);
+    let recorder = match &config.record_path {
+        Some(path) => Some(Recorder::create(path).await?),
+        None => None,
+    };
+
+    let mut worker_txs = Vec::with_capacity(config.persist_workers);
+    let mut workers = Vec::with_capacity(config.persist_workers);
+    for worker_id in 0..config.persist_workers {
+        let (tx, rx) = mpsc::channel(config.persist_buffer);
+        worker_txs.push(tx);
+        workers.push(tokio::spawn(persist_stage(
+            worker_id,
+            indexer.clone(),
+            rx,
+            depth.clone(),
+        )));
+    }
+    // Background tasks run until the pipeline has drained
+    let (stop, stopped) = watch::channel(false);
+    let checkpoints = tokio::spawn(poll_checkpoints(
+        indexer.clone(),
+        sui_client.clone(),
+        depth.clone(),
+        config.checkpoint_poll_interval,
+        stopped.clone(),
+    ));
+    let linker = tokio::spawn(link_transactions(
+        indexer.clone(),
+        config.link_retry_interval,
+        stopped.clone(),
+    ));
+    let saver = tokio::spawn(save_progress(
+        indexer.clone(),
+        progress.clone(),
+        config.cursor_save_interval,
+        stopped,
+    ));
+    let decoder = tokio::spawn(decode_stage(
+        indexer.clone(),
+        batch_rx,
+        worker_txs,
+        depth.clone(),
+        progress,
+        recorder,
+    ));
+
+    let result = fetch_stage(
+        &indexer,
+        &sui_client,
+        batch_tx,
+        &depth,
+        config.poll_interval,
+        shutdown,
+    )
+    .await;
+
+    // The fetcher has dropped its sender, so the later stages drain what is
+    // queued and then exit on their own
+    decoder.await?;
+    for worker in workers {
+        worker.await?;
+    }
+    let _ = stop.send(true);
+    checkpoints.await?;
+    linker.await?;
+    saver.await?;
+
+    // Everything fetched up to the cursor has now been through the pipeline
+    if let Some(cursor) = result? {
+        storage::save_cursor(indexer.db(), &cursor).await?;
+        info!("Saved cursor {:?}", cursor);
+    }
+    Ok(())
+}
+
+// Why the WebSocket phase of the fetcher ended
+enum StreamEnd {
+    // Shutdown was requested or the decode stage went away
+    Stopped,
+    // The subscription could not be set up, or the stream broke
+    Failed,
+}
+
+// Returns the id of the last event handed to the pipeline, if any
+async fn fetch_stage(
+    indexer: &Indexer,
+    sui_client: &SuiClient,
+    batches: mpsc::Sender<Batch>,
+    depth: &QueueDepth,
+    poll_interval: Duration,
+    mut shutdown: Shutdown,
+) -> Result<Option<EventID>> {
+    let mut cursor = storage::load_cursor(indexer.db()).await?;
+
+    // The subscription only delivers new events, so first query what was
+    // missed since the last run stopped
+    let backfilled = match cursor {
+        Some(saved) => {
+            info!("Resuming from cursor {:?}", saved);
+            indexer.metrics.set_mode(IngestMode::Polling);
+            backfill(
+                indexer,
+                sui_client,
+                &batches,
+                depth,
+                &mut shutdown,
+                &mut cursor,
+                None,
+            )
+            .await?
+        }
+        None => None,
+    };
+
+    // Then switch to the WebSocket subscription
+    let end = match backfilled {
+        Some(end) => end,
+        None => {
+            stream_events(
+                indexer,
+                sui_client,
+                &batches,
+                depth,
+                &mut shutdown,
+                &mut cursor,
+            )
+            .await?
+        }
+    };
+
+    // Fall back to polling, resuming after the last event handed on
+    if let StreamEnd::Failed = end {
+        indexer.metrics.set_mode(IngestMode::Polling);
+        poll_events(
+            indexer,
+            sui_client,
+            &batches,
+            depth,
+            poll_interval,
+            &mut shutdown,
+            &mut cursor,
+        )
+        .await?;
+    }
+
+    if *shutdown.borrow() {
+        info!("Stopped fetching events");
+    }
+    Ok(cursor)
+}
+
+async fn stream_events(
+    indexer: &Indexer,
+    sui_client: &SuiClient,
+    batches: &mpsc::Sender<Batch>,
+    depth: &QueueDepth,
+    shutdown: &mut Shutdown,
+    cursor: &mut Option<EventID>,
+) -> Result<StreamEnd> {
+    let mut subscribe_all = match sui_client
+        .event_api()
+        .subscribe_event(indexer.event_filter()?)
+        .await
+    {
+        Ok(subscription) => subscription,
+        Err(e) => {
+            error!(
+                "Failed to subscribe via WebSocket: {}. Falling back to polling.",
+                e
+            );
+            indexer.metrics.record_rpc_error("subscribe_event");
+            return Ok(StreamEnd::Failed);
+        }
+    };
+
+    info!("Successfully subscribed to events via WebSocket");
+    // Events committed between the last backfilled page and the
+    // subscription starting only show up in a query. Fetch them while the
+    // subscription buffers, and skip them if it delivers them as well.
+    let mut backfilled = HashSet::new();
+    if cursor.is_some() {
+        if let Some(end) = backfill(
+            indexer,
+            sui_client,
+            batches,
+            depth,
+            shutdown,
+            cursor,
+            Some(&mut backfilled),
+        )
+        .await?
+        {
+            return Ok(end);
+        }
+    }
+    indexer.metrics.set_mode(IngestMode::WebSocket);
+    indexer.health.set_caught_up(true);
+    // A transaction's events arrive together; taking everything already
+    // received lets the decoder route them as one transaction
+    let mut subscription = subscribe_all.ready_chunks(MAX_STREAM_BATCH);
+    loop {
+        let received = tokio::select! {
+            biased;
+            _ = shutdown::requested(shutdown) => return Ok(StreamEnd::Stopped),
+            received = subscription.next() => received,
+        };
+        let Some(received) = received else {
+            error!("WebSocket subscription closed. Falling back to polling.");
+            indexer.metrics.record_rpc_error("subscription_stream");
+            indexer.health.set_caught_up(false);
+            return Ok(StreamEnd::Failed);
+        };
+
+        let mut batch = Vec::with_capacity(received.len());
+        let mut failure = None;
+        for event in received {
+            match event {
+                Ok(event) => {
+                    if !backfilled.remove(&event.id) {
+                        batch.push(event);
+                    }
+                }
+                Err(e) => {
+                    failure = Some(e);
+                    break;
+                }
+            }
+        }
+        if let Some(last) = batch.last() {
+            let id = last.id;
+            let batch = Batch {
+                events: batch,
+                cursor: id,
+            };
+            if !send_batch(batches, depth, batch).await {
+                return Ok(StreamEnd::Stopped);
+            }
+            *cursor = Some(id);
+        }
+        if let Some(e) = failure {
+            error!("Error receiving event: {}. Falling back to polling.", e);
+            indexer.metrics.record_rpc_error("subscription_stream");
+            indexer.health.set_caught_up(false);
+            return Ok(StreamEnd::Failed);
+        }
+    }
+}
+
+async fn poll_events(
+    indexer: &Indexer,
+    sui_client: &SuiClient,
+    batches: &mpsc::Sender<Batch>,
+    depth: &QueueDepth,
+    poll_interval: Duration,
+    shutdown: &mut Shutdown,
+    cursor: &mut Option<EventID>,
+) -> Result<()> {
+    loop {
+        match fetch_page(indexer, sui_client, batches, depth, shutdown, cursor, None).await? {
+            Fetched::Stopped => return Ok(()),
+            // Fetch the next page straight away while this one is decoded
+            // and persisted; only sleep once caught up
+            Fetched::Page {
+                has_next_page: true,
+            } => continue,
+            Fetched::Page { .. } | Fetched::Failed => {}
+        }
+
+        // Sleep before next poll
+        tokio::select! {
+            biased;
+            _ = shutdown::requested(shutdown) => return Ok(()),
+            _ = tokio::time::sleep(poll_interval) => {}
+        }
+    }
+}
+
+// Query pages from `cursor` up to the last one, adding the ids handed on to
+// `seen` if given. Returns why it stopped before that, if it did.
+async fn backfill(
+    indexer: &Indexer,
+    sui_client: &SuiClient,
+    batches: &mpsc::Sender<Batch>,
+    depth: &QueueDepth,
+    shutdown: &mut Shutdown,
+    cursor: &mut Option<EventID>,
+    mut seen: Option<&mut HashSet<EventID>>,
+) -> Result<Option<StreamEnd>> {
+    loop {
+        let fetched = fetch_page(
+            indexer,
+            sui_client,
+            batches,
+            depth,
+            shutdown,
+            cursor,
+            seen.as_deref_mut(),
+        )
+        .await?;
+        match fetched {
+            Fetched::Page {
+                has_next_page: true,
+            } => {}
+            Fetched::Page { .. } => return Ok(None),
+            Fetched::Failed => return Ok(Some(StreamEnd::Failed)),
+            Fetched::Stopped => return Ok(Some(StreamEnd::Stopped)),
+        }
+    }
+}
+
+// Outcome of querying one page of events
+enum Fetched {
+    Page { has_next_page: bool },
+    Failed,
+    // Shutdown was requested or the decode stage went away
+    Stopped,
+}
+
+// Query the page after `cursor`, hand its events to the pipeline and move
+// `cursor` past them
+async fn fetch_page(
+    indexer: &Indexer,
+    sui_client: &SuiClient,
+    batches: &mpsc::Sender<Batch>,
+    depth: &QueueDepth,
+    shutdown: &mut Shutdown,
+    cursor: &mut Option<EventID>,
+    seen: Option<&mut HashSet<EventID>>,
+) -> Result<Fetched> {
+    let filter = indexer.event_filter()?;
+    let page = tokio::select! {
+        biased;
+        _ = shutdown::requested(shutdown) => return Ok(Fetched::Stopped),
+        page = sui_client.event_api().query_events(filter, *cursor, None, false) => page,
+    };
+    let event_page = match page {
+        Ok(event_page) => event_page,
+        Err(e) => {
+            error!("Failed to poll events: {}", e);
+            indexer.metrics.record_rpc_error("query_events");
+            indexer.health.set_caught_up(false);
+            return Ok(Fetched::Failed);
+        }
+    };
+
+    let next_cursor = event_page.next_cursor.or(*cursor);
+    let has_next_page = event_page.has_next_page;
+    indexer.health.set_caught_up(!has_next_page);
+    if let Some(last) = event_page.data.last() {
+        if let Some(seen) = seen {
+            seen.extend(event_page.data.iter().map(|event| event.id));
+        }
+        let batch = Batch {
+            cursor: next_cursor.unwrap_or(last.id),
+            events: event_page.data,
+        };
+        if !send_batch(batches, depth, batch).await {
+            return Ok(Fetched::Stopped);
+        }
+    }
+    *cursor = next_cursor;
+    Ok(Fetched::Page { has_next_page })
+}
+
+// Keep the checkpoint gauges current. Events only carry a checkpoint when
+// their transaction is looked up, so once the fetcher is caught up and the
+// queues are empty everything up to the latest checkpoint counts as
+// processed.
+async fn poll_checkpoints(
+    indexer: Indexer,
+    sui_client: SuiClient,
+    depth: Arc<QueueDepth>,
+    interval: Duration,
+    mut shutdown: Shutdown,
+) {
+    let mut ticks = tokio::time::interval(interval);
+    loop {
+        tokio::select! {
+            biased;
+            _ = shutdown::requested(&mut shutdown) => return,
+            _ = ticks.tick() => {}
+        }
+        match sui_client
+            .read_api()
+            .get_latest_checkpoint_sequence_number()
+            .await
+        {
+            Ok(latest) => {
+                indexer.metrics.set_latest_checkpoint(latest);
+                if indexer.health.caught_up() && depth.fetched() == 0 && depth.decoded() == 0 {
+                    indexer.metrics.record_checkpoint(latest);
+                }
+            }
+            Err(e) => {
+                error!("Failed to fetch the latest checkpoint: {}", e);
+                indexer
+                    .metrics
+                    .record_rpc_error("get_latest_checkpoint_sequence_number");
+            }
+        }
+    }
+}
+
+// Link rows stored while their transaction couldn't be looked up, starting
+// with the ones left from before this run
+async fn link_transactions(indexer: Indexer, interval: Duration, mut stopped: Shutdown) {
+    let Some(source) = indexer.transactions.clone() else {
+        return;
+    };
+    let mut ticks = tokio::time::interval(interval);
+    loop {
+        tokio::select! {
+            biased;
+            _ = shutdown::requested(&mut stopped) => return,
+            _ = ticks.tick() => {}
+        }
+        match transactions::link_unlinked(indexer.db(), source.as_ref(), MAX_LINKS_PER_PASS).await {
+            Ok(0) => {}
+            Ok(fetched) => info!("Linked stored rows to {} transactions", fetched),
+            Err(e) => error!(error = %e, "Failed to link rows to their transactions"),
+        }
+    }
+}
+
+// Save the cursor of the persisted events whenever it moves
+async fn save_progress(
+    indexer: Indexer,
+    progress: Arc<Progress>,
+    interval: Duration,
+    mut stopped: Shutdown,
+) {
+    let mut ticks = tokio::time::interval(interval);
+    loop {
+        tokio::select! {
+            biased;
+            _ = shutdown::requested(&mut stopped) => return,
+            _ = ticks.tick() => {}
+        }
+        let Some(cursor) = progress.completed() else {
+            continue;
+        };
+        match storage::save_cursor(indexer.db(), &cursor).await {
+            Ok(()) => debug!("Saved cursor {:?}", cursor),
+            Err(e) => error!(error = %e, "Failed to save cursor"),
+        }
+    }
+}
+
+async fn send_batch(batches: &mpsc::Sender<Batch>, depth: &QueueDepth, batch: Batch) -> bool {
+    depth.fetched.fetch_add(1, Ordering::Relaxed);
+    if batches.send(batch).await.is_err() {
+        depth.fetched.fetch_sub(1, Ordering::Relaxed);
+        error!("Decode stage stopped, halting fetch");
+        return false;
+    }
+    debug!(
+        "Queue depth: {} fetched, {} decoded",
+        depth.fetched(),
+        depth.decoded()
+    );
+    true
+}
+
+async fn decode_stage(
+    indexer: Indexer,
+    mut batches: mpsc::Receiver<Batch>,
+    workers: Vec<mpsc::Sender<(DecodedEvent, InFlight)>>,
+    depth: Arc<QueueDepth>,
+    progress: Arc<Progress>,
+    mut recorder: Option<Recorder>,
+) {
+    let mut routes = Routes::new(workers.len());
+    while let Some(batch) = batches.recv().await {
+        depth.fetched.fetch_sub(1, Ordering::Relaxed);
+        if let Some(recorder) = recorder.as_mut() {
+            record(recorder, &batch.events).await;
+        }
+        let mut decoded_batch = Vec::with_capacity(batch.events.len());
+        for event in batch.events {
+            match indexer.decode_event(&event) {
+                Ok(Some(decoded)) => decoded_batch.push(decoded),
+                Ok(None) => {}
+                Err(e) => {
+                    error!(
+                        event_type = %event.type_,
+                        tx_digest = %event.id.tx_digest,
+                        error = %e,
+                        "Failed to decode event"
+                    );
+                    indexer.metrics.record_decode_failure();
+                }
+            }
+        }
+
+        indexer.prefetch_transactions(&decoded_batch).await;
+
+        // Launchpad of each transaction in the batch that names one
+        let launchpads: HashMap<String, String> = decoded_batch
+            .iter()
+            .filter_map(|decoded| {
+                let launchpad_id = decoded.event.launchpad_id()?;
+                Some((decoded.metadata.tx_digest.clone(), launchpad_id))
+            })
+            .collect();
+
+        let in_flight = progress.start(batch.cursor, decoded_batch.len());
+        for mut decoded in decoded_batch {
+            let metadata = &mut decoded.metadata;
+            if metadata.launchpad_id.is_none() {
+                metadata.launchpad_id = launchpads.get(&metadata.tx_digest).cloned();
+            }
+            let worker =
+                &workers[routes.worker_for(&metadata.tx_digest, metadata.launchpad_id.as_deref())];
+            depth.decoded.fetch_add(1, Ordering::Relaxed);
+            if worker.send((decoded, in_flight.clone())).await.is_err() {
+                depth.decoded.fetch_sub(1, Ordering::Relaxed);
+                error!("Persist worker stopped, halting decode");
+                return;
+            }
+        }
+    }
+}
+
+// Capture failures are logged but never stop ingestion
+async fn record(recorder: &mut Recorder, batch: &[SuiEvent]) {
+    for event in batch {
+        if let Err(e) = recorder.record(event).await {
+            error!(tx_digest = %event.id.tx_digest, error = %e, "Failed to record event");
+        }
+    }
+    if let Err(e) = recorder.flush().await {
+        error!(error = %e, "Failed to flush capture file");
+    }
+}
+
+// Which persist worker each transaction goes to
+struct Routes {
+    workers: usize,
+    recent: HashMap<String, usize>,
+    order: VecDeque<String>,
+}
+
+impl Routes {
+    fn new(workers: usize) -> Self {
+        Self {
+            workers,
+            recent: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    // The worker a transaction already went to, or else the one for its
+    // launchpad. A transaction with no known launchpad is routed by its
+    // digest, which keeps its own events in order.
+    fn worker_for(&mut self, tx_digest: &str, launchpad_id: Option<&str>) -> usize {
+        if let Some(&worker) = self.recent.get(tx_digest) {
+            return worker;
+        }
+        let mut hasher = DefaultHasher::new();
+        launchpad_id.unwrap_or(tx_digest).hash(&mut hasher);
+        let worker = (hasher.finish() % self.workers as u64) as usize;
+
+        if self.order.len() == MAX_ROUTED_TXS {
+            if let Some(oldest) = self.order.pop_front() {
+                self.recent.remove(&oldest);
+            }
+        }
+        self.recent.insert(tx_digest.to_string(), worker);
+        self.order.push_back(tx_digest.to_string());
+        worker
+    }
+}
+
+async fn persist_stage(
+    worker_id: usize,
+    indexer: Indexer,
+    mut events: mpsc::Receiver<(DecodedEvent, InFlight)>,
+    depth: Arc<QueueDepth>,
+) {
+    while let Some((decoded, in_flight)) = events.recv().await {
+        depth.decoded.fetch_sub(1, Ordering::Relaxed);
+        // Everything logged while persisting carries the event's identity
+        let span = info_span!(
+            "event",
+            worker_id,
+            event_type = decoded.event.name(),
+            tx_digest = %decoded.metadata.tx_digest,
+            launchpad_id = decoded.event.launchpad_id().as_deref(),
+        );
+        match indexer
+            .persist_event(decoded)
+            .instrument(span.clone())
+            .await
+        {
+            Ok(()) => span.in_scope(|| debug!("Indexed event")),
+            Err(e) => span.in_scope(|| error!(error = %e, "Failed to handle event")),
+        }
+        in_flight.fetch_sub(1, Ordering::Release);
+    }
+}
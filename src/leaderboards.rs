@@ -0,0 +1,326 @@
+//! Rankings of buyers, launchpads and wallets over trailing windows.
+//!
+//! Boards are aggregated by the database and kept for a while before being
+//! recomputed, so a busy marketing page doesn't rescan the purchase history
+//! on every request; concurrent requests for a stale board share one
+//! recomputation. SUI raised is estimated from purchases at the price in
+//! effect before each, like a portfolio's spending, and recorded on each
+//! purchase once its launchpad is known.
+
+use anyhow::Result;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::hash::Hash;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use surrealdb::{engine::any::Any, Surreal};
+use utoipa::ToSchema;
+
+// Entries kept per board; requests can ask for fewer
+pub const MAX_ENTRIES: usize = 100;
+
+const DAY_MS: u64 = 24 * 60 * 60 * 1000;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Deserialize, ToSchema)]
+pub enum Window {
+    #[serde(rename = "24h")]
+    Day,
+    #[serde(rename = "7d")]
+    Week,
+    #[default]
+    #[serde(rename = "all")]
+    AllTime,
+}
+
+impl Window {
+    // Earliest timestamp inside the window ending at `now`
+    fn since(self, now: u64) -> u64 {
+        match self {
+            Window::Day => now.saturating_sub(DAY_MS),
+            Window::Week => now.saturating_sub(7 * DAY_MS),
+            Window::AllTime => 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LaunchpadOrder {
+    /// Estimated SUI raised from purchases.
+    #[default]
+    Raised,
+    /// Distinct buyers.
+    Buyers,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct BuyerRank {
+    pub rank: usize,
+    pub wallet: String,
+    /// Tokens bought in the window.
+    pub volume: u64,
+    pub purchases: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct LaunchpadRank {
+    pub rank: usize,
+    pub launchpad_id: String,
+    pub name: Option<String>,
+    /// Estimated from purchases, in the price's units.
+    pub sui_raised: u64,
+    pub unique_buyers: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct WalletRank {
+    pub rank: usize,
+    pub wallet: String,
+    /// Purchases, transfers either way, vesting claims and launchpads created.
+    pub events: u64,
+}
+
+#[derive(Deserialize)]
+struct BuyerVolume {
+    buyer: String,
+    volume: u64,
+    purchases: u64,
+}
+
+pub async fn top_buyers(db: &Surreal<Any>, since: u64) -> Result<Vec<BuyerRank>> {
+    let mut volumes: Vec<BuyerVolume> = db
+        .query(
+            "SELECT buyer, math::sum(amount) AS volume, count() AS purchases \
+             FROM token_purchases WHERE timestamp >= $since GROUP BY buyer",
+        )
+        .bind(("since", since))
+        .await?
+        .take(0)?;
+    volumes.sort_by(|a, b| b.volume.cmp(&a.volume).then_with(|| a.buyer.cmp(&b.buyer)));
+    Ok(volumes
+        .into_iter()
+        .take(MAX_ENTRIES)
+        .enumerate()
+        .map(|(i, v)| BuyerRank {
+            rank: i + 1,
+            wallet: v.buyer,
+            volume: v.volume,
+            purchases: v.purchases,
+        })
+        .collect())
+}
+
+#[derive(Deserialize)]
+struct LaunchpadTotal {
+    launchpad_id: String,
+    raised: u64,
+    buyers: u64,
+}
+
+#[derive(Deserialize)]
+struct LaunchpadName {
+    launchpad_id: String,
+    name: String,
+}
+
+pub async fn top_launchpads(
+    db: &Surreal<Any>,
+    since: u64,
+    order: LaunchpadOrder,
+) -> Result<Vec<LaunchpadRank>> {
+    // Purchases are only counted once `storage::attribute_purchases` has tied
+    // them to a launchpad
+    let mut response = db
+        .query(
+            "SELECT launchpad_id, math::sum(raised) AS raised FROM token_purchases \
+             WHERE timestamp >= $since AND launchpad_id != NONE GROUP BY launchpad_id",
+        )
+        .query(
+            "SELECT launchpad_id, count() AS buyers FROM ( \
+                 SELECT launchpad_id, buyer FROM token_purchases \
+                 WHERE timestamp >= $since AND launchpad_id != NONE \
+                 GROUP BY launchpad_id, buyer \
+             ) GROUP BY launchpad_id",
+        )
+        .bind(("since", since))
+        .await?;
+    let raised: Vec<LaunchpadTotal> = response.take(0)?;
+    let buyers: Vec<LaunchpadTotal> = response.take(1)?;
+    let buyers: HashMap<String, u64> = buyers
+        .into_iter()
+        .map(|t| (t.launchpad_id, t.buyers))
+        .collect();
+
+    let mut ranked: Vec<LaunchpadRank> = raised
+        .into_iter()
+        .map(|t| LaunchpadRank {
+            rank: 0,
+            unique_buyers: buyers.get(&t.launchpad_id).copied().unwrap_or(0),
+            launchpad_id: t.launchpad_id,
+            name: None,
+            sui_raised: t.raised,
+        })
+        .collect();
+    ranked.sort_by(|a, b| {
+        let (a_key, b_key) = match order {
+            LaunchpadOrder::Raised => (a.sui_raised, b.sui_raised),
+            LaunchpadOrder::Buyers => (a.unique_buyers, b.unique_buyers),
+        };
+        b_key
+            .cmp(&a_key)
+            .then_with(|| a.launchpad_id.cmp(&b.launchpad_id))
+    });
+    ranked.truncate(MAX_ENTRIES);
+
+    let ids: Vec<String> = ranked.iter().map(|l| l.launchpad_id.clone()).collect();
+    let names: Vec<LaunchpadName> = db
+        .query("SELECT launchpad_id, name FROM launchpads WHERE launchpad_id INSIDE $ids")
+        .bind(("ids", ids))
+        .await?
+        .take(0)?;
+    let names: HashMap<String, String> = names
+        .into_iter()
+        .map(|l| (l.launchpad_id, l.name))
+        .collect();
+    for (i, launchpad) in ranked.iter_mut().enumerate() {
+        launchpad.rank = i + 1;
+        launchpad.name = names.get(&launchpad.launchpad_id).cloned();
+    }
+    Ok(ranked)
+}
+
+#[derive(Deserialize)]
+struct WalletCount {
+    wallet: String,
+    events: u64,
+}
+
+// Tables and the wallet fields counted as taking part in each row
+const ACTIVITY: [(&str, &str); 5] = [
+    ("token_purchases", "buyer"),
+    ("token_transfers", "`from`"),
+    ("token_transfers", "`to`"),
+    ("vesting_claims", "user"),
+    ("launchpads", "creator"),
+];
+
+pub async fn most_active_wallets(db: &Surreal<Any>, since: u64) -> Result<Vec<WalletRank>> {
+    let statements: Vec<String> = ACTIVITY
+        .iter()
+        .map(|(table, field)| {
+            format!(
+                "SELECT {} AS wallet, count() AS events FROM {} \
+                 WHERE timestamp >= $since GROUP BY wallet",
+                field, table
+            )
+        })
+        .collect();
+    let mut response = db
+        .query(statements.join(";"))
+        .bind(("since", since))
+        .await?;
+
+    let mut counts: HashMap<String, u64> = HashMap::new();
+    for i in 0..ACTIVITY.len() {
+        let rows: Vec<WalletCount> = response.take(i)?;
+        for row in rows {
+            *counts.entry(row.wallet).or_default() += row.events;
+        }
+    }
+
+    let mut ranked: Vec<(String, u64)> = counts.into_iter().collect();
+    ranked.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    Ok(ranked
+        .into_iter()
+        .take(MAX_ENTRIES)
+        .enumerate()
+        .map(|(i, (wallet, events))| WalletRank {
+            rank: i + 1,
+            wallet,
+            events,
+        })
+        .collect())
+}
+
+// A board and when it was computed, locked while it is being recomputed
+type Slot<T> = Arc<tokio::sync::Mutex<Option<(Instant, Arc<Vec<T>>)>>>;
+
+// Boards computed at most once per `ttl`, by one caller at a time per key
+struct Cache<K, T> {
+    entries: Mutex<HashMap<K, Slot<T>>>,
+}
+
+impl<K: Eq + Hash, T> Cache<K, T> {
+    fn new() -> Self {
+        Self {
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    async fn get<F>(&self, key: K, ttl: Duration, compute: F) -> Result<Arc<Vec<T>>>
+    where
+        F: Future<Output = Result<Vec<T>>>,
+    {
+        let slot = self.entries.lock().unwrap().entry(key).or_default().clone();
+        // Callers that find the board stale wait here for the first of them
+        // to recompute it, then take its result
+        let mut entry = slot.lock().await;
+        if let Some((at, board)) = entry.as_ref() {
+            if at.elapsed() < ttl {
+                return Ok(board.clone());
+            }
+        }
+        let board = Arc::new(compute.await?);
+        *entry = Some((Instant::now(), board.clone()));
+        Ok(board)
+    }
+}
+
+/// Leaderboards served by the API, recomputed once they are `ttl` old.
+pub struct Leaderboards {
+    db: Surreal<Any>,
+    ttl: Duration,
+    buyers: Cache<Window, BuyerRank>,
+    launchpads: Cache<(Window, LaunchpadOrder), LaunchpadRank>,
+    wallets: Cache<Window, WalletRank>,
+}
+
+// Start of `window`, in milliseconds
+fn since(window: Window) -> u64 {
+    window.since(Utc::now().timestamp_millis() as u64)
+}
+
+impl Leaderboards {
+    pub fn new(db: Surreal<Any>, ttl: Duration) -> Self {
+        Self {
+            db,
+            ttl,
+            buyers: Cache::new(),
+            launchpads: Cache::new(),
+            wallets: Cache::new(),
+        }
+    }
+
+    pub async fn top_buyers(&self, window: Window) -> Result<Arc<Vec<BuyerRank>>> {
+        let compute = top_buyers(&self.db, since(window));
+        self.buyers.get(window, self.ttl, compute).await
+    }
+
+    pub async fn top_launchpads(
+        &self,
+        window: Window,
+        order: LaunchpadOrder,
+    ) -> Result<Arc<Vec<LaunchpadRank>>> {
+        let compute = top_launchpads(&self.db, since(window), order);
+        self.launchpads
+            .get((window, order), self.ttl, compute)
+            .await
+    }
+
+    pub async fn most_active_wallets(&self, window: Window) -> Result<Arc<Vec<WalletRank>>> {
+        let compute = most_active_wallets(&self.db, since(window));
+        self.wallets.get(window, self.ttl, compute).await
+    }
+}
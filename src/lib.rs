@@ -0,0 +1,345 @@
+//! Indexer for the launchpad Move package on Sui.
+//!
+//! [`events`] and [`decoding`] hold the typed event API other services can
+//! depend on; [`Indexer`] ties decoding, [`storage`] and ingestion together.
+
+use anyhow::Result;
+use std::env;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use sui_sdk::{
+    rpc_types::{EventFilter, SuiEvent},
+    types::base_types::ObjectID,
+    SuiClientBuilder,
+};
+use surrealdb::{
+    engine::any::{connect, Any},
+    opt::auth::Root,
+    Surreal,
+};
+use tracing::{info, warn};
+
+pub mod api;
+pub mod audit;
+pub mod auth;
+pub mod capture;
+pub mod decoding;
+pub mod events;
+pub mod feed;
+pub mod fees;
+pub mod graphql;
+pub mod handlers;
+mod health;
+mod ingestion;
+pub mod leaderboards;
+mod metrics;
+pub mod pnl;
+pub mod portfolio;
+pub mod rest;
+pub mod shutdown;
+pub mod storage;
+pub mod transactions;
+pub mod vesting;
+pub mod webhooks;
+
+pub use ingestion::PipelineConfig;
+
+use decoding::{DecodedEvent, EventMetadata};
+use events::LaunchpadEvent;
+use feed::Feed;
+use handlers::EventHandler;
+use health::Health;
+use ingestion::QueueDepth;
+use metrics::Metrics;
+use shutdown::Shutdown;
+use transactions::TransactionSource;
+use vesting::VestingSource;
+
+#[derive(Clone)]
+pub struct Indexer {
+    package_id: ObjectID,
+    db: Surreal<Any>,
+    queue_depth: Arc<QueueDepth>,
+    metrics: Arc<Metrics>,
+    health: Arc<Health>,
+    feed: Feed,
+    handlers: Arc<Vec<Arc<dyn EventHandler>>>,
+    handler_timeout: Duration,
+    transactions: Option<Arc<dyn TransactionSource>>,
+    vesting: Option<Arc<dyn VestingSource>>,
+}
+
+impl Indexer {
+    pub async fn new(package_id: &str) -> Result<Self> {
+        Self::connect(package_id, "launchpad").await
+    }
+
+    // Connect to the SurrealDB server and index into `database`
+    pub async fn connect(package_id: &str, database: &str) -> Result<Self> {
+        // Create database connection
+        let db = connect("ws://127.0.0.1:8000").await?;
+        db.signin(Root {
+            username: "root",
+            password: "root",
+        })
+        .await?;
+        db.use_ns("sui").use_db(database).await?;
+        Self::with_db(package_id, db).await
+    }
+
+    // Build an indexer on an already connected database with its namespace
+    // and database selected, e.g. an embedded `mem://` instance in tests
+    pub async fn with_db(package_id: &str, db: Surreal<Any>) -> Result<Self> {
+        info!("Initializing Indexer with package ID: {}", package_id);
+
+        storage::define_schema(&db).await?;
+        storage::attribute_stored_purchases(&db).await?;
+        storage::fill_holder_balances(&db).await?;
+        let feed = Feed::new(db.clone(), env_or("FEED_BUFFER", 10_000)).await?;
+
+        Ok(Self {
+            package_id: ObjectID::from_hex_literal(package_id)?,
+            db,
+            queue_depth: Arc::new(QueueDepth::default()),
+            metrics: Arc::new(Metrics::new()?),
+            health: Arc::new(Health::default()),
+            feed,
+            handlers: Arc::new(Vec::new()),
+            handler_timeout: Duration::from_millis(env_or("HANDLER_TIMEOUT_MS", 5000)),
+            transactions: None,
+            vesting: None,
+        })
+    }
+
+    // Register `handler` to be called for every event stored from now on
+    pub fn with_handler(mut self, handler: impl EventHandler + 'static) -> Self {
+        Arc::make_mut(&mut self.handlers).push(Arc::new(handler));
+        self
+    }
+
+    // Look up the transaction of every event stored from now on in `source`.
+    // `start` uses the node unless FETCH_TRANSACTIONS is false.
+    pub fn with_transaction_source(mut self, source: impl TransactionSource + 'static) -> Self {
+        self.transactions = Some(Arc::new(source));
+        self
+    }
+
+    // Look up the vesting terms of every launchpad created from now on in
+    // `source`. `start` uses the node and fetches any terms still missing.
+    pub fn with_vesting_source(mut self, source: impl VestingSource + 'static) -> Self {
+        self.vesting = Some(Arc::new(source));
+        self
+    }
+
+    pub async fn handle_event(&self, event: SuiEvent) -> Result<()> {
+        if let Some(decoded) = self.decode_event(&event)? {
+            self.persist_event(decoded).await?;
+        }
+        Ok(())
+    }
+
+    pub fn decode_event(&self, event: &SuiEvent) -> Result<Option<DecodedEvent>> {
+        decoding::decode_event(&self.package_id, event)
+    }
+
+    async fn persist_event(&self, mut decoded: DecodedEvent) -> Result<()> {
+        let linked = self.resolve_transaction(&mut decoded.metadata).await;
+        self.resolve_launchpad(&mut decoded.metadata).await;
+        let DecodedEvent { metadata, event } = &decoded;
+        let started = Instant::now();
+        storage::store_event(&self.db, &decoded, linked).await?;
+        if let (
+            Some(launchpad_id),
+            LaunchpadEvent::TokensPurchased(_) | LaunchpadEvent::BalanceUpdate(_),
+        ) = (&metadata.launchpad_id, event)
+        {
+            storage::attribute_purchases(&self.db, &metadata.tx_digest, launchpad_id).await?;
+        }
+        if let LaunchpadEvent::BalanceUpdate(update) = event {
+            storage::refresh_holder_balance(
+                &self.db,
+                &update.launchpad_id.to_string(),
+                &update.holder.to_string(),
+            )
+            .await?;
+        }
+        if let LaunchpadEvent::LaunchpadCreated(created) = event {
+            self.resolve_vesting(&created.launchpad_id.to_string())
+                .await;
+        }
+        self.metrics
+            .record_write(event.name(), started.elapsed(), metadata.timestamp);
+        if let Some(checkpoint) = metadata.checkpoint {
+            self.metrics.record_checkpoint(checkpoint);
+        }
+        self.health.record_event();
+        self.feed.publish(event, metadata).await;
+
+        handlers::dispatch(
+            &self.handlers,
+            event,
+            metadata,
+            self.handler_timeout,
+            &self.metrics,
+        )
+        .await;
+        Ok(())
+    }
+
+    // Fill in the checkpoint from the event's transaction, fetching it if it
+    // isn't stored yet. True once the transaction is stored; a failed lookup
+    // is logged and the event stored without it.
+    async fn resolve_transaction(&self, metadata: &mut EventMetadata) -> bool {
+        let Some(source) = &self.transactions else {
+            return false;
+        };
+        match transactions::context(&self.db, source.as_ref(), &metadata.tx_digest).await {
+            Ok(meta) => {
+                metadata.checkpoint = meta.checkpoint;
+                metadata.timestamp = metadata.timestamp.or(meta.timestamp);
+                true
+            }
+            Err(e) => {
+                warn!(tx_digest = %metadata.tx_digest, error = %e, "Failed to look up transaction");
+                self.metrics.record_rpc_error("get_transaction_block");
+                false
+            }
+        }
+    }
+
+    // Look up the transactions of a batch in as few requests as possible, so
+    // persisting its events finds them stored. A failure is logged, and each
+    // event then looks its own transaction up.
+    async fn prefetch_transactions(&self, batch: &[DecodedEvent]) {
+        let Some(source) = &self.transactions else {
+            return;
+        };
+        let digests = batch.iter().map(|d| d.metadata.tx_digest.clone()).collect();
+        if let Err(e) = transactions::prefetch(&self.db, source.as_ref(), digests).await {
+            warn!(error = %e, "Failed to look up the transactions of a batch");
+            self.metrics
+                .record_rpc_error("multi_get_transaction_blocks");
+        }
+    }
+
+    // Fill in the launchpad from the transaction's stored BalanceUpdate for
+    // events that don't name one. Left unset if that isn't stored yet.
+    async fn resolve_launchpad(&self, metadata: &mut EventMetadata) {
+        if metadata.launchpad_id.is_some() {
+            return;
+        }
+        match storage::tx_launchpad(&self.db, &metadata.tx_digest).await {
+            Ok(launchpad_id) => metadata.launchpad_id = launchpad_id,
+            Err(e) => {
+                warn!(tx_digest = %metadata.tx_digest, error = %e, "Failed to look up launchpad")
+            }
+        }
+    }
+
+    // Store the vesting terms of a new launchpad. A failed lookup is logged
+    // and retried by `vesting::fetch_missing_terms` on the next start.
+    async fn resolve_vesting(&self, launchpad_id: &str) {
+        let Some(source) = &self.vesting else {
+            return;
+        };
+        let stored = match source.terms(launchpad_id).await {
+            Ok(terms) => vesting::store_terms(&self.db, launchpad_id, terms).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = stored {
+            warn!(%launchpad_id, error = %e, "Failed to look up vesting terms");
+            self.metrics.record_rpc_error("get_object");
+        }
+    }
+
+    pub async fn start(&self, shutdown: Shutdown) -> Result<()> {
+        let rpc_url = env::var("SUI_RPC_URL").expect("SUI_RPC_URL must be set");
+        // Derive the WebSocket URL from an https RPC URL unless one is given
+        let ws_url = env::var("SUI_WS_URL").ok().or_else(|| {
+            rpc_url
+                .starts_with("https://")
+                .then(|| rpc_url.replace("https://", "wss://"))
+        });
+        self.start_with_urls(&rpc_url, ws_url.as_deref(), shutdown)
+            .await
+    }
+
+    pub async fn start_with_urls(
+        &self,
+        rpc_url: &str,
+        ws_url: Option<&str>,
+        shutdown: Shutdown,
+    ) -> Result<()> {
+        self.start_with_config(rpc_url, ws_url, PipelineConfig::from_env(), shutdown)
+            .await
+    }
+
+    pub async fn start_with_config(
+        &self,
+        rpc_url: &str,
+        ws_url: Option<&str>,
+        config: PipelineConfig,
+        shutdown: Shutdown,
+    ) -> Result<()> {
+        info!("Starting indexer with RPC URL: {}", rpc_url);
+
+        // Build client with both HTTP and WebSocket URLs
+        let sui_client = if let Some(ws_url) = ws_url {
+            info!("Using WebSocket URL: {}", ws_url);
+            SuiClientBuilder::default()
+                .ws_url(ws_url)
+                .build(rpc_url)
+                .await?
+        } else {
+            // Fallback to HTTP-only client
+            info!("Using HTTP-only client");
+            SuiClientBuilder::default().build(rpc_url).await?
+        };
+
+        info!("Successfully connected to Sui client");
+        self.health.set_sui_client(sui_client.clone());
+
+        let mut indexer = self.clone();
+        if indexer.transactions.is_none() && env_or("FETCH_TRANSACTIONS", true) {
+            indexer.transactions = Some(Arc::new(sui_client.clone()));
+        }
+        let vesting = indexer
+            .vesting
+            .get_or_insert_with(|| Arc::new(sui_client.clone()))
+            .clone();
+        let fetched = vesting::fetch_missing_terms(&indexer.db, vesting.as_ref()).await?;
+        if fetched > 0 {
+            info!("Fetched vesting terms of {} launchpads", fetched);
+        }
+        ingestion::run(indexer, sui_client, config, shutdown).await
+    }
+
+    fn event_filter(&self) -> Result<EventFilter> {
+        Ok(EventFilter::MoveModule {
+            package: self.package_id,
+            module: "launchpad".parse()?,
+        })
+    }
+
+    pub fn db(&self) -> &Surreal<Any> {
+        &self.db
+    }
+
+    // Live feed of every event stored by this indexer
+    pub fn feed(&self) -> &Feed {
+        &self.feed
+    }
+
+    fn queue_depth(&self) -> Arc<QueueDepth> {
+        self.queue_depth.clone()
+    }
+}
+
+// Read an env var, falling back to `default` when it is unset or unparsable
+pub fn env_or<T: FromStr>(key: &str, default: T) -> T {
+    env::var(key)
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(default)
+}
@@ -1,499 +1,207 @@
 use anyhow::Result;
-use chrono::{DateTime, Utc};
+use chrono::Utc;
 use dotenv::dotenv;
-use log::{error, info, LevelFilter};
-use log4rs;
-use log4rs::append::file::FileAppender;
-use log4rs::config::{Appender, Config, Logger, Root as LogRoot};
+use file_rotate::{compression::Compression, suffix::AppendCount, ContentLimit, FileRotate};
+use indexer_new::pnl::PnlTracker;
+use indexer_new::webhooks::{WebhookConfig, Webhooks};
+use indexer_new::{api, auth, capture, env_or, shutdown, Indexer};
 use std::env;
-use sui_sdk::{
-    rpc_types::{SuiEvent, EventFilter},
-    types::base_types::ObjectID,
-    SuiClientBuilder,
-};
-use surrealdb::{
-    engine::remote::ws::{Client, Ws},
-    opt::auth::Root,
-    sql::Thing,
-    Surreal,
-};
-use serde::{Deserialize, Serialize};
-use futures::StreamExt;
-use bcs;
-
-// Event structs
-#[derive(Debug, Deserialize, Serialize)]
-struct TokensPurchased {
-    buyer: String,
-    amount: u64,
-    timestamp: u64,
-}
-
-#[derive(Debug, Deserialize, Serialize)]
-struct TokensTransferred {
-    from: String,
-    to: String,
-    amount: u64,
-    timestamp: u64,
-}
-
-#[derive(Debug, Deserialize, Serialize)]
-struct PriceUpdate {
-    new_price: u64,
-    tokens_sold: u64,
-    timestamp: u64,
-}
-
-#[derive(Debug, Deserialize, Serialize)]
-struct LiquidityDeployed {
-    launchpad_id: String,
-    sui_amount: u64,
-    timestamp: u64,
-}
-
-#[derive(Debug, Deserialize, Serialize)]
-struct PoolPaused {
-    launchpad_id: String,
-    timestamp: u64,
-}
-
-#[derive(Debug, Deserialize, Serialize)]
-struct PoolUnpaused {
-    launchpad_id: String,
-    timestamp: u64,
-}
-
-#[derive(Debug, Deserialize, Serialize)]
-struct LaunchpadCreated {
-    launchpad_id: String,
-    creator: String,
-    name: String,
-    description: String,
-    token_supply: u64,
-    initial_price: u64,
-    price_increment: u64,
-    website_url: String,
-    timestamp: u64,
-}
-
-#[derive(Debug, Deserialize, Serialize)]
-struct VestingClaimed {
-    user: String,
-    amount: u64,
-    timestamp: u64,
-}
-
-#[derive(Debug, Deserialize, Serialize)]
-struct FeeUpdated {
-    previous_fee: u64,
-    new_fee: u64,
-}
-
-#[derive(Debug, Deserialize, Serialize)]
-struct AdminTransferred {
-    previous_admin: String,
-    new_admin: String,
-}
-
-#[derive(Debug, Deserialize, Serialize)]
-struct BalanceUpdate {
-    launchpad_id: String,
-    holder: String,
-    balance: u64,
-    timestamp: u64,
-}
-
-// Database records
-#[derive(Debug, Serialize, Deserialize)]
-struct Transaction {
-    transaction_type: String,
-    timestamp: i64,
-    tx_digest: String,
-}
+use std::path::Path;
+use std::time::Duration;
+use tokio::sync::watch;
+use tracing::{error, info};
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::{fmt, prelude::*, EnvFilter};
+
+// Logging is configured through env vars:
+//   LOG_OUTPUT    file (default) or stdout
+//   LOG_FORMAT    text (default) or json
+//   LOG_DIR       directory for log files, default "logs"
+//   LOG_ROTATION  minutely, hourly, daily (default), size or never
+//   LOG_MAX_SIZE_MB size a file grows to before it is rotated, with size
+//                 rotation; default 100
+//   LOG_MAX_FILES rotated files to keep, default 7
+//   RUST_LOG      filter directives, overriding the defaults below
+//
+// The returned guard flushes buffered lines when dropped, so it must live
+// until the end of `main`.
+fn setup_logging() -> Result<WorkerGuard> {
+    let (writer, guard) = match env::var("LOG_OUTPUT").as_deref() {
+        Ok("stdout") => tracing_appender::non_blocking(std::io::stdout()),
+        _ if env::var("LOG_ROTATION").as_deref() == Ok("size") => {
+            // indexer.log, with older lines in indexer.log.1, .2, ...
+            let dir = env::var("LOG_DIR").unwrap_or_else(|_| "logs".to_string());
+            let max_bytes = env_or("LOG_MAX_SIZE_MB", 100usize) * 1024 * 1024;
+            std::fs::create_dir_all(&dir)?;
+            let appender = FileRotate::new(
+                Path::new(&dir).join("indexer.log"),
+                AppendCount::new(env_or("LOG_MAX_FILES", 7)),
+                ContentLimit::BytesSurpassed(max_bytes),
+                Compression::None,
+                #[cfg(unix)]
+                None,
+            );
+            tracing_appender::non_blocking(appender)
+        }
+        _ => {
+            let rotation = match env::var("LOG_ROTATION").as_deref() {
+                Ok("minutely") => Rotation::MINUTELY,
+                Ok("hourly") => Rotation::HOURLY,
+                Ok("never") => Rotation::NEVER,
+                _ => Rotation::DAILY,
+            };
+            let appender = RollingFileAppender::builder()
+                .rotation(rotation)
+                .filename_prefix("indexer")
+                .filename_suffix("log")
+                .max_log_files(env_or("LOG_MAX_FILES", 7))
+                .build(env::var("LOG_DIR").unwrap_or_else(|_| "logs".to_string()))?;
+            tracing_appender::non_blocking(appender)
+        }
+    };
 
-#[derive(Debug, Deserialize, Serialize)]
-struct Holder {
-    id: Option<Thing>,
-    wallet_address: String,
-    balance: u64,
-    last_updated: DateTime<Utc>,
-}
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| {
+        EnvFilter::new("info,tokio_tungstenite=warn,tungstenite=warn,hyper=warn")
+    });
+    let layer = fmt::layer().with_writer(writer).with_ansi(false);
+    let layer = match env::var("LOG_FORMAT").as_deref() {
+        Ok("json") => layer.json().boxed(),
+        _ => layer.boxed(),
+    };
 
-struct Indexer {
-    package_id: ObjectID,
-    db: Surreal<Client>,
+    // Also routes `log` records from dependencies into tracing
+    tracing_subscriber::registry()
+        .with(layer)
+        .with(filter)
+        .try_init()?;
+    Ok(guard)
 }
 
-impl Indexer {
-    async fn new(package_id: &str) -> Result<Self> {
-        info!("Initializing Indexer with package ID: {}", package_id);
-        
-        // Create database connection
-        let db = Surreal::new::<Ws>("127.0.0.1:8000").await?;
-        db.signin(Root {
-            username: "root",
-            password: "root",
-        })
-        .await?;
-        db.use_ns("sui").use_db("launchpad").await?;
-
-        // Create tables if they don't exist
-        db.query("DEFINE TABLE token_purchases SCHEMAFULL").await?;
-        db.query("DEFINE FIELD buyer ON token_purchases TYPE string").await?;
-        db.query("DEFINE FIELD amount ON token_purchases TYPE number").await?;
-        db.query("DEFINE FIELD timestamp ON token_purchases TYPE number").await?;
-        db.query("DEFINE FIELD tx_digest ON token_purchases TYPE string").await?;
-
-        db.query("DEFINE TABLE token_transfers SCHEMAFULL").await?;
-        db.query("DEFINE FIELD from ON token_transfers TYPE string").await?;
-        db.query("DEFINE FIELD to ON token_transfers TYPE string").await?;
-        db.query("DEFINE FIELD amount ON token_transfers TYPE number").await?;
-        db.query("DEFINE FIELD timestamp ON token_transfers TYPE number").await?;
-        db.query("DEFINE FIELD tx_digest ON token_transfers TYPE string").await?;
-
-        db.query("DEFINE TABLE price_updates SCHEMAFULL").await?;
-        db.query("DEFINE FIELD new_price ON price_updates TYPE number").await?;
-        db.query("DEFINE FIELD tokens_sold ON price_updates TYPE number").await?;
-        db.query("DEFINE FIELD timestamp ON price_updates TYPE number").await?;
-        db.query("DEFINE FIELD tx_digest ON price_updates TYPE string").await?;
-
-        db.query("DEFINE TABLE liquidity_deployments SCHEMAFULL").await?;
-        db.query("DEFINE FIELD launchpad_id ON liquidity_deployments TYPE string").await?;
-        db.query("DEFINE FIELD sui_amount ON liquidity_deployments TYPE number").await?;
-        db.query("DEFINE FIELD timestamp ON liquidity_deployments TYPE number").await?;
-        db.query("DEFINE FIELD tx_digest ON liquidity_deployments TYPE string").await?;
-
-        db.query("DEFINE TABLE pool_pauses SCHEMAFULL").await?;
-        db.query("DEFINE FIELD launchpad_id ON pool_pauses TYPE string").await?;
-        db.query("DEFINE FIELD timestamp ON pool_pauses TYPE number").await?;
-        db.query("DEFINE FIELD tx_digest ON pool_pauses TYPE string").await?;
-
-        db.query("DEFINE TABLE pool_unpauses SCHEMAFULL").await?;
-        db.query("DEFINE FIELD launchpad_id ON pool_unpauses TYPE string").await?;
-        db.query("DEFINE FIELD timestamp ON pool_unpauses TYPE number").await?;
-        db.query("DEFINE FIELD tx_digest ON pool_unpauses TYPE string").await?;
-
-        db.query("DEFINE TABLE launchpads SCHEMAFULL").await?;
-        db.query("DEFINE FIELD launchpad_id ON launchpads TYPE string").await?;
-        db.query("DEFINE FIELD creator ON launchpads TYPE string").await?;
-        db.query("DEFINE FIELD name ON launchpads TYPE string").await?;
-        db.query("DEFINE FIELD description ON launchpads TYPE string").await?;
-        db.query("DEFINE FIELD token_supply ON launchpads TYPE number").await?;
-        db.query("DEFINE FIELD initial_price ON launchpads TYPE number").await?;
-        db.query("DEFINE FIELD price_increment ON launchpads TYPE number").await?;
-        db.query("DEFINE FIELD website_url ON launchpads TYPE string").await?;
-        db.query("DEFINE FIELD timestamp ON launchpads TYPE number").await?;
-        db.query("DEFINE FIELD tx_digest ON launchpads TYPE string").await?;
-
-        db.query("DEFINE TABLE vesting_claims SCHEMAFULL").await?;
-        db.query("DEFINE FIELD user ON vesting_claims TYPE string").await?;
-        db.query("DEFINE FIELD amount ON vesting_claims TYPE number").await?;
-        db.query("DEFINE FIELD timestamp ON vesting_claims TYPE number").await?;
-        db.query("DEFINE FIELD tx_digest ON vesting_claims TYPE string").await?;
-
-        db.query("DEFINE TABLE fee_updates SCHEMAFULL").await?;
-        db.query("DEFINE FIELD previous_fee ON fee_updates TYPE number").await?;
-        db.query("DEFINE FIELD new_fee ON fee_updates TYPE number").await?;
-        db.query("DEFINE FIELD tx_digest ON fee_updates TYPE string").await?;
-
-        db.query("DEFINE TABLE admin_transfers SCHEMAFULL").await?;
-        db.query("DEFINE FIELD previous_admin ON admin_transfers TYPE string").await?;
-        db.query("DEFINE FIELD new_admin ON admin_transfers TYPE string").await?;
-        db.query("DEFINE FIELD tx_digest ON admin_transfers TYPE string").await?;
+#[tokio::main]
+async fn main() -> Result<()> {
+    dotenv().ok();
+    let log_guard = setup_logging()?;
 
-        db.query("DEFINE TABLE balance_updates SCHEMAFULL").await?;
-        db.query("DEFINE FIELD launchpad_id ON balance_updates TYPE string").await?;
-        db.query("DEFINE FIELD holder ON balance_updates TYPE string").await?;
-        db.query("DEFINE FIELD balance ON balance_updates TYPE number").await?;
-        db.query("DEFINE FIELD timestamp ON balance_updates TYPE number").await?;
-        db.query("DEFINE FIELD tx_digest ON balance_updates TYPE string").await?;
+    let package_id = env::var("PACKAGE_ID").expect("PACKAGE_ID must be set");
 
-        Ok(Self {
-            package_id: ObjectID::from_hex_literal(package_id)?,
-            db,
-        })
+    // `replay <capture.jsonl> [database]` re-indexes a capture file into a
+    // fresh database instead of following the chain
+    let args: Vec<String> = env::args().collect();
+    if args.get(1).map(String::as_str) == Some("replay") {
+        let path = args
+            .get(2)
+            .expect("usage: indexer-new replay <capture.jsonl> [database]");
+        let database = args
+            .get(3)
+            .cloned()
+            .unwrap_or_else(|| format!("replay_{}", Utc::now().format("%Y%m%d%H%M%S")));
+        info!("Replaying {} into database {}", path, database);
+        let indexer = Indexer::connect(&package_id, &database).await?;
+        let summary = capture::replay(&indexer, path).await?;
+        println!(
+            "Replayed {} events into database {} ({} failed)",
+            summary.events, database, summary.failed
+        );
+        return Ok(());
     }
 
-    async fn handle_event(&mut self, event: SuiEvent) -> Result<()> {
-        let timestamp = event.timestamp_ms;
-        let tx_digest = event.id.tx_digest.to_string();
-        let package_id = self.package_id.to_string();
-
-        match event.type_.to_string().as_str() {
-            event_type if event_type == format!("{}::launchpad::TokensPurchased", package_id) => {
-                let purchase: TokensPurchased = bcs::from_bytes(&event.bcs)?;
-                let _created: Vec<TokensPurchased> = self.db
-                    .query("CREATE token_purchases SET buyer = $buyer, amount = $amount, timestamp = $timestamp, tx_digest = $tx_digest")
-                    .bind(("buyer", purchase.buyer))
-                    .bind(("amount", purchase.amount))
-                    .bind(("timestamp", timestamp))
-                    .bind(("tx_digest", tx_digest))
-                    .await?
-                    .take(0)?;
-            }
-            event_type if event_type == format!("{}::launchpad::TokensTransferred", package_id) => {
-                let transfer: TokensTransferred = bcs::from_bytes(&event.bcs)?;
-                let _created: Vec<TokensTransferred> = self.db
-                    .query("CREATE token_transfers SET from = $from, to = $to, amount = $amount, timestamp = $timestamp, tx_digest = $tx_digest")
-                    .bind(("from", transfer.from))
-                    .bind(("to", transfer.to))
-                    .bind(("amount", transfer.amount))
-                    .bind(("timestamp", timestamp))
-                    .bind(("tx_digest", tx_digest))
-                    .await?
-                    .take(0)?;
-            }
-            event_type if event_type == format!("{}::launchpad::PriceUpdate", package_id) => {
-                let update: PriceUpdate = bcs::from_bytes(&event.bcs)?;
-                let _created: Vec<PriceUpdate> = self.db
-                    .query("CREATE price_updates SET new_price = $new_price, tokens_sold = $tokens_sold, timestamp = $timestamp, tx_digest = $tx_digest")
-                    .bind(("new_price", update.new_price))
-                    .bind(("tokens_sold", update.tokens_sold))
-                    .bind(("timestamp", timestamp))
-                    .bind(("tx_digest", tx_digest))
-                    .await?
-                    .take(0)?;
-            }
-            event_type if event_type == format!("{}::launchpad::LiquidityDeployed", package_id) => {
-                let deploy: LiquidityDeployed = bcs::from_bytes(&event.bcs)?;
-                let _created: Vec<LiquidityDeployed> = self.db
-                    .query("CREATE liquidity_deployments SET launchpad_id = $launchpad_id, sui_amount = $sui_amount, timestamp = $timestamp, tx_digest = $tx_digest")
-                    .bind(("launchpad_id", deploy.launchpad_id))
-                    .bind(("sui_amount", deploy.sui_amount))
-                    .bind(("timestamp", timestamp))
-                    .bind(("tx_digest", tx_digest))
-                    .await?
-                    .take(0)?;
+    // `keys create <name> [requests per minute] [--admin]`, `keys revoke <id>`
+    // and `keys list` manage API keys. Admin keys can also use the
+    // management routes.
+    if args.get(1).map(String::as_str) == Some("keys") {
+        let indexer = Indexer::new(&package_id).await?;
+        let db = indexer.db();
+        match (args.get(2).map(String::as_str), args.get(3)) {
+            (Some("create"), Some(name)) => {
+                let options = &args[4..];
+                let admin = options.iter().any(|o| o == "--admin");
+                let rate = options
+                    .iter()
+                    .find(|o| *o != "--admin")
+                    .map(|r| r.parse())
+                    .transpose()?;
+                let (key, secret) = auth::create_key(db, name, rate, admin).await?;
+                println!("Created API key {} ({})", key.id, key.name);
+                println!("{}", secret);
             }
-            event_type if event_type == format!("{}::launchpad::PoolPaused", package_id) => {
-                let pause: PoolPaused = bcs::from_bytes(&event.bcs)?;
-                let _created: Vec<PoolPaused> = self.db
-                    .query("CREATE pool_pauses SET launchpad_id = $launchpad_id, timestamp = $timestamp, tx_digest = $tx_digest")
-                    .bind(("launchpad_id", pause.launchpad_id))
-                    .bind(("timestamp", timestamp))
-                    .bind(("tx_digest", tx_digest))
-                    .await?
-                    .take(0)?;
-            }
-            event_type if event_type == format!("{}::launchpad::PoolUnpaused", package_id) => {
-                let unpause: PoolUnpaused = bcs::from_bytes(&event.bcs)?;
-                let _created: Vec<PoolUnpaused> = self.db
-                    .query("CREATE pool_unpauses SET launchpad_id = $launchpad_id, timestamp = $timestamp, tx_digest = $tx_digest")
-                    .bind(("launchpad_id", unpause.launchpad_id))
-                    .bind(("timestamp", timestamp))
-                    .bind(("tx_digest", tx_digest))
-                    .await?
-                    .take(0)?;
-            }
-            event_type if event_type == format!("{}::launchpad::LaunchpadCreated", package_id) => {
-                let launchpad: LaunchpadCreated = bcs::from_bytes(&event.bcs)?;
-                let _created: Vec<LaunchpadCreated> = self.db
-                    .query("CREATE launchpads SET launchpad_id = $launchpad_id, creator = $creator, name = $name, description = $description, token_supply = $token_supply, initial_price = $initial_price, price_increment = $price_increment, website_url = $website_url, timestamp = $timestamp, tx_digest = $tx_digest")
-                    .bind(("launchpad_id", launchpad.launchpad_id))
-                    .bind(("creator", launchpad.creator))
-                    .bind(("name", launchpad.name))
-                    .bind(("description", launchpad.description))
-                    .bind(("token_supply", launchpad.token_supply))
-                    .bind(("initial_price", launchpad.initial_price))
-                    .bind(("price_increment", launchpad.price_increment))
-                    .bind(("website_url", launchpad.website_url))
-                    .bind(("timestamp", timestamp))
-                    .bind(("tx_digest", tx_digest))
-                    .await?
-                    .take(0)?;
-            }
-            event_type if event_type == format!("{}::launchpad::VestingClaimed", package_id) => {
-                let claim: VestingClaimed = bcs::from_bytes(&event.bcs)?;
-                let _created: Vec<VestingClaimed> = self.db
-                    .query("CREATE vesting_claims SET user = $user, amount = $amount, timestamp = $timestamp, tx_digest = $tx_digest")
-                    .bind(("user", claim.user))
-                    .bind(("amount", claim.amount))
-                    .bind(("timestamp", timestamp))
-                    .bind(("tx_digest", tx_digest))
-                    .await?
-                    .take(0)?;
-            }
-            event_type if event_type == format!("{}::launchpad::FeeUpdated", package_id) => {
-                let fee: FeeUpdated = bcs::from_bytes(&event.bcs)?;
-                let _created: Vec<FeeUpdated> = self.db
-                    .query("CREATE fee_updates SET previous_fee = $previous_fee, new_fee = $new_fee, tx_digest = $tx_digest")
-                    .bind(("previous_fee", fee.previous_fee))
-                    .bind(("new_fee", fee.new_fee))
-                    .bind(("tx_digest", tx_digest))
-                    .await?
-                    .take(0)?;
-            }
-            event_type if event_type == format!("{}::launchpad::AdminTransferred", package_id) => {
-                let transfer: AdminTransferred = bcs::from_bytes(&event.bcs)?;
-                let _created: Vec<AdminTransferred> = self.db
-                    .query("CREATE admin_transfers SET previous_admin = $previous_admin, new_admin = $new_admin, tx_digest = $tx_digest")
-                    .bind(("previous_admin", transfer.previous_admin))
-                    .bind(("new_admin", transfer.new_admin))
-                    .bind(("tx_digest", tx_digest))
-                    .await?
-                    .take(0)?;
-            }
-            event_type if event_type == format!("{}::launchpad::BalanceUpdate", package_id) => {
-                let update: BalanceUpdate = bcs::from_bytes(&event.bcs)?;
-                let _created: Vec<BalanceUpdate> = self.db
-                    .query("CREATE balance_updates SET launchpad_id = $launchpad_id, holder = $holder, balance = $balance, timestamp = $timestamp, tx_digest = $tx_digest")
-                    .bind(("launchpad_id", update.launchpad_id))
-                    .bind(("holder", update.holder))
-                    .bind(("balance", update.balance))
-                    .bind(("timestamp", timestamp))
-                    .bind(("tx_digest", tx_digest))
-                    .await?
-                    .take(0)?;
+            (Some("revoke"), Some(id)) => {
+                if auth::revoke_key(db, id).await? {
+                    println!("Revoked API key {}", id);
+                } else {
+                    println!("No active API key {}", id);
+                }
             }
-            _ => {
-                error!("Unknown event type: {}", event.type_);
+            (Some("list"), _) => {
+                for key in auth::keys(db).await? {
+                    let rate = key
+                        .rate_per_minute
+                        .map_or("default".to_string(), |r| format!("{}/min", r));
+                    let status = if key.revoked_at.is_some() {
+                        "revoked"
+                    } else {
+                        "active"
+                    };
+                    let scope = if key.admin { "admin" } else { "read" };
+                    println!(
+                        "{}\t{}\t{}...\t{}\t{}\t{}\t{} requests\t{} rejected",
+                        key.id,
+                        key.name,
+                        key.prefix,
+                        scope,
+                        rate,
+                        status,
+                        key.requests,
+                        key.rejected
+                    );
+                }
             }
+            _ => eprintln!(
+                "usage: indexer-new keys create <name> [requests per minute] [--admin] \
+                 | revoke <id> | list"
+            ),
         }
-        Ok(())
+        return Ok(());
     }
 
-    async fn start(&mut self) -> Result<()> {
-        let rpc_url = env::var("SUI_RPC_URL").expect("SUI_RPC_URL must be set");
-        info!("Starting indexer with RPC URL: {}", rpc_url);
-
-        // Build client with both HTTP and WebSocket URLs
-        let sui_client = if rpc_url.starts_with("https://") {
-            let ws_url = rpc_url.replace("https://", "wss://");
-            info!("Using WebSocket URL: {}", ws_url);
-            SuiClientBuilder::default()
-                .ws_url(&ws_url)
-                .build(&rpc_url)
-                .await?
-        } else {
-            // Fallback to HTTP-only client
-            info!("Using HTTP-only client");
-            SuiClientBuilder::default()
-                .build(&rpc_url)
-                .await?
-        };
+    let indexer = Indexer::new(&package_id).await?;
+    let webhooks = Webhooks::start(indexer.db().clone(), WebhookConfig::from_env()).await?;
+    let pnl = PnlTracker::start(indexer.db().clone()).await?;
+    let indexer = indexer.with_handler(webhooks).with_handler(pnl);
 
-        info!("Successfully connected to Sui client");
+    let (shutdown_tx, shutdown) = watch::channel(false);
+    let deadline = Duration::from_secs(env_or("SHUTDOWN_TIMEOUT_SECS", 30));
 
-        // Try WebSocket subscription first
-        match sui_client
-            .event_api()
-            .subscribe_event(EventFilter::MoveModule {
-                package: self.package_id,
-                module: "launchpad".parse()?,
-            })
-            .await
-        {
-            Ok(mut subscribe_all) => {
-                info!("Successfully subscribed to events via WebSocket");
-                while let Some(event) = subscribe_all.next().await {
-                    match event {
-                        Ok(event) => {
-                            if let Err(e) = self.handle_event(event).await {
-                                error!("Failed to handle event: {}", e);
-                            }
-                        }
-                        Err(e) => {
-                            error!("Error receiving event: {}", e);
-                            break; // Exit WebSocket loop on error
-                        }
-                    }
-                }
-            }
-            Err(e) => {
-                error!("Failed to subscribe via WebSocket: {}. Falling back to polling.", e);
-                // Fallback to polling
-                let mut cursor = None;
-                loop {
-                    // Query events using regular HTTP API
-                    match sui_client
-                        .event_api()
-                        .query_events(EventFilter::MoveModule {
-                            package: self.package_id,
-                            module: "launchpad".parse()?,
-                        }, cursor, None, false)
-                        .await
-                    {
-                        Ok(event_page) => {
-                            for event in event_page.data {
-                                if let Err(e) = self.handle_event(event).await {
-                                    error!("Failed to handle event: {}", e);
-                                }
-                            }
-                            cursor = event_page.next_cursor;
-                        }
-                        Err(e) => {
-                            error!("Failed to poll events: {}", e);
-                        }
-                    }
+    let server = tokio::spawn(api::serve(indexer.clone(), shutdown.clone()));
 
-                    // Sleep before next poll
-                    let poll_interval = env::var("POLL_INTERVAL_MS")
-                        .ok()
-                        .and_then(|s| s.parse().ok())
-                        .unwrap_or(1000);
-                    tokio::time::sleep(std::time::Duration::from_millis(poll_interval)).await;
+    // Start indexing
+    let ingest_indexer = indexer.clone();
+    let mut ingest = tokio::spawn(async move { ingest_indexer.start(shutdown).await });
+
+    let result = tokio::select! {
+        result = &mut ingest => result?,
+        _ = shutdown::termination_signal() => {
+            info!("Shutdown requested, draining pipeline (deadline {:?})", deadline);
+            let _ = shutdown_tx.send(true);
+            match tokio::time::timeout(deadline, &mut ingest).await {
+                Ok(result) => result?,
+                Err(_) => {
+                    error!("Pipeline did not drain within {:?}, exiting", deadline);
+                    // `exit` skips destructors; flush the log lines first
+                    drop(log_guard);
+                    std::process::exit(1);
                 }
             }
         }
+    };
 
-        Ok(())
-    }
-
-    // Helper functions to query the database
-    async fn get_holder_balance(&self, wallet_address: &str) -> Result<Option<u64>> {
-        let holder: Option<Holder> = self.db
-            .select(("holders", wallet_address))
-            .await?;
-        Ok(holder.map(|h| h.balance))
+    let _ = shutdown_tx.send(true);
+    if let Err(e) = server.await? {
+        error!("HTTP server stopped: {}", e);
     }
 
-    async fn get_transactions(&self, wallet_address: &str) -> Result<Vec<Transaction>> {
-        let transactions: Vec<Transaction> = self.db
-            .query("SELECT transaction_type, timestamp, tx_digest FROM transactions WHERE wallet_address = $address ORDER BY timestamp DESC")
-            .bind(("address", wallet_address.to_string()))
-            .await?
-            .take(0)?;
-        Ok(transactions)
-    }
-}
-
-fn setup_logging() -> Result<()> {
-    let appender = FileAppender::builder()
-        .build("indexer.log")?;
-
-    let config = Config::builder()
-        .appender(Appender::builder().build("file", Box::new(appender)))
-        .logger(Logger::builder()
-            .appender("file")
-            .additive(false)
-            .build("indexer_new", LevelFilter::Info))
-        .logger(Logger::builder()
-            .appender("file")
-            .additive(false)
-            .build("tokio_tungstenite", LevelFilter::Warn))
-        .logger(Logger::builder()
-            .appender("file")
-            .additive(false)
-            .build("tungstenite", LevelFilter::Warn))
-        .logger(Logger::builder()
-            .appender("file")
-            .additive(false)
-            .build("hyper", LevelFilter::Warn))
-        .build(LogRoot::builder().appender("file").build(LevelFilter::Info))?;
-
-    log4rs::init_config(config)?;
-    Ok(())
-}
-
-#[tokio::main]
-async fn main() -> Result<()> {
-    dotenv().ok();
-    setup_logging()?;
-
-    let package_id = env::var("PACKAGE_ID").expect("PACKAGE_ID must be set");
-    let mut indexer = Indexer::new(&package_id).await?;
-    
-    // Start indexing
-    indexer.start().await?;
-    Ok(())
+    // Dropping the last handle closes the SurrealDB connection
+    drop(indexer);
+    info!("Database connection closed, exiting");
+    result
 }
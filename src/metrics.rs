@@ -0,0 +1,207 @@
+use anyhow::Result;
+use prometheus::{
+    Encoder, Gauge, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge, IntGaugeVec,
+    Opts, Registry, TextEncoder,
+};
+use std::time::Duration;
+
+use crate::ingestion::QueueDepth;
+
+// How events are currently reaching the indexer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IngestMode {
+    WebSocket,
+    Polling,
+}
+
+impl IngestMode {
+    fn label(self) -> &'static str {
+        match self {
+            IngestMode::WebSocket => "websocket",
+            IngestMode::Polling => "polling",
+        }
+    }
+}
+
+pub struct Metrics {
+    registry: Registry,
+    events_indexed: IntCounterVec,
+    decode_failures: IntCounter,
+    db_write_seconds: HistogramVec,
+    rpc_errors: IntCounterVec,
+    handler_failures: IntCounterVec,
+    last_event_timestamp: Gauge,
+    latest_checkpoint: IntGauge,
+    processed_checkpoint: IntGauge,
+    checkpoint_lag: IntGauge,
+    ingest_mode: IntGaugeVec,
+    queue_depth: IntGaugeVec,
+}
+
+impl Metrics {
+    pub fn new() -> Result<Self> {
+        let registry = Registry::new_custom(Some("indexer".to_string()), None)?;
+
+        let events_indexed = IntCounterVec::new(
+            Opts::new(
+                "events_indexed_total",
+                "Events written to the database, by event type",
+            ),
+            &["event_type"],
+        )?;
+        let decode_failures = IntCounter::new(
+            "decode_failures_total",
+            "Events that could not be BCS-decoded",
+        )?;
+        let db_write_seconds = HistogramVec::new(
+            HistogramOpts::new(
+                "db_write_seconds",
+                "Latency of database writes, by event type",
+            ),
+            &["event_type"],
+        )?;
+        let rpc_errors = IntCounterVec::new(
+            Opts::new(
+                "rpc_errors_total",
+                "Errors returned by the Sui RPC, by operation",
+            ),
+            &["operation"],
+        )?;
+        let handler_failures = IntCounterVec::new(
+            Opts::new(
+                "handler_failures_total",
+                "Failed event handler calls, by handler",
+            ),
+            &["handler"],
+        )?;
+        let last_event_timestamp = Gauge::new(
+            "last_event_timestamp_seconds",
+            "On-chain timestamp of the most recently indexed event",
+        )?;
+        let latest_checkpoint = IntGauge::new(
+            "latest_checkpoint",
+            "Latest checkpoint reported by the node",
+        )?;
+        let processed_checkpoint = IntGauge::new(
+            "processed_checkpoint",
+            "Checkpoint of the most recently indexed event, or the latest one once caught up",
+        )?;
+        let checkpoint_lag = IntGauge::new(
+            "checkpoint_lag",
+            "Checkpoints between the latest one and the last processed one",
+        )?;
+        let ingest_mode = IntGaugeVec::new(
+            Opts::new(
+                "ingest_mode",
+                "1 for the active ingestion mode, 0 otherwise",
+            ),
+            &["mode"],
+        )?;
+        let queue_depth = IntGaugeVec::new(
+            Opts::new("queue_depth", "Items waiting between pipeline stages"),
+            &["stage"],
+        )?;
+
+        registry.register(Box::new(events_indexed.clone()))?;
+        registry.register(Box::new(decode_failures.clone()))?;
+        registry.register(Box::new(db_write_seconds.clone()))?;
+        registry.register(Box::new(rpc_errors.clone()))?;
+        registry.register(Box::new(handler_failures.clone()))?;
+        registry.register(Box::new(last_event_timestamp.clone()))?;
+        registry.register(Box::new(latest_checkpoint.clone()))?;
+        registry.register(Box::new(processed_checkpoint.clone()))?;
+        registry.register(Box::new(checkpoint_lag.clone()))?;
+        registry.register(Box::new(ingest_mode.clone()))?;
+        registry.register(Box::new(queue_depth.clone()))?;
+
+        Ok(Self {
+            registry,
+            events_indexed,
+            decode_failures,
+            db_write_seconds,
+            rpc_errors,
+            handler_failures,
+            last_event_timestamp,
+            latest_checkpoint,
+            processed_checkpoint,
+            checkpoint_lag,
+            ingest_mode,
+            queue_depth,
+        })
+    }
+
+    pub fn record_write(&self, event_type: &str, elapsed: Duration, timestamp_ms: Option<u64>) {
+        self.events_indexed.with_label_values(&[event_type]).inc();
+        self.db_write_seconds
+            .with_label_values(&[event_type])
+            .observe(elapsed.as_secs_f64());
+        if let Some(timestamp_ms) = timestamp_ms {
+            self.last_event_timestamp.set(timestamp_ms as f64 / 1000.0);
+        }
+    }
+
+    pub fn record_decode_failure(&self) {
+        self.decode_failures.inc();
+    }
+
+    pub fn record_rpc_error(&self, operation: &str) {
+        self.rpc_errors.with_label_values(&[operation]).inc();
+    }
+
+    pub fn record_handler_failure(&self, handler: &str) {
+        self.handler_failures.with_label_values(&[handler]).inc();
+    }
+
+    pub fn set_mode(&self, mode: IngestMode) {
+        for candidate in [IngestMode::WebSocket, IngestMode::Polling] {
+            self.ingest_mode
+                .with_label_values(&[candidate.label()])
+                .set((candidate == mode) as i64);
+        }
+    }
+
+    pub fn mode(&self) -> Option<IngestMode> {
+        [IngestMode::WebSocket, IngestMode::Polling]
+            .into_iter()
+            .find(|mode| self.ingest_mode.with_label_values(&[mode.label()]).get() == 1)
+    }
+
+    pub fn set_latest_checkpoint(&self, checkpoint: u64) {
+        self.latest_checkpoint.set(checkpoint as i64);
+    }
+
+    // Checkpoints only move forward; events persisted out of order across
+    // workers don't pull the gauge back
+    pub fn record_checkpoint(&self, checkpoint: u64) {
+        let checkpoint = checkpoint as i64;
+        if checkpoint > self.processed_checkpoint.get() {
+            self.processed_checkpoint.set(checkpoint);
+        }
+    }
+
+    // Checkpoints the indexer is behind the node, once the node has been asked
+    pub fn checkpoint_lag(&self) -> Option<u64> {
+        let latest = self.latest_checkpoint.get();
+        if latest == 0 {
+            return None;
+        }
+        Some((latest - self.processed_checkpoint.get()).max(0) as u64)
+    }
+
+    // Render every metric in the Prometheus text format
+    pub fn render(&self, queue_depth: &QueueDepth) -> Result<String> {
+        if let Some(lag) = self.checkpoint_lag() {
+            self.checkpoint_lag.set(lag as i64);
+        }
+        self.queue_depth
+            .with_label_values(&["fetched"])
+            .set(queue_depth.fetched() as i64);
+        self.queue_depth
+            .with_label_values(&["decoded"])
+            .set(queue_depth.decoded() as i64);
+
+        let mut buffer = Vec::new();
+        TextEncoder::new().encode(&self.registry.gather(), &mut buffer)?;
+        Ok(String::from_utf8(buffer)?)
+    }
+}
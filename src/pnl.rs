@@ -0,0 +1,537 @@
+//! Cost basis and profit and loss per (wallet, launchpad).
+//!
+//! [`PnlTracker`] updates positions as events are stored. Purchases add
+//! tokens at the price in effect before them, transfers in add tokens at the
+//! price in effect then, and transfers out dispose of tokens at that price,
+//! realizing the difference to their cost basis. The basis is tracked both
+//! first-in-first-out and as an average cost. Tokens disposed beyond the
+//! tracked quantity, e.g. ones claimed from vesting, count as acquired at no
+//! cost. Unrealized PnL compares the remaining basis with the latest price
+//! when queried.
+//!
+//! Trades are attributed to a launchpad through the BalanceUpdate of their
+//! transaction, which may be stored after them. Nothing is held in memory:
+//! whenever a trade or BalanceUpdate of a transaction whose launchpad is
+//! known is handled, every stored trade of that transaction not yet in
+//! `pnl_applied` is applied, and starting the tracker applies whatever the
+//! stored history still has outstanding, a page of trades at a time. A trade
+//! and its `pnl_applied` row are written in one transaction, so a failure
+//! leaves the trade to be applied next time.
+//!
+//! Quantities and costs saturate at `i64::MAX`, the largest integer the
+//! database stores.
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use surrealdb::{engine::any::Any, Surreal};
+use tokio::sync::Mutex;
+use tracing::info;
+use utoipa::ToSchema;
+
+use crate::decoding::EventMetadata;
+use crate::events::LaunchpadEvent;
+use crate::handlers::EventHandler;
+use crate::storage;
+
+/// PnL of one wallet in one launchpad.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct Pnl {
+    pub wallet: String,
+    pub launchpad_id: String,
+    /// Tokens currently held, as tracked from trades.
+    pub quantity: u64,
+    /// Latest `PriceUpdate.new_price`, if the launchpad has traded.
+    pub current_price: Option<u64>,
+    pub fifo: Basis,
+    pub average: Basis,
+}
+
+/// PnL under one cost basis method, in the price's units.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct Basis {
+    /// Cost of the tokens still held.
+    pub cost_basis: u64,
+    pub realized: i64,
+    /// Value at the current price less the cost basis.
+    pub unrealized: Option<i64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Lot {
+    amount: u64,
+    price: u64,
+}
+
+// Stored state of a position
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct Holding {
+    wallet: String,
+    launchpad_id: String,
+    quantity: u64,
+    // Oldest first
+    fifo_lots: Vec<Lot>,
+    fifo_realized: i64,
+    // Total cost of the quantity held
+    average_cost: u64,
+    average_realized: i64,
+    updated_at: u64,
+}
+
+// Stored trades read per query while catching up
+const CATCH_UP_PAGE: usize = 500;
+
+// Largest quantity or cost that can be stored
+const MAX_STORED: u64 = i64::MAX as u64;
+
+fn clamp(value: i128) -> i64 {
+    value.clamp(i64::MIN.into(), i64::MAX.into()) as i64
+}
+
+impl Holding {
+    fn acquire(&mut self, amount: u64, price: u64) {
+        self.quantity = self.quantity.saturating_add(amount).min(MAX_STORED);
+        self.fifo_lots.push(Lot { amount, price });
+        self.average_cost = self
+            .average_cost
+            .saturating_add(amount.saturating_mul(price))
+            .min(MAX_STORED);
+    }
+
+    fn dispose(&mut self, amount: u64, price: u64) {
+        let proceeds = i128::from(amount) * i128::from(price);
+
+        let mut left = amount;
+        let mut fifo_basis = 0i128;
+        while left > 0 {
+            let Some(lot) = self.fifo_lots.first_mut() else {
+                break;
+            };
+            let taken = left.min(lot.amount);
+            fifo_basis += i128::from(taken) * i128::from(lot.price);
+            lot.amount -= taken;
+            left -= taken;
+            if lot.amount == 0 {
+                self.fifo_lots.remove(0);
+            }
+        }
+        self.fifo_realized = clamp(i128::from(self.fifo_realized) + proceeds - fifo_basis);
+
+        let held = amount.min(self.quantity);
+        let average_basis = if self.quantity == 0 {
+            0
+        } else {
+            (u128::from(self.average_cost) * u128::from(held) / u128::from(self.quantity)) as u64
+        };
+        self.average_cost = self.average_cost.saturating_sub(average_basis);
+        self.average_realized =
+            clamp(i128::from(self.average_realized) + proceeds - i128::from(average_basis));
+        self.quantity = self.quantity.saturating_sub(held);
+    }
+
+    fn fifo_cost(&self) -> u64 {
+        self.fifo_lots
+            .iter()
+            .map(|lot| lot.amount.saturating_mul(lot.price))
+            .fold(0, u64::saturating_add)
+            .min(MAX_STORED)
+    }
+
+    fn pnl(self, current_price: Option<u64>) -> Pnl {
+        let value = current_price.map(|price| i128::from(price) * i128::from(self.quantity));
+        let fifo_cost = self.fifo_cost();
+        Pnl {
+            fifo: Basis {
+                cost_basis: fifo_cost,
+                realized: self.fifo_realized,
+                unrealized: value.map(|v| clamp(v - i128::from(fifo_cost))),
+            },
+            average: Basis {
+                cost_basis: self.average_cost,
+                realized: self.average_realized,
+                unrealized: value.map(|v| clamp(v - i128::from(self.average_cost))),
+            },
+            wallet: self.wallet,
+            launchpad_id: self.launchpad_id,
+            quantity: self.quantity,
+            current_price,
+        }
+    }
+}
+
+async fn stored(db: &Surreal<Any>, wallet: &str, launchpad_id: &str) -> Result<Option<Holding>> {
+    let stored = db
+        .query("SELECT * OMIT id FROM type::thing('pnl_positions', [$wallet, $launchpad_id])")
+        .bind(("wallet", wallet.to_string()))
+        .bind(("launchpad_id", launchpad_id.to_string()))
+        .await?
+        .take(0)?;
+    Ok(stored)
+}
+
+// The stored position, or an empty one
+async fn holding(db: &Surreal<Any>, wallet: &str, launchpad_id: &str) -> Result<Holding> {
+    let stored = stored(db, wallet, launchpad_id).await?;
+    Ok(stored.unwrap_or_else(|| Holding {
+        wallet: wallet.to_string(),
+        launchpad_id: launchpad_id.to_string(),
+        ..Holding::default()
+    }))
+}
+
+/// PnL of `wallet` in every launchpad it traded.
+pub async fn wallet_pnl(db: &Surreal<Any>, wallet: &str) -> Result<Vec<Pnl>> {
+    let holdings: Vec<Holding> = db
+        .query("SELECT * OMIT id FROM pnl_positions WHERE wallet = $wallet ORDER BY launchpad_id")
+        .bind(("wallet", wallet.to_string()))
+        .await?
+        .take(0)?;
+    let mut pnl = Vec::with_capacity(holdings.len());
+    for holding in holdings {
+        let price = storage::latest_price(db, &holding.launchpad_id).await?;
+        pnl.push(holding.pnl(price));
+    }
+    Ok(pnl)
+}
+
+/// PnL of `wallet` in one launchpad, if it traded there.
+pub async fn pnl(db: &Surreal<Any>, wallet: &str, launchpad_id: &str) -> Result<Option<Pnl>> {
+    match stored(db, wallet, launchpad_id).await? {
+        Some(holding) => {
+            let price = storage::latest_price(db, launchpad_id).await?;
+            Ok(Some(holding.pnl(price)))
+        }
+        None => Ok(None),
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Trade {
+    Buy {
+        wallet: String,
+        amount: u64,
+    },
+    Transfer {
+        from: String,
+        to: String,
+        amount: u64,
+    },
+}
+
+// A trade as stored in `token_purchases` or `token_transfers`
+#[derive(Debug, Clone)]
+struct StoredTrade {
+    tx_digest: String,
+    event_seq: u64,
+    // The checkpoint time when there is one
+    timestamp: u64,
+    trade: Trade,
+}
+
+impl StoredTrade {
+    fn event_id(&self) -> String {
+        format!("{}_{}", self.tx_digest, self.event_seq)
+    }
+
+    // Where the trade falls in the order trades happened
+    fn position(&self) -> (u64, &str, u64) {
+        (self.timestamp, &self.tx_digest, self.event_seq)
+    }
+}
+
+#[derive(Deserialize)]
+struct PurchaseRow {
+    tx_digest: String,
+    event_seq: u64,
+    timestamp: u64,
+    buyer: String,
+    amount: u64,
+}
+
+#[derive(Deserialize)]
+struct TransferRow {
+    tx_digest: String,
+    event_seq: u64,
+    timestamp: u64,
+    from: String,
+    to: String,
+    amount: u64,
+}
+
+const PURCHASE_FIELDS: &str = "tx_digest, meta::id(id)[1] AS event_seq, timestamp, buyer, amount";
+const TRANSFER_FIELDS: &str =
+    "tx_digest, meta::id(id)[1] AS event_seq, timestamp, `from`, `to`, amount";
+
+// Both kinds of trade, in the order they happened
+fn in_order(purchases: Vec<PurchaseRow>, transfers: Vec<TransferRow>) -> Vec<StoredTrade> {
+    let mut trades: Vec<StoredTrade> = purchases
+        .into_iter()
+        .map(|row| StoredTrade {
+            tx_digest: row.tx_digest,
+            event_seq: row.event_seq,
+            timestamp: row.timestamp,
+            trade: Trade::Buy {
+                wallet: row.buyer,
+                amount: row.amount,
+            },
+        })
+        .chain(transfers.into_iter().map(|row| StoredTrade {
+            tx_digest: row.tx_digest,
+            event_seq: row.event_seq,
+            timestamp: row.timestamp,
+            trade: Trade::Transfer {
+                from: row.from,
+                to: row.to,
+                amount: row.amount,
+            },
+        }))
+        .collect();
+    trades.sort_by(|a, b| a.position().cmp(&b.position()));
+    trades
+}
+
+// Stored trades of `tx_digests`, in the order they happened
+async fn stored_trades(db: &Surreal<Any>, tx_digests: Vec<String>) -> Result<Vec<StoredTrade>> {
+    let mut response = db
+        .query(format!(
+            "SELECT {} FROM token_purchases WHERE tx_digest INSIDE $tx_digests",
+            PURCHASE_FIELDS
+        ))
+        .query(format!(
+            "SELECT {} FROM token_transfers WHERE tx_digest INSIDE $tx_digests",
+            TRANSFER_FIELDS
+        ))
+        .bind(("tx_digests", tx_digests))
+        .await?;
+    Ok(in_order(response.take(0)?, response.take(1)?))
+}
+
+// Up to `limit` stored trades after the one at `after`, in the order they
+// happened
+async fn trades_after(
+    db: &Surreal<Any>,
+    after: (u64, &str, u64),
+    limit: usize,
+) -> Result<Vec<StoredTrade>> {
+    const AFTER: &str = "timestamp > $timestamp OR (timestamp = $timestamp AND \
+        (tx_digest > $tx_digest OR (tx_digest = $tx_digest AND meta::id(id)[1] > $event_seq)))";
+    let (timestamp, tx_digest, event_seq) = after;
+    let mut response = db
+        .query(format!(
+            "SELECT {} FROM token_purchases WHERE {} \
+             ORDER BY timestamp, tx_digest, event_seq LIMIT $limit",
+            PURCHASE_FIELDS, AFTER
+        ))
+        .query(format!(
+            "SELECT {} FROM token_transfers WHERE {} \
+             ORDER BY timestamp, tx_digest, event_seq LIMIT $limit",
+            TRANSFER_FIELDS, AFTER
+        ))
+        .bind(("timestamp", timestamp))
+        .bind(("tx_digest", tx_digest.to_string()))
+        .bind(("event_seq", event_seq))
+        .bind(("limit", limit))
+        .await?;
+    // Each table's first `limit` include the first `limit` of both
+    let mut trades = in_order(response.take(0)?, response.take(1)?);
+    trades.truncate(limit);
+    Ok(trades)
+}
+
+// `trades` without the ones already in `pnl_applied`, read in one round trip
+async fn unapplied(db: &Surreal<Any>, trades: Vec<StoredTrade>) -> Result<Vec<StoredTrade>> {
+    if trades.is_empty() {
+        return Ok(trades);
+    }
+    let statements: String = (0..trades.len())
+        .map(|i| {
+            format!(
+                "SELECT VALUE meta::id(id) FROM type::thing('pnl_applied', $event{});",
+                i
+            )
+        })
+        .collect();
+    let mut query = db.query(statements);
+    for (i, trade) in trades.iter().enumerate() {
+        query = query.bind((format!("event{}", i), trade.event_id()));
+    }
+    let mut response = query.await?;
+    let mut applied = HashSet::new();
+    for i in 0..trades.len() {
+        let found: Vec<String> = response.take(i)?;
+        applied.extend(found);
+    }
+    Ok(trades
+        .into_iter()
+        .filter(|trade| !applied.contains(&trade.event_id()))
+        .collect())
+}
+
+// Positions read while applying a batch of trades, so each is read once;
+// dropped with the batch, since a failed write leaves them ahead of the
+// database
+struct Positions<'a> {
+    db: &'a Surreal<Any>,
+    held: HashMap<(String, String), Holding>,
+}
+
+impl<'a> Positions<'a> {
+    fn new(db: &'a Surreal<Any>) -> Self {
+        Self {
+            db,
+            held: HashMap::new(),
+        }
+    }
+
+    async fn get(&mut self, wallet: &str, launchpad_id: &str) -> Result<&mut Holding> {
+        let key = (wallet.to_string(), launchpad_id.to_string());
+        if !self.held.contains_key(&key) {
+            let holding = holding(self.db, wallet, launchpad_id).await?;
+            self.held.insert(key.clone(), holding);
+        }
+        Ok(self.held.get_mut(&key).expect("position was just read"))
+    }
+}
+
+/// Keeps `pnl_positions` up to date as trades are stored.
+pub struct PnlTracker {
+    db: Surreal<Any>,
+    // Held while applying so positions change one trade at a time
+    applying: Mutex<()>,
+}
+
+impl PnlTracker {
+    pub fn new(db: Surreal<Any>) -> Self {
+        Self {
+            db,
+            applying: Mutex::new(()),
+        }
+    }
+
+    /// A tracker that has first applied every stored trade still missing
+    /// from the positions, e.g. ones stored before a crash or before the
+    /// tracker existed.
+    pub async fn start(db: Surreal<Any>) -> Result<Self> {
+        let tracker = Self::new(db);
+        tracker.catch_up().await?;
+        Ok(tracker)
+    }
+
+    async fn catch_up(&self) -> Result<()> {
+        let _applying = self.applying.lock().await;
+        let mut after = (0, String::new(), 0);
+        let mut applied = 0;
+        loop {
+            let page = trades_after(&self.db, (after.0, &after.1, after.2), CATCH_UP_PAGE).await?;
+            let Some(last) = page.last() else {
+                break;
+            };
+            after = (last.timestamp, last.tx_digest.clone(), last.event_seq);
+            let full = page.len() == CATCH_UP_PAGE;
+
+            // Trades of transactions with no BalanceUpdate can't be attributed
+            let digests = page.iter().map(|trade| trade.tx_digest.clone()).collect();
+            let launchpads = storage::tx_launchpads(&self.db, digests).await?;
+            let mut positions = Positions::new(&self.db);
+            for trade in unapplied(&self.db, page).await? {
+                if let Some(launchpad_id) = launchpads.get(&trade.tx_digest) {
+                    self.apply(&mut positions, launchpad_id, &trade).await?;
+                    applied += 1;
+                }
+            }
+            if !full {
+                break;
+            }
+        }
+        if applied > 0 {
+            info!("Applied {} stored trades to PnL positions", applied);
+        }
+        Ok(())
+    }
+
+    // Apply the stored trades of `tx_digest` that haven't been yet
+    async fn apply_tx(&self, launchpad_id: &str, tx_digest: &str) -> Result<()> {
+        let _applying = self.applying.lock().await;
+        let trades = stored_trades(&self.db, vec![tx_digest.to_string()]).await?;
+        let mut positions = Positions::new(&self.db);
+        for trade in unapplied(&self.db, trades).await? {
+            self.apply(&mut positions, launchpad_id, &trade).await?;
+        }
+        Ok(())
+    }
+
+    // Apply `stored`, a trade not in `pnl_applied` yet
+    async fn apply(
+        &self,
+        positions: &mut Positions<'_>,
+        launchpad_id: &str,
+        stored: &StoredTrade,
+    ) -> Result<()> {
+        let timestamp = stored.timestamp;
+        let price = storage::price_before(&self.db, launchpad_id, timestamp)
+            .await?
+            .unwrap_or(0);
+        let mut changed = Vec::new();
+        match &stored.trade {
+            Trade::Buy { wallet, amount } => {
+                let buyer = positions.get(wallet, launchpad_id).await?;
+                buyer.acquire(*amount, price);
+                changed.push(buyer.clone());
+            }
+            Trade::Transfer { from, to, amount } if from != to => {
+                let sender = positions.get(from, launchpad_id).await?;
+                sender.dispose(*amount, price);
+                changed.push(sender.clone());
+                let receiver = positions.get(to, launchpad_id).await?;
+                receiver.acquire(*amount, price);
+                changed.push(receiver.clone());
+            }
+            Trade::Transfer { .. } => {}
+        }
+
+        let mut query = self.db.query("BEGIN TRANSACTION");
+        for (i, holding) in changed.iter_mut().enumerate() {
+            holding.updated_at = timestamp;
+            query = query
+                .query(format!(
+                    "UPSERT type::thing('pnl_positions', [$wallet{i}, $launchpad_id]) \
+                     CONTENT $holding{i}"
+                ))
+                .bind((format!("wallet{}", i), holding.wallet.clone()))
+                .bind((format!("holding{}", i), holding.clone()));
+        }
+        query
+            .query("CREATE type::thing('pnl_applied', $event_id)")
+            .query("COMMIT TRANSACTION")
+            .bind(("launchpad_id", launchpad_id.to_string()))
+            .bind(("event_id", stored.event_id()))
+            .await?
+            .check()?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl EventHandler for PnlTracker {
+    fn name(&self) -> &str {
+        "pnl"
+    }
+
+    async fn handle(&self, event: &LaunchpadEvent, metadata: &EventMetadata) -> Result<()> {
+        let relevant = matches!(
+            event,
+            LaunchpadEvent::TokensPurchased(_)
+                | LaunchpadEvent::TokensTransferred(_)
+                | LaunchpadEvent::BalanceUpdate(_)
+        );
+        // Until the transaction's BalanceUpdate is stored its trades wait in
+        // their tables
+        match &metadata.launchpad_id {
+            Some(launchpad_id) if relevant => {
+                self.apply_tx(launchpad_id, &metadata.tx_digest).await
+            }
+            _ => Ok(()),
+        }
+    }
+}
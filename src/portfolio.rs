@@ -0,0 +1,172 @@
+//! Everything a wallet holds and did, per launchpad.
+//!
+//! Purchases, transfers and vesting claims don't carry a launchpad id, so
+//! each is attributed to the launchpad of the BalanceUpdate emitted in the
+//! same transaction. Amounts spent are estimated at the price in effect
+//! before each purchase, the launchpad's initial price until its first
+//! PriceUpdate; values use the latest price.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use surrealdb::{engine::any::Any, Surreal};
+use utoipa::ToSchema;
+
+use crate::storage::{self, PriceHistory};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct Portfolio {
+    pub wallet: String,
+    pub positions: Vec<Position>,
+    /// Estimated total spent on purchases, in the price's units.
+    pub total_spent: u64,
+    /// Value of every position at its latest price.
+    pub total_value: u64,
+}
+
+/// The wallet's activity in one launchpad.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct Position {
+    pub launchpad_id: String,
+    pub launchpad_name: Option<String>,
+    pub balance: u64,
+    pub tokens_bought: u64,
+    /// Estimated cost of the tokens bought.
+    pub spent: u64,
+    pub tokens_received: u64,
+    pub tokens_sent: u64,
+    pub vesting_claimed: u64,
+    /// Latest `PriceUpdate.new_price`, if the launchpad has traded.
+    pub latest_price: Option<u64>,
+    /// Unrealized value of the balance at the latest price.
+    pub value: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct Purchase {
+    amount: u64,
+    timestamp: u64,
+    tx_digest: String,
+}
+
+#[derive(Deserialize)]
+struct Transfer {
+    from: String,
+    amount: u64,
+    tx_digest: String,
+}
+
+#[derive(Deserialize)]
+struct Claim {
+    amount: u64,
+    tx_digest: String,
+}
+
+#[derive(Deserialize)]
+struct LaunchpadName {
+    launchpad_id: String,
+    name: String,
+}
+
+pub async fn portfolio(db: &Surreal<Any>, wallet: &str) -> Result<Portfolio> {
+    let mut response = db
+        .query("SELECT amount, timestamp, tx_digest FROM token_purchases WHERE buyer = $wallet")
+        .query(
+            "SELECT `from`, amount, tx_digest FROM token_transfers \
+             WHERE `from` = $wallet OR `to` = $wallet",
+        )
+        .query("SELECT amount, tx_digest FROM vesting_claims WHERE user = $wallet")
+        .bind(("wallet", wallet.to_string()))
+        .await?;
+    let purchases: Vec<Purchase> = response.take(0)?;
+    let transfers: Vec<Transfer> = response.take(1)?;
+    let claims: Vec<Claim> = response.take(2)?;
+
+    let digests: HashSet<&str> = purchases
+        .iter()
+        .map(|p| p.tx_digest.as_str())
+        .chain(transfers.iter().map(|t| t.tx_digest.as_str()))
+        .chain(claims.iter().map(|c| c.tx_digest.as_str()))
+        .collect();
+    let launchpad_of =
+        storage::tx_launchpads(db, digests.into_iter().map(str::to_string).collect()).await?;
+
+    let mut positions: BTreeMap<String, Position> = BTreeMap::new();
+    for balance in storage::wallet_balances(db, wallet).await? {
+        entry(&mut positions, &balance.launchpad_id).balance = balance.balance;
+    }
+    for transfer in &transfers {
+        if let Some(id) = launchpad_of.get(&transfer.tx_digest) {
+            let position = entry(&mut positions, id);
+            if transfer.from == wallet {
+                position.tokens_sent += transfer.amount;
+            } else {
+                position.tokens_received += transfer.amount;
+            }
+        }
+    }
+    for claim in &claims {
+        if let Some(id) = launchpad_of.get(&claim.tx_digest) {
+            entry(&mut positions, id).vesting_claimed += claim.amount;
+        }
+    }
+    for purchase in &purchases {
+        if let Some(id) = launchpad_of.get(&purchase.tx_digest) {
+            entry(&mut positions, id).tokens_bought += purchase.amount;
+        }
+    }
+
+    let ids: Vec<String> = positions.keys().cloned().collect();
+    let names: Vec<LaunchpadName> = db
+        .query("SELECT launchpad_id, name FROM launchpads WHERE launchpad_id INSIDE $ids")
+        .bind(("ids", ids.clone()))
+        .await?
+        .take(0)?;
+    let names: HashMap<String, String> = names
+        .into_iter()
+        .map(|launchpad| (launchpad.launchpad_id, launchpad.name))
+        .collect();
+    let prices = PriceHistory::load(db, ids).await?;
+
+    for (id, position) in positions.iter_mut() {
+        position.launchpad_name = names.get(id).cloned();
+        position.latest_price = prices.latest(id);
+        position.value = position
+            .latest_price
+            .map(|price| price.saturating_mul(position.balance));
+        position.spent = purchases
+            .iter()
+            .filter(|p| launchpad_of.get(&p.tx_digest) == Some(id))
+            .map(|p| {
+                let price = prices.before(id, p.timestamp).unwrap_or(0);
+                price.saturating_mul(p.amount)
+            })
+            .fold(0, u64::saturating_add);
+    }
+
+    let positions: Vec<Position> = positions.into_values().collect();
+    Ok(Portfolio {
+        wallet: wallet.to_string(),
+        total_spent: positions
+            .iter()
+            .map(|p| p.spent)
+            .fold(0, u64::saturating_add),
+        total_value: positions
+            .iter()
+            .filter_map(|p| p.value)
+            .fold(0, u64::saturating_add),
+        positions,
+    })
+}
+
+fn entry<'a>(
+    positions: &'a mut BTreeMap<String, Position>,
+    launchpad_id: &str,
+) -> &'a mut Position {
+    positions
+        .entry(launchpad_id.to_string())
+        .or_insert_with(|| Position {
+            launchpad_id: launchpad_id.to_string(),
+            ..Position::default()
+        })
+}
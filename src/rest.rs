@@ -0,0 +1,848 @@
+//! REST endpoints over the indexed tables, documented with OpenAPI.
+//!
+//! Handlers are registered through `utoipa-axum`, so the routes served and
+//! the paths in `/openapi.json` come from the same annotations.
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use utoipa::{IntoParams, OpenApi, ToSchema};
+use utoipa_axum::{router::OpenApiRouter, routes};
+
+use crate::api::{internal_error, ApiState};
+use crate::audit::{self, AdminHistory, AuditEntry, Cursor};
+use crate::fees::{self, FeePeriod, FeeSchedule, Period, Revenue};
+use crate::leaderboards::{
+    BuyerRank, LaunchpadOrder, LaunchpadRank, WalletRank, Window, MAX_ENTRIES,
+};
+use crate::pnl::{self, Pnl};
+use crate::portfolio::{self, Portfolio};
+use crate::storage::{self, Balance, WhitelistEntry, LAUNCHPAD_TXS};
+use crate::transactions::{self, TransactionMeta};
+use crate::vesting::{self, VestingClaim, VestingPosition};
+
+const DEFAULT_LIMIT: usize = 50;
+const MAX_LIMIT: usize = 500;
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Launchpad indexer API",
+        description = "Launchpads, trades and balances indexed from the launchpad Move package"
+    ),
+    tags(
+        (name = "launchpads", description = "Launchpads and their trading"),
+        (name = "wallets", description = "Per-wallet views"),
+        (name = "fees", description = "Creation fees and revenue"),
+        (name = "admin", description = "Privileged actions"),
+        (name = "transactions", description = "Transactions that emitted events"),
+        (name = "leaderboards", description = "Rankings over trailing windows"),
+        (name = "webhooks", description = "Push delivery of events, for admin keys"),
+        (name = "feed", description = "Live events over WebSocket or server-sent events"),
+        (name = "graphql", description = "GraphQL API over the same data"),
+        (name = "operations", description = "Probes and metrics, open without a key")
+    )
+)]
+struct ApiDoc;
+
+pub(crate) fn router() -> OpenApiRouter<ApiState> {
+    OpenApiRouter::with_openapi(ApiDoc::openapi())
+        .routes(routes!(list_launchpads))
+        .routes(routes!(get_launchpad))
+        .routes(routes!(launchpad_purchases))
+        .routes(routes!(launchpad_balances))
+        .routes(routes!(launchpad_candles))
+        .routes(routes!(launchpad_vesting_claims))
+        .routes(routes!(launchpad_whitelist))
+        .routes(routes!(whitelist_status))
+        .routes(routes!(wallet_balances))
+        .routes(routes!(wallet_portfolio))
+        .routes(routes!(wallet_pnl))
+        .routes(routes!(wallet_launchpad_pnl))
+        .routes(routes!(wallet_vesting))
+        .routes(routes!(fee_schedule))
+        .routes(routes!(fee_revenue))
+        .routes(routes!(get_transaction))
+        .routes(routes!(top_buyers))
+        .routes(routes!(top_launchpads))
+        .routes(routes!(most_active_wallets))
+}
+
+// Endpoints served only to admin keys
+pub(crate) fn admin_router() -> OpenApiRouter<ApiState> {
+    OpenApiRouter::new().routes(routes!(admin_audit))
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct Launchpad {
+    pub launchpad_id: String,
+    pub creator: String,
+    pub name: String,
+    pub description: String,
+    pub token_supply: u64,
+    pub initial_price: u64,
+    pub price_increment: u64,
+    pub website_url: String,
+    /// Creation time, in milliseconds.
+    pub timestamp: u64,
+    pub tx_digest: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct Purchase {
+    pub buyer: String,
+    pub amount: u64,
+    /// Milliseconds since the epoch.
+    pub timestamp: u64,
+    pub tx_digest: String,
+}
+
+/// Price movement over one interval.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct Candle {
+    /// Start of the interval, in milliseconds.
+    pub start: u64,
+    pub open: u64,
+    pub high: u64,
+    pub low: u64,
+    pub close: u64,
+    /// Tokens bought during the interval.
+    pub volume: u64,
+    /// Purchases during the interval.
+    pub trades: u64,
+}
+
+/// Whether an address is whitelisted on a launchpad.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct WhitelistStatus {
+    pub launchpad_id: String,
+    pub address: String,
+    pub whitelisted: bool,
+    /// When the address was last put on the whitelist, in milliseconds.
+    pub added_at: Option<u64>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ErrorBody {
+    pub error: String,
+}
+
+fn bad_request(error: anyhow::Error) -> Response {
+    let body = ErrorBody {
+        error: error.to_string(),
+    };
+    (StatusCode::BAD_REQUEST, Json(body)).into_response()
+}
+
+fn not_found(what: &str) -> Response {
+    let body = ErrorBody {
+        error: format!("{} not found", what),
+    };
+    (StatusCode::NOT_FOUND, Json(body)).into_response()
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct LaunchpadParams {
+    /// Only launchpads created by this address.
+    creator: Option<String>,
+    /// Page size, at most 500.
+    limit: Option<usize>,
+    offset: Option<usize>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct PurchaseParams {
+    /// Only purchases by this address.
+    buyer: Option<String>,
+    /// Earliest timestamp, in milliseconds, inclusive.
+    since: Option<u64>,
+    /// Latest timestamp, in milliseconds, exclusive.
+    until: Option<u64>,
+    /// Page size, at most 500.
+    limit: Option<usize>,
+    offset: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, ToSchema)]
+pub enum Interval {
+    #[serde(rename = "1m")]
+    OneMinute,
+    #[serde(rename = "5m")]
+    FiveMinutes,
+    #[serde(rename = "15m")]
+    FifteenMinutes,
+    #[default]
+    #[serde(rename = "1h")]
+    OneHour,
+    #[serde(rename = "4h")]
+    FourHours,
+    #[serde(rename = "1d")]
+    OneDay,
+}
+
+impl Interval {
+    fn millis(self) -> u64 {
+        let minutes = match self {
+            Interval::OneMinute => 1,
+            Interval::FiveMinutes => 5,
+            Interval::FifteenMinutes => 15,
+            Interval::OneHour => 60,
+            Interval::FourHours => 240,
+            Interval::OneDay => 1440,
+        };
+        minutes * 60_000
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct CandleParams {
+    /// Candle width, 1h by default.
+    #[param(inline)]
+    interval: Option<Interval>,
+    /// Earliest timestamp, in milliseconds, inclusive.
+    since: Option<u64>,
+    /// Latest timestamp, in milliseconds, exclusive.
+    until: Option<u64>,
+}
+
+// `LIMIT` and `START` clauses for a page
+fn page(limit: Option<usize>, offset: Option<usize>) -> String {
+    format!(
+        "LIMIT {} START {}",
+        limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT),
+        offset.unwrap_or(0)
+    )
+}
+
+/// List launchpads, newest first
+#[utoipa::path(
+    get,
+    path = "/launchpads",
+    tag = "launchpads",
+    params(LaunchpadParams),
+    responses(
+        (status = 200, description = "Launchpads, newest first", body = [Launchpad]),
+        (status = 500, description = "Database error")
+    )
+)]
+async fn list_launchpads(
+    State(state): State<ApiState>,
+    Query(params): Query<LaunchpadParams>,
+) -> Response {
+    let condition = if params.creator.is_some() {
+        "WHERE creator = $creator"
+    } else {
+        ""
+    };
+    let result = state
+        .indexer
+        .db()
+        .query(format!(
+            "SELECT * OMIT id FROM launchpads {} ORDER BY timestamp DESC {}",
+            condition,
+            page(params.limit, params.offset)
+        ))
+        .bind(("creator", params.creator))
+        .await
+        .and_then(|mut response| response.take::<Vec<Launchpad>>(0));
+    match result {
+        Ok(launchpads) => Json(launchpads).into_response(),
+        Err(e) => internal_error(e.into()),
+    }
+}
+
+/// Get one launchpad
+#[utoipa::path(
+    get,
+    path = "/launchpads/{id}",
+    tag = "launchpads",
+    params(("id" = String, Path, description = "Launchpad object id")),
+    responses(
+        (status = 200, description = "The launchpad", body = Launchpad),
+        (status = 404, description = "No such launchpad", body = ErrorBody),
+        (status = 500, description = "Database error")
+    )
+)]
+async fn get_launchpad(State(state): State<ApiState>, Path(id): Path<String>) -> Response {
+    let result = state
+        .indexer
+        .db()
+        .query("SELECT * OMIT id FROM launchpads WHERE launchpad_id = $id LIMIT 1")
+        .bind(("id", id))
+        .await
+        .and_then(|mut response| response.take::<Option<Launchpad>>(0));
+    match result {
+        Ok(Some(launchpad)) => Json(launchpad).into_response(),
+        Ok(None) => not_found("launchpad"),
+        Err(e) => internal_error(e.into()),
+    }
+}
+
+/// List purchases on a launchpad, newest first
+#[utoipa::path(
+    get,
+    path = "/launchpads/{id}/purchases",
+    tag = "launchpads",
+    params(("id" = String, Path, description = "Launchpad object id"), PurchaseParams),
+    responses(
+        (status = 200, description = "Purchases, newest first", body = [Purchase]),
+        (status = 500, description = "Database error")
+    )
+)]
+async fn launchpad_purchases(
+    State(state): State<ApiState>,
+    Path(id): Path<String>,
+    Query(params): Query<PurchaseParams>,
+) -> Response {
+    let mut conditions = vec![LAUNCHPAD_TXS];
+    if params.buyer.is_some() {
+        conditions.push("buyer = $buyer");
+    }
+    if params.since.is_some() {
+        conditions.push("timestamp >= $since");
+    }
+    if params.until.is_some() {
+        conditions.push("timestamp < $until");
+    }
+    let result = state
+        .indexer
+        .db()
+        .query(format!(
+            "SELECT * OMIT id FROM token_purchases WHERE {} ORDER BY timestamp DESC {}",
+            conditions.join(" AND "),
+            page(params.limit, params.offset)
+        ))
+        .bind(("launchpad_id", id))
+        .bind(("buyer", params.buyer))
+        .bind(("since", params.since))
+        .bind(("until", params.until))
+        .await
+        .and_then(|mut response| response.take::<Vec<Purchase>>(0));
+    match result {
+        Ok(purchases) => Json(purchases).into_response(),
+        Err(e) => internal_error(e.into()),
+    }
+}
+
+/// List current balances in a launchpad, largest first
+#[utoipa::path(
+    get,
+    path = "/launchpads/{id}/balances",
+    tag = "launchpads",
+    params(("id" = String, Path, description = "Launchpad object id")),
+    responses(
+        (status = 200, description = "Balances, largest first", body = [Balance]),
+        (status = 500, description = "Database error")
+    )
+)]
+async fn launchpad_balances(State(state): State<ApiState>, Path(id): Path<String>) -> Response {
+    match storage::launchpad_balances(state.indexer.db(), &id).await {
+        Ok(balances) => Json(balances).into_response(),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// Price candles for a launchpad, oldest first
+#[utoipa::path(
+    get,
+    path = "/launchpads/{id}/candles",
+    tag = "launchpads",
+    params(("id" = String, Path, description = "Launchpad object id"), CandleParams),
+    responses(
+        (status = 200, description = "Candles, oldest first", body = [Candle]),
+        (status = 500, description = "Database error")
+    )
+)]
+async fn launchpad_candles(
+    State(state): State<ApiState>,
+    Path(id): Path<String>,
+    Query(params): Query<CandleParams>,
+) -> Response {
+    match candles(&state, id, &params).await {
+        Ok(candles) => Json(candles).into_response(),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// Vesting claims made in a launchpad
+#[utoipa::path(
+    get,
+    path = "/launchpads/{id}/vesting/claims",
+    tag = "launchpads",
+    params(("id" = String, Path, description = "Launchpad object id")),
+    responses(
+        (status = 200, description = "Claims, newest first", body = [VestingClaim]),
+        (status = 500, description = "Database error")
+    )
+)]
+async fn launchpad_vesting_claims(
+    State(state): State<ApiState>,
+    Path(id): Path<String>,
+) -> Response {
+    match vesting::launchpad_claims(state.indexer.db(), &id).await {
+        Ok(claims) => Json(claims).into_response(),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// Addresses whitelisted on a launchpad, oldest first
+#[utoipa::path(
+    get,
+    path = "/launchpads/{id}/whitelist",
+    tag = "launchpads",
+    params(("id" = String, Path, description = "Launchpad object id")),
+    responses(
+        (status = 200, description = "Whitelisted addresses", body = [WhitelistEntry]),
+        (status = 500, description = "Database error")
+    )
+)]
+async fn launchpad_whitelist(State(state): State<ApiState>, Path(id): Path<String>) -> Response {
+    match storage::whitelist(state.indexer.db(), &id).await {
+        Ok(entries) => Json(entries).into_response(),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// Whether an address is whitelisted on a launchpad
+#[utoipa::path(
+    get,
+    path = "/launchpads/{id}/whitelist/{address}",
+    tag = "launchpads",
+    params(
+        ("id" = String, Path, description = "Launchpad object id"),
+        ("address" = String, Path, description = "Wallet address")
+    ),
+    responses(
+        (status = 200, description = "The address's whitelist status", body = WhitelistStatus),
+        (status = 500, description = "Database error")
+    )
+)]
+async fn whitelist_status(
+    State(state): State<ApiState>,
+    Path((id, address)): Path<(String, String)>,
+) -> Response {
+    match storage::whitelist_entry(state.indexer.db(), &id, &address).await {
+        Ok(entry) => Json(WhitelistStatus {
+            whitelisted: entry.is_some(),
+            added_at: entry.map(|e| e.added_at),
+            launchpad_id: id,
+            address,
+        })
+        .into_response(),
+        Err(e) => internal_error(e),
+    }
+}
+
+#[derive(Deserialize)]
+struct Point {
+    value: u64,
+    timestamp: u64,
+}
+
+async fn candles(
+    state: &ApiState,
+    id: String,
+    params: &CandleParams,
+) -> anyhow::Result<Vec<Candle>> {
+    let mut conditions = vec![LAUNCHPAD_TXS];
+    if params.since.is_some() {
+        conditions.push("timestamp >= $since");
+    }
+    if params.until.is_some() {
+        conditions.push("timestamp < $until");
+    }
+    let condition = conditions.join(" AND ");
+    let mut response = state
+        .indexer
+        .db()
+        .query(format!(
+            "SELECT new_price AS value, timestamp FROM price_updates WHERE {} ORDER BY timestamp ASC",
+            condition
+        ))
+        .query(format!(
+            "SELECT amount AS value, timestamp FROM token_purchases WHERE {} ORDER BY timestamp ASC",
+            condition
+        ))
+        .bind(("launchpad_id", id))
+        .bind(("since", params.since))
+        .bind(("until", params.until))
+        .await?;
+    let prices: Vec<Point> = response.take(0)?;
+    let purchases: Vec<Point> = response.take(1)?;
+    Ok(build_candles(
+        &prices,
+        &purchases,
+        params.interval.unwrap_or_default().millis(),
+    ))
+}
+
+// Prices set the candles; purchases add volume, opening a flat candle at the
+// last known price when they fall in an interval without a price change
+fn build_candles(prices: &[Point], purchases: &[Point], width: u64) -> Vec<Candle> {
+    let mut candles: BTreeMap<u64, Candle> = BTreeMap::new();
+    for price in prices {
+        let start = price.timestamp - price.timestamp % width;
+        candles
+            .entry(start)
+            .and_modify(|c| {
+                c.high = c.high.max(price.value);
+                c.low = c.low.min(price.value);
+                c.close = price.value;
+            })
+            .or_insert(Candle {
+                start,
+                open: price.value,
+                high: price.value,
+                low: price.value,
+                close: price.value,
+                volume: 0,
+                trades: 0,
+            });
+    }
+    for purchase in purchases {
+        let start = purchase.timestamp - purchase.timestamp % width;
+        if !candles.contains_key(&start) {
+            let last = candles.range(..start).next_back().map(|(_, c)| c.close);
+            let Some(price) = last else { continue };
+            candles.insert(
+                start,
+                Candle {
+                    start,
+                    open: price,
+                    high: price,
+                    low: price,
+                    close: price,
+                    volume: 0,
+                    trades: 0,
+                },
+            );
+        }
+        let candle = candles.get_mut(&start).expect("inserted above");
+        candle.volume = candle.volume.saturating_add(purchase.value);
+        candle.trades += 1;
+    }
+    candles.into_values().collect()
+}
+
+/// List a wallet's current balance in every launchpad it holds
+#[utoipa::path(
+    get,
+    path = "/wallets/{address}/balances",
+    tag = "wallets",
+    params(("address" = String, Path, description = "Wallet address")),
+    responses(
+        (status = 200, description = "Balances, largest first", body = [Balance]),
+        (status = 500, description = "Database error")
+    )
+)]
+async fn wallet_balances(State(state): State<ApiState>, Path(address): Path<String>) -> Response {
+    match storage::wallet_balances(state.indexer.db(), &address).await {
+        Ok(balances) => Json(balances).into_response(),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// A wallet's positions across launchpads, with spending and value
+#[utoipa::path(
+    get,
+    path = "/wallets/{address}/portfolio",
+    tag = "wallets",
+    params(("address" = String, Path, description = "Wallet address")),
+    responses(
+        (status = 200, description = "The wallet's portfolio", body = Portfolio),
+        (status = 500, description = "Database error")
+    )
+)]
+async fn wallet_portfolio(State(state): State<ApiState>, Path(address): Path<String>) -> Response {
+    match portfolio::portfolio(state.indexer.db(), &address).await {
+        Ok(portfolio) => Json(portfolio).into_response(),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// A wallet's PnL in every launchpad it traded
+#[utoipa::path(
+    get,
+    path = "/wallets/{address}/pnl",
+    tag = "wallets",
+    params(("address" = String, Path, description = "Wallet address")),
+    responses(
+        (status = 200, description = "PnL per launchpad", body = [Pnl]),
+        (status = 500, description = "Database error")
+    )
+)]
+async fn wallet_pnl(State(state): State<ApiState>, Path(address): Path<String>) -> Response {
+    match pnl::wallet_pnl(state.indexer.db(), &address).await {
+        Ok(pnl) => Json(pnl).into_response(),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// A wallet's PnL in one launchpad
+#[utoipa::path(
+    get,
+    path = "/wallets/{address}/pnl/{id}",
+    tag = "wallets",
+    params(
+        ("address" = String, Path, description = "Wallet address"),
+        ("id" = String, Path, description = "Launchpad object id")
+    ),
+    responses(
+        (status = 200, description = "The wallet's PnL in the launchpad", body = Pnl),
+        (status = 404, description = "The wallet never traded the launchpad", body = ErrorBody),
+        (status = 500, description = "Database error")
+    )
+)]
+async fn wallet_launchpad_pnl(
+    State(state): State<ApiState>,
+    Path((address, id)): Path<(String, String)>,
+) -> Response {
+    match pnl::pnl(state.indexer.db(), &address, &id).await {
+        Ok(Some(pnl)) => Json(pnl).into_response(),
+        Ok(None) => not_found("position"),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// A wallet's vesting per launchpad, with its claims
+#[utoipa::path(
+    get,
+    path = "/wallets/{address}/vesting",
+    tag = "wallets",
+    params(("address" = String, Path, description = "Wallet address")),
+    responses(
+        (status = 200, description = "Vesting positions per launchpad", body = [VestingPosition]),
+        (status = 500, description = "Database error")
+    )
+)]
+async fn wallet_vesting(State(state): State<ApiState>, Path(address): Path<String>) -> Response {
+    let now = Utc::now().timestamp_millis() as u64;
+    match vesting::positions(state.indexer.db(), &address, now).await {
+        Ok(positions) => Json(positions).into_response(),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// Creation fees over time, oldest first
+#[utoipa::path(
+    get,
+    path = "/fees/schedule",
+    tag = "fees",
+    responses(
+        (status = 200, description = "Fees with the times they applied", body = [FeePeriod]),
+        (status = 500, description = "Database error")
+    )
+)]
+async fn fee_schedule(State(state): State<ApiState>) -> Response {
+    match FeeSchedule::load(state.indexer.db(), fees::initial_fee()).await {
+        Ok(schedule) => Json(schedule.periods()).into_response(),
+        Err(e) => internal_error(e),
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct RevenueParams {
+    /// Length of each period, a month by default.
+    #[param(inline)]
+    period: Option<Period>,
+    /// Earliest creation time, in milliseconds, inclusive.
+    since: Option<u64>,
+    /// Latest creation time, in milliseconds, exclusive.
+    until: Option<u64>,
+}
+
+/// Creation fees collected per period, oldest first
+#[utoipa::path(
+    get,
+    path = "/fees/revenue",
+    tag = "fees",
+    params(RevenueParams),
+    responses(
+        (status = 200, description = "Creation fees per period", body = [Revenue]),
+        (status = 500, description = "Database error")
+    )
+)]
+async fn fee_revenue(
+    State(state): State<ApiState>,
+    Query(params): Query<RevenueParams>,
+) -> Response {
+    let db = state.indexer.db();
+    let result = async {
+        let schedule = FeeSchedule::load(db, fees::initial_fee()).await?;
+        let period = params.period.unwrap_or_default();
+        fees::revenue(db, &schedule, period, params.since, params.until).await
+    }
+    .await;
+    match result {
+        Ok(revenue) => Json(revenue).into_response(),
+        Err(e) => internal_error(e),
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct AuditParams {
+    /// Only actions whose sender wasn't the admin at the time.
+    flagged: Option<bool>,
+    /// `cursor` of the last entry of the previous page.
+    before: Option<String>,
+    /// Page size, at most 500.
+    limit: Option<usize>,
+}
+
+/// Privileged actions, newest first
+#[utoipa::path(
+    get,
+    path = "/admin/audit",
+    tag = "admin",
+    params(AuditParams),
+    responses(
+        (status = 200, description = "Actions with their sender", body = [AuditEntry]),
+        (status = 400, description = "Malformed cursor", body = ErrorBody),
+        (status = 401, description = "No API key"),
+        (status = 403, description = "Not an admin API key"),
+        (status = 500, description = "Database error")
+    )
+)]
+async fn admin_audit(State(state): State<ApiState>, Query(params): Query<AuditParams>) -> Response {
+    let before = match params.before.as_deref().map(Cursor::from_str).transpose() {
+        Ok(before) => before,
+        Err(e) => return bad_request(e),
+    };
+    let limit = params.limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT);
+    let db = state.indexer.db();
+    let result = async {
+        let history = AdminHistory::load(db, audit::initial_admin()).await?;
+        let flagged_only = params.flagged.unwrap_or(false);
+        audit::audit_trail(db, &history, flagged_only, before.as_ref(), limit).await
+    }
+    .await;
+    match result {
+        Ok(entries) => Json(entries).into_response(),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// Sender, gas, status and Move call of a transaction that emitted events
+#[utoipa::path(
+    get,
+    path = "/transactions/{digest}",
+    tag = "transactions",
+    params(("digest" = String, Path, description = "Transaction digest")),
+    responses(
+        (status = 200, description = "The transaction's context", body = TransactionMeta),
+        (status = 404, description = "The transaction hasn't been looked up", body = ErrorBody),
+        (status = 500, description = "Database error")
+    )
+)]
+async fn get_transaction(State(state): State<ApiState>, Path(digest): Path<String>) -> Response {
+    match transactions::transaction(state.indexer.db(), &digest).await {
+        Ok(Some(meta)) => Json(meta).into_response(),
+        Ok(None) => not_found("transaction"),
+        Err(e) => internal_error(e),
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct BoardParams {
+    /// Trailing window, all time by default.
+    #[param(inline)]
+    window: Option<Window>,
+    /// Entries to return, at most 100.
+    limit: Option<usize>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct LaunchpadBoardParams {
+    /// Trailing window, all time by default.
+    #[param(inline)]
+    window: Option<Window>,
+    /// Ranking, by SUI raised by default.
+    #[param(inline)]
+    by: Option<LaunchpadOrder>,
+    /// Entries to return, at most 100.
+    limit: Option<usize>,
+}
+
+// The first `limit` entries of a board
+fn top<T: Serialize>(board: &[T], limit: Option<usize>) -> Response {
+    let limit = limit.unwrap_or(10).min(MAX_ENTRIES);
+    Json(&board[..limit.min(board.len())]).into_response()
+}
+
+/// Top buyers by tokens bought
+#[utoipa::path(
+    get,
+    path = "/leaderboards/buyers",
+    tag = "leaderboards",
+    params(BoardParams),
+    responses(
+        (status = 200, description = "Buyers, largest volume first", body = [BuyerRank]),
+        (status = 500, description = "Database error")
+    )
+)]
+async fn top_buyers(State(state): State<ApiState>, Query(params): Query<BoardParams>) -> Response {
+    let window = params.window.unwrap_or_default();
+    match state.leaderboards.top_buyers(window).await {
+        Ok(board) => top(&board, params.limit),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// Top launchpads by SUI raised or by unique buyers
+#[utoipa::path(
+    get,
+    path = "/leaderboards/launchpads",
+    tag = "leaderboards",
+    params(LaunchpadBoardParams),
+    responses(
+        (status = 200, description = "Launchpads, best first", body = [LaunchpadRank]),
+        (status = 500, description = "Database error")
+    )
+)]
+async fn top_launchpads(
+    State(state): State<ApiState>,
+    Query(params): Query<LaunchpadBoardParams>,
+) -> Response {
+    let window = params.window.unwrap_or_default();
+    let order = params.by.unwrap_or_default();
+    match state.leaderboards.top_launchpads(window, order).await {
+        Ok(board) => top(&board, params.limit),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// Most active wallets by event count
+#[utoipa::path(
+    get,
+    path = "/leaderboards/wallets",
+    tag = "leaderboards",
+    params(BoardParams),
+    responses(
+        (status = 200, description = "Wallets, most events first", body = [WalletRank]),
+        (status = 500, description = "Database error")
+    )
+)]
+async fn most_active_wallets(
+    State(state): State<ApiState>,
+    Query(params): Query<BoardParams>,
+) -> Response {
+    let window = params.window.unwrap_or_default();
+    match state.leaderboards.most_active_wallets(window).await {
+        Ok(board) => top(&board, params.limit),
+        Err(e) => internal_error(e),
+    }
+}
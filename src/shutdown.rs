@@ -0,0 +1,43 @@
+use tokio::sync::watch;
+use tracing::error;
+
+// Flips to `true` once the process has been asked to stop
+pub type Shutdown = watch::Receiver<bool>;
+
+// Resolves on SIGINT (Ctrl-C) or, on unix, SIGTERM
+pub async fn termination_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            error!("Failed to listen for Ctrl-C: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                sigterm.recv().await;
+            }
+            Err(e) => {
+                error!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
+
+// Resolves once shutdown has been requested
+pub async fn requested(shutdown: &mut Shutdown) {
+    // A dropped sender also means the process is going away
+    let _ = shutdown.wait_for(|stop| *stop).await;
+}
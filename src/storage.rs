@@ -0,0 +1,878 @@
+//! SurrealDB schema and persistence of decoded events.
+
+use anyhow::{bail, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::str::FromStr;
+use sui_sdk::types::{digests::TransactionDigest, event::EventID};
+use surrealdb::{engine::any::Any, sql::Thing, Surreal};
+use utoipa::ToSchema;
+
+use crate::decoding::DecodedEvent;
+use crate::events;
+
+// Database records
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Transaction {
+    pub transaction_type: String,
+    pub timestamp: i64,
+    pub tx_digest: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Holder {
+    pub id: Option<Thing>,
+    pub wallet_address: String,
+    pub balance: u64,
+    pub last_updated: DateTime<Utc>,
+}
+
+/// A wallet's latest balance in one launchpad.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct Balance {
+    pub launchpad_id: String,
+    pub holder: String,
+    pub balance: u64,
+    /// Timestamp of the balance update, in milliseconds.
+    pub updated_at: u64,
+}
+
+// Matches rows from transactions that touched `$launchpad_id`. Only launchpad
+// creation and balance updates name their launchpad, so every other event is
+// tied to one through a balance update emitted in the same transaction.
+pub(crate) const LAUNCHPAD_TXS: &str =
+    "tx_digest INSIDE (SELECT VALUE tx_digest FROM balance_updates WHERE launchpad_id = $launchpad_id)";
+
+// Position of the last event handed to the pipeline, kept across restarts
+#[derive(Debug, Serialize, Deserialize)]
+struct StoredCursor {
+    tx_digest: String,
+    event_seq: u64,
+}
+
+/// Create the tables if they don't exist.
+pub async fn define_schema(db: &Surreal<Any>) -> Result<()> {
+    db.query("DEFINE TABLE token_purchases SCHEMAFULL").await?;
+    db.query("DEFINE FIELD buyer ON token_purchases TYPE string")
+        .await?;
+    db.query("DEFINE FIELD amount ON token_purchases TYPE number")
+        .await?;
+    db.query("DEFINE FIELD timestamp ON token_purchases TYPE number")
+        .await?;
+    db.query("DEFINE FIELD tx_digest ON token_purchases TYPE string")
+        .await?;
+    // Set by `attribute_purchases` once the transaction's launchpad is known
+    db.query("DEFINE FIELD launchpad_id ON token_purchases TYPE option<string>")
+        .await?;
+    db.query("DEFINE FIELD raised ON token_purchases TYPE option<number>")
+        .await?;
+    db.query("DEFINE INDEX token_purchases_launchpad ON token_purchases FIELDS launchpad_id")
+        .await?;
+    db.query("DEFINE INDEX token_purchases_timestamp ON token_purchases FIELDS timestamp")
+        .await?;
+
+    db.query("DEFINE TABLE token_transfers SCHEMAFULL").await?;
+    db.query("DEFINE FIELD from ON token_transfers TYPE string")
+        .await?;
+    db.query("DEFINE FIELD to ON token_transfers TYPE string")
+        .await?;
+    db.query("DEFINE FIELD amount ON token_transfers TYPE number")
+        .await?;
+    db.query("DEFINE FIELD timestamp ON token_transfers TYPE number")
+        .await?;
+    db.query("DEFINE FIELD tx_digest ON token_transfers TYPE string")
+        .await?;
+    db.query("DEFINE INDEX token_transfers_timestamp ON token_transfers FIELDS timestamp")
+        .await?;
+
+    db.query("DEFINE TABLE price_updates SCHEMAFULL").await?;
+    db.query("DEFINE FIELD new_price ON price_updates TYPE number")
+        .await?;
+    db.query("DEFINE FIELD tokens_sold ON price_updates TYPE number")
+        .await?;
+    db.query("DEFINE FIELD timestamp ON price_updates TYPE number")
+        .await?;
+    db.query("DEFINE FIELD tx_digest ON price_updates TYPE string")
+        .await?;
+
+    db.query("DEFINE TABLE liquidity_deployments SCHEMAFULL")
+        .await?;
+    db.query("DEFINE FIELD launchpad_id ON liquidity_deployments TYPE string")
+        .await?;
+    db.query("DEFINE FIELD sui_amount ON liquidity_deployments TYPE number")
+        .await?;
+    db.query("DEFINE FIELD timestamp ON liquidity_deployments TYPE number")
+        .await?;
+    db.query("DEFINE FIELD tx_digest ON liquidity_deployments TYPE string")
+        .await?;
+
+    db.query("DEFINE TABLE pool_pauses SCHEMAFULL").await?;
+    db.query("DEFINE FIELD launchpad_id ON pool_pauses TYPE string")
+        .await?;
+    db.query("DEFINE FIELD timestamp ON pool_pauses TYPE number")
+        .await?;
+    db.query("DEFINE FIELD tx_digest ON pool_pauses TYPE string")
+        .await?;
+
+    db.query("DEFINE TABLE pool_unpauses SCHEMAFULL").await?;
+    db.query("DEFINE FIELD launchpad_id ON pool_unpauses TYPE string")
+        .await?;
+    db.query("DEFINE FIELD timestamp ON pool_unpauses TYPE number")
+        .await?;
+    db.query("DEFINE FIELD tx_digest ON pool_unpauses TYPE string")
+        .await?;
+
+    db.query("DEFINE TABLE launchpads SCHEMAFULL").await?;
+    db.query("DEFINE FIELD launchpad_id ON launchpads TYPE string")
+        .await?;
+    db.query("DEFINE FIELD creator ON launchpads TYPE string")
+        .await?;
+    db.query("DEFINE FIELD name ON launchpads TYPE string")
+        .await?;
+    db.query("DEFINE FIELD description ON launchpads TYPE string")
+        .await?;
+    db.query("DEFINE FIELD token_supply ON launchpads TYPE number")
+        .await?;
+    db.query("DEFINE FIELD initial_price ON launchpads TYPE number")
+        .await?;
+    db.query("DEFINE FIELD price_increment ON launchpads TYPE number")
+        .await?;
+    db.query("DEFINE FIELD website_url ON launchpads TYPE string")
+        .await?;
+    db.query("DEFINE FIELD timestamp ON launchpads TYPE number")
+        .await?;
+    db.query("DEFINE FIELD tx_digest ON launchpads TYPE string")
+        .await?;
+
+    // Keyed by launchpad id, see `vesting::store_terms`
+    db.query("DEFINE TABLE launchpad_vesting SCHEMAFULL")
+        .await?;
+    db.query("DEFINE FIELD enabled ON launchpad_vesting TYPE bool")
+        .await?;
+    db.query("DEFINE FIELD duration_ms ON launchpad_vesting TYPE number")
+        .await?;
+
+    db.query("DEFINE TABLE vesting_claims SCHEMAFULL").await?;
+    db.query("DEFINE FIELD user ON vesting_claims TYPE string")
+        .await?;
+    db.query("DEFINE FIELD amount ON vesting_claims TYPE number")
+        .await?;
+    db.query("DEFINE FIELD timestamp ON vesting_claims TYPE number")
+        .await?;
+    db.query("DEFINE FIELD tx_digest ON vesting_claims TYPE string")
+        .await?;
+
+    db.query("DEFINE TABLE fee_updates SCHEMAFULL").await?;
+    db.query("DEFINE FIELD previous_fee ON fee_updates TYPE number")
+        .await?;
+    db.query("DEFINE FIELD new_fee ON fee_updates TYPE number")
+        .await?;
+    db.query("DEFINE FIELD timestamp ON fee_updates TYPE option<number>")
+        .await?;
+    db.query("DEFINE FIELD tx_digest ON fee_updates TYPE string")
+        .await?;
+
+    db.query("DEFINE TABLE admin_transfers SCHEMAFULL").await?;
+    db.query("DEFINE FIELD previous_admin ON admin_transfers TYPE string")
+        .await?;
+    db.query("DEFINE FIELD new_admin ON admin_transfers TYPE string")
+        .await?;
+    db.query("DEFINE FIELD timestamp ON admin_transfers TYPE option<number>")
+        .await?;
+    db.query("DEFINE FIELD tx_digest ON admin_transfers TYPE string")
+        .await?;
+
+    db.query("DEFINE TABLE balance_updates SCHEMAFULL").await?;
+    db.query("DEFINE FIELD launchpad_id ON balance_updates TYPE string")
+        .await?;
+    db.query("DEFINE FIELD holder ON balance_updates TYPE string")
+        .await?;
+    db.query("DEFINE FIELD balance ON balance_updates TYPE number")
+        .await?;
+    db.query("DEFINE FIELD timestamp ON balance_updates TYPE number")
+        .await?;
+    db.query("DEFINE FIELD tx_digest ON balance_updates TYPE string")
+        .await?;
+    db.query("DEFINE INDEX balance_updates_holder ON balance_updates FIELDS launchpad_id, holder")
+        .await?;
+
+    // Latest balance update of each holder, keyed by [launchpad_id, holder]
+    db.query("DEFINE TABLE holder_balances SCHEMAFULL").await?;
+    db.query("DEFINE FIELD launchpad_id ON holder_balances TYPE string")
+        .await?;
+    db.query("DEFINE FIELD holder ON holder_balances TYPE string")
+        .await?;
+    db.query("DEFINE FIELD balance ON holder_balances TYPE number")
+        .await?;
+    db.query("DEFINE FIELD updated_at ON holder_balances TYPE number")
+        .await?;
+    db.query(
+        "DEFINE INDEX holder_balances_launchpad ON holder_balances \
+         FIELDS launchpad_id, balance",
+    )
+    .await?;
+
+    db.query("DEFINE TABLE whitelist_updates SCHEMAFULL")
+        .await?;
+    db.query("DEFINE FIELD launchpad_id ON whitelist_updates TYPE string")
+        .await?;
+    db.query("DEFINE FIELD address ON whitelist_updates TYPE string")
+        .await?;
+    db.query("DEFINE FIELD whitelisted ON whitelist_updates TYPE bool")
+        .await?;
+    db.query("DEFINE FIELD timestamp ON whitelist_updates TYPE number")
+        .await?;
+    db.query("DEFINE FIELD tx_digest ON whitelist_updates TYPE string")
+        .await?;
+    db.query("DEFINE INDEX whitelist_entries ON whitelist_updates FIELDS launchpad_id, address")
+        .await?;
+
+    db.query("DEFINE TABLE liquidity_moves SCHEMAFULL").await?;
+    db.query("DEFINE FIELD launchpad_id ON liquidity_moves TYPE string")
+        .await?;
+    db.query("DEFINE FIELD recipient ON liquidity_moves TYPE string")
+        .await?;
+    db.query("DEFINE FIELD sui_amount ON liquidity_moves TYPE number")
+        .await?;
+    db.query("DEFINE FIELD timestamp ON liquidity_moves TYPE number")
+        .await?;
+    db.query("DEFINE FIELD tx_digest ON liquidity_moves TYPE string")
+        .await?;
+
+    db.query("DEFINE TABLE transactions_meta SCHEMAFULL")
+        .await?;
+    db.query("DEFINE FIELD tx_digest ON transactions_meta TYPE string")
+        .await?;
+    db.query("DEFINE FIELD sender ON transactions_meta TYPE string")
+        .await?;
+    db.query("DEFINE FIELD status ON transactions_meta TYPE string")
+        .await?;
+    db.query("DEFINE FIELD error ON transactions_meta TYPE option<string>")
+        .await?;
+    db.query("DEFINE FIELD gas_used ON transactions_meta TYPE number")
+        .await?;
+    db.query("DEFINE FIELD move_call ON transactions_meta TYPE option<string>")
+        .await?;
+    db.query("DEFINE FIELD checkpoint ON transactions_meta TYPE option<number>")
+        .await?;
+    db.query("DEFINE FIELD timestamp ON transactions_meta TYPE option<number>")
+        .await?;
+    // Every event row can point at the transaction that emitted it
+    for table in events::TABLES {
+        db.query(format!(
+            "DEFINE FIELD transaction ON {} TYPE option<record<transactions_meta>>",
+            table
+        ))
+        .await?;
+    }
+
+    db.query("DEFINE TABLE webhook_subscriptions SCHEMAFULL")
+        .await?;
+    db.query("DEFINE FIELD url ON webhook_subscriptions TYPE string")
+        .await?;
+    db.query("DEFINE FIELD secret ON webhook_subscriptions TYPE string")
+        .await?;
+    db.query("DEFINE FIELD event_types ON webhook_subscriptions TYPE array<string>")
+        .await?;
+    db.query("DEFINE FIELD launchpad_id ON webhook_subscriptions TYPE option<string>")
+        .await?;
+    db.query("DEFINE FIELD wallet ON webhook_subscriptions TYPE option<string>")
+        .await?;
+    db.query("DEFINE FIELD created_at ON webhook_subscriptions TYPE datetime")
+        .await?;
+
+    db.query("DEFINE TABLE webhook_deliveries SCHEMAFULL")
+        .await?;
+    db.query("DEFINE FIELD subscription_id ON webhook_deliveries TYPE string")
+        .await?;
+    db.query("DEFINE FIELD event_type ON webhook_deliveries TYPE string")
+        .await?;
+    db.query("DEFINE FIELD tx_digest ON webhook_deliveries TYPE string")
+        .await?;
+    db.query("DEFINE FIELD event_seq ON webhook_deliveries TYPE number")
+        .await?;
+    db.query("DEFINE FIELD payload ON webhook_deliveries TYPE string")
+        .await?;
+    db.query("DEFINE FIELD status ON webhook_deliveries TYPE string")
+        .await?;
+    db.query("DEFINE FIELD attempts ON webhook_deliveries TYPE number")
+        .await?;
+    db.query("DEFINE FIELD last_error ON webhook_deliveries TYPE option<string>")
+        .await?;
+    db.query("DEFINE FIELD created_at ON webhook_deliveries TYPE number")
+        .await?;
+    db.query("DEFINE FIELD updated_at ON webhook_deliveries TYPE number")
+        .await?;
+    db.query("DEFINE FIELD next_attempt_at ON webhook_deliveries TYPE number DEFAULT 0")
+        .await?;
+    db.query("DEFINE INDEX webhook_deliveries_status ON webhook_deliveries FIELDS status")
+        .await?;
+    db.query(
+        "DEFINE INDEX webhook_deliveries_due ON webhook_deliveries \
+         FIELDS status, next_attempt_at",
+    )
+    .await?;
+
+    db.query("DEFINE TABLE api_keys SCHEMAFULL").await?;
+    db.query("DEFINE FIELD name ON api_keys TYPE string")
+        .await?;
+    db.query("DEFINE FIELD key_hash ON api_keys TYPE string")
+        .await?;
+    db.query("DEFINE FIELD prefix ON api_keys TYPE string")
+        .await?;
+    db.query("DEFINE FIELD rate_per_minute ON api_keys TYPE option<number>")
+        .await?;
+    db.query("DEFINE FIELD admin ON api_keys TYPE bool DEFAULT false")
+        .await?;
+    db.query("DEFINE FIELD requests ON api_keys TYPE number")
+        .await?;
+    db.query("DEFINE FIELD rejected ON api_keys TYPE number")
+        .await?;
+    db.query("DEFINE FIELD created_at ON api_keys TYPE number")
+        .await?;
+    db.query("DEFINE FIELD last_used_at ON api_keys TYPE option<number>")
+        .await?;
+    db.query("DEFINE FIELD revoked_at ON api_keys TYPE option<number>")
+        .await?;
+    db.query("DEFINE INDEX api_keys_hash ON api_keys FIELDS key_hash UNIQUE")
+        .await?;
+
+    db.query("DEFINE TABLE pnl_positions SCHEMAFULL").await?;
+    db.query("DEFINE FIELD wallet ON pnl_positions TYPE string")
+        .await?;
+    db.query("DEFINE FIELD launchpad_id ON pnl_positions TYPE string")
+        .await?;
+    db.query("DEFINE FIELD quantity ON pnl_positions TYPE number")
+        .await?;
+    db.query("DEFINE FIELD fifo_lots ON pnl_positions TYPE array<object>")
+        .await?;
+    db.query("DEFINE FIELD fifo_lots.*.amount ON pnl_positions TYPE number")
+        .await?;
+    db.query("DEFINE FIELD fifo_lots.*.price ON pnl_positions TYPE number")
+        .await?;
+    db.query("DEFINE FIELD fifo_realized ON pnl_positions TYPE number")
+        .await?;
+    db.query("DEFINE FIELD average_cost ON pnl_positions TYPE number")
+        .await?;
+    db.query("DEFINE FIELD average_realized ON pnl_positions TYPE number")
+        .await?;
+    db.query("DEFINE FIELD updated_at ON pnl_positions TYPE number")
+        .await?;
+    db.query("DEFINE INDEX pnl_positions_wallet ON pnl_positions FIELDS wallet")
+        .await?;
+
+    db.query("DEFINE TABLE pnl_applied SCHEMAFULL").await?;
+
+    db.query("DEFINE TABLE feed_events SCHEMAFULL").await?;
+    db.query("DEFINE FIELD seq ON feed_events TYPE number")
+        .await?;
+    db.query("DEFINE FIELD payload ON feed_events TYPE string")
+        .await?;
+    db.query("DEFINE INDEX feed_events_seq ON feed_events FIELDS seq UNIQUE")
+        .await?;
+
+    db.query("DEFINE TABLE indexer_state SCHEMAFULL").await?;
+    db.query("DEFINE FIELD tx_digest ON indexer_state TYPE string")
+        .await?;
+    db.query("DEFINE FIELD event_seq ON indexer_state TYPE number")
+        .await?;
+    Ok(())
+}
+
+/// Write `decoded` as the row of its event's table keyed by its event id,
+/// `[tx_digest, event_seq]`, so storing an event again leaves one row. The
+/// row holds the event's fields plus its transaction digest, with the
+/// event's own `timestamp`, where it has one, replaced by the checkpoint
+/// timestamp. Events without a timestamp of their own get the checkpoint's
+/// when known. With `linked`, the row points at the transaction's
+/// `transactions_meta`.
+pub async fn store_event(db: &Surreal<Any>, decoded: &DecodedEvent, linked: bool) -> Result<()> {
+    let DecodedEvent { metadata, event } = decoded;
+
+    let mut row = match event.fields()? {
+        Value::Object(row) => row,
+        other => bail!("{} is not a struct: {}", event.name(), other),
+    };
+    if row.contains_key("timestamp") || metadata.timestamp.is_some() {
+        row.insert("timestamp".to_string(), json!(metadata.timestamp));
+    }
+    row.insert("tx_digest".to_string(), json!(metadata.tx_digest));
+
+    let mut query = db.query("UPSERT type::thing($table, [$tx_digest, $event_seq]) MERGE $row");
+    if linked {
+        query = query.query(
+            "UPDATE type::thing($table, [$tx_digest, $event_seq]) \
+             SET transaction = type::thing('transactions_meta', $tx_digest)",
+        );
+    }
+    query
+        .bind(("table", event.table()))
+        .bind(("row", Value::Object(row)))
+        .bind(("tx_digest", metadata.tx_digest.clone()))
+        .bind(("event_seq", metadata.event_seq))
+        .await?
+        .check()?;
+    Ok(())
+}
+
+pub async fn load_cursor(db: &Surreal<Any>) -> Result<Option<EventID>> {
+    let cursor: Option<StoredCursor> = db.select(("indexer_state", "cursor")).await?;
+    cursor
+        .map(|c| {
+            Ok(EventID {
+                tx_digest: TransactionDigest::from_str(&c.tx_digest)?,
+                event_seq: c.event_seq,
+            })
+        })
+        .transpose()
+}
+
+pub async fn save_cursor(db: &Surreal<Any>, cursor: &EventID) -> Result<()> {
+    let _saved: Option<StoredCursor> = db
+        .upsert(("indexer_state", "cursor"))
+        .content(StoredCursor {
+            tx_digest: cursor.tx_digest.to_string(),
+            event_seq: cursor.event_seq,
+        })
+        .await?;
+    Ok(())
+}
+
+// Helper functions to query the database
+pub async fn get_holder_balance(db: &Surreal<Any>, wallet_address: &str) -> Result<Option<u64>> {
+    let holder: Option<Holder> = db.select(("holders", wallet_address)).await?;
+    Ok(holder.map(|h| h.balance))
+}
+
+pub async fn get_transactions(db: &Surreal<Any>, wallet_address: &str) -> Result<Vec<Transaction>> {
+    let transactions: Vec<Transaction> = db
+        .query("SELECT transaction_type, timestamp, tx_digest FROM transactions WHERE wallet_address = $address ORDER BY timestamp DESC")
+        .bind(("address", wallet_address.to_string()))
+        .await?
+        .take(0)?;
+    Ok(transactions)
+}
+
+// Latest balance per launchpad and holder among the balance updates matching
+// `condition`, largest first
+async fn latest_balances(
+    db: &Surreal<Any>,
+    condition: &str,
+    binds: Vec<(&'static str, String)>,
+) -> Result<Vec<Balance>> {
+    let mut query = db.query(format!(
+        "SELECT launchpad_id, holder, balance, timestamp AS updated_at FROM balance_updates \
+         WHERE {} ORDER BY updated_at ASC",
+        condition
+    ));
+    for bind in binds {
+        query = query.bind(bind);
+    }
+    let updates: Vec<Balance> = query.await?.take(0)?;
+
+    let mut latest = HashMap::new();
+    for update in updates {
+        latest.insert((update.launchpad_id.clone(), update.holder.clone()), update);
+    }
+    let mut balances: Vec<Balance> = latest.into_values().collect();
+    balances.sort_by(|a, b| {
+        b.balance
+            .cmp(&a.balance)
+            .then_with(|| b.holder.cmp(&a.holder))
+            .then_with(|| a.launchpad_id.cmp(&b.launchpad_id))
+    });
+    Ok(balances)
+}
+
+pub async fn launchpad_balances(db: &Surreal<Any>, launchpad_id: &str) -> Result<Vec<Balance>> {
+    latest_balances(
+        db,
+        "launchpad_id = $launchpad_id",
+        vec![("launchpad_id", launchpad_id.to_string())],
+    )
+    .await
+}
+
+pub async fn wallet_balances(db: &Surreal<Any>, wallet: &str) -> Result<Vec<Balance>> {
+    latest_balances(db, "holder = $holder", vec![("holder", wallet.to_string())]).await
+}
+
+pub async fn balance(
+    db: &Surreal<Any>,
+    launchpad_id: &str,
+    holder: &str,
+) -> Result<Option<Balance>> {
+    let balance: Option<Balance> = db
+        .query(
+            "SELECT launchpad_id, holder, balance, timestamp AS updated_at FROM balance_updates \
+             WHERE launchpad_id = $launchpad_id AND holder = $holder \
+             ORDER BY updated_at DESC LIMIT 1",
+        )
+        .bind(("launchpad_id", launchpad_id.to_string()))
+        .bind(("holder", holder.to_string()))
+        .await?
+        .take(0)?;
+    Ok(balance)
+}
+
+async fn store_holder_balance(db: &Surreal<Any>, balance: Balance) -> Result<()> {
+    db.query("UPSERT type::thing('holder_balances', [$launchpad_id, $holder]) CONTENT $balance")
+        .bind(("launchpad_id", balance.launchpad_id.clone()))
+        .bind(("holder", balance.holder.clone()))
+        .bind(("balance", balance))
+        .await?
+        .check()?;
+    Ok(())
+}
+
+/// Bring `holder_balances` up to date with the latest stored balance update
+/// of `holder` in `launchpad_id`, whatever order updates were stored in.
+pub async fn refresh_holder_balance(
+    db: &Surreal<Any>,
+    launchpad_id: &str,
+    holder: &str,
+) -> Result<()> {
+    if let Some(latest) = balance(db, launchpad_id, holder).await? {
+        store_holder_balance(db, latest).await?;
+    }
+    Ok(())
+}
+
+/// Fill `holder_balances` from the stored balance updates if it is empty,
+/// e.g. in a database written before the table existed.
+pub async fn fill_holder_balances(db: &Surreal<Any>) -> Result<()> {
+    let filled: Vec<Thing> = db
+        .query("SELECT VALUE id FROM holder_balances LIMIT 1")
+        .await?
+        .take(0)?;
+    if !filled.is_empty() {
+        return Ok(());
+    }
+    for balance in latest_balances(db, "true", Vec::new()).await? {
+        store_holder_balance(db, balance).await?;
+    }
+    Ok(())
+}
+
+/// Latest balances of `launchpad_id`'s holders of at least `min_balance`,
+/// largest first and by holder descending among equal ones, starting after
+/// the `(balance, holder)` given.
+pub async fn holder_balances(
+    db: &Surreal<Any>,
+    launchpad_id: &str,
+    min_balance: u64,
+    after: Option<(u64, String)>,
+    limit: usize,
+) -> Result<Vec<Balance>> {
+    let resume = if after.is_some() {
+        "AND (balance < $after_balance \
+         OR (balance = $after_balance AND holder < $after_holder))"
+    } else {
+        ""
+    };
+    let (after_balance, after_holder) = after.unzip();
+    let balances = db
+        .query(format!(
+            "SELECT launchpad_id, holder, balance, updated_at FROM holder_balances \
+             WHERE launchpad_id = $launchpad_id AND balance >= $min_balance {} \
+             ORDER BY balance DESC, holder DESC LIMIT $limit",
+            resume
+        ))
+        .bind(("launchpad_id", launchpad_id.to_string()))
+        .bind(("min_balance", min_balance))
+        .bind(("after_balance", after_balance))
+        .bind(("after_holder", after_holder))
+        .bind(("limit", limit))
+        .await?
+        .take(0)?;
+    Ok(balances)
+}
+
+#[derive(Deserialize)]
+struct PricePoint {
+    new_price: u64,
+}
+
+/// Latest `PriceUpdate.new_price` on a launchpad.
+pub async fn latest_price(db: &Surreal<Any>, launchpad_id: &str) -> Result<Option<u64>> {
+    let latest: Option<PricePoint> = db
+        .query(format!(
+            "SELECT new_price, timestamp FROM price_updates WHERE {} \
+             ORDER BY timestamp DESC LIMIT 1",
+            LAUNCHPAD_TXS
+        ))
+        .bind(("launchpad_id", launchpad_id.to_string()))
+        .await?
+        .take(0)?;
+    Ok(latest.map(|p| p.new_price))
+}
+
+/// Price in effect just before `timestamp`: the last PriceUpdate set before
+/// it, else the launchpad's initial price.
+pub async fn price_before(
+    db: &Surreal<Any>,
+    launchpad_id: &str,
+    timestamp: u64,
+) -> Result<Option<u64>> {
+    let mut response = db
+        .query(format!(
+            "SELECT new_price, timestamp FROM price_updates WHERE {} AND timestamp < $timestamp \
+             ORDER BY timestamp DESC LIMIT 1",
+            LAUNCHPAD_TXS
+        ))
+        .query("SELECT VALUE initial_price FROM launchpads WHERE launchpad_id = $launchpad_id")
+        .bind(("launchpad_id", launchpad_id.to_string()))
+        .bind(("timestamp", timestamp))
+        .await?;
+    let before: Option<PricePoint> = response.take(0)?;
+    let initial: Vec<u64> = response.take(1)?;
+    Ok(before.map(|p| p.new_price).or(initial.first().copied()))
+}
+
+/// Launchpad of the transaction `tx_digest`, known once its BalanceUpdate is
+/// stored.
+pub async fn tx_launchpad(db: &Surreal<Any>, tx_digest: &str) -> Result<Option<String>> {
+    let ids: Vec<String> = db
+        .query("SELECT VALUE launchpad_id FROM balance_updates WHERE tx_digest = $tx_digest")
+        .bind(("tx_digest", tx_digest.to_string()))
+        .await?
+        .take(0)?;
+    Ok(ids.into_iter().next())
+}
+
+#[derive(Deserialize)]
+struct TxLaunchpad {
+    tx_digest: String,
+    launchpad_id: String,
+}
+
+/// Launchpad of each of `tx_digests` whose BalanceUpdate is stored.
+pub async fn tx_launchpads(
+    db: &Surreal<Any>,
+    tx_digests: Vec<String>,
+) -> Result<HashMap<String, String>> {
+    let links: Vec<TxLaunchpad> = db
+        .query(
+            "SELECT tx_digest, launchpad_id FROM balance_updates WHERE tx_digest INSIDE $digests",
+        )
+        .bind(("digests", tx_digests))
+        .await?
+        .take(0)?;
+    Ok(links
+        .into_iter()
+        .map(|link| (link.tx_digest, link.launchpad_id))
+        .collect())
+}
+
+#[derive(Deserialize)]
+struct Unattributed {
+    event_seq: u64,
+    amount: u64,
+    timestamp: u64,
+}
+
+/// Record `launchpad_id` on the purchases of `tx_digest` that don't have it
+/// yet, with the SUI they raised at the price in effect before each.
+pub async fn attribute_purchases(
+    db: &Surreal<Any>,
+    tx_digest: &str,
+    launchpad_id: &str,
+) -> Result<()> {
+    let purchases: Vec<Unattributed> = db
+        .query(
+            "SELECT meta::id(id)[1] AS event_seq, amount, timestamp FROM token_purchases \
+             WHERE tx_digest = $tx_digest AND launchpad_id = NONE",
+        )
+        .bind(("tx_digest", tx_digest.to_string()))
+        .await?
+        .take(0)?;
+    for purchase in purchases {
+        let price = price_before(db, launchpad_id, purchase.timestamp)
+            .await?
+            .unwrap_or(0);
+        let raised = price.saturating_mul(purchase.amount).min(i64::MAX as u64);
+        db.query(
+            "UPDATE type::thing('token_purchases', [$tx_digest, $event_seq]) \
+             SET launchpad_id = $launchpad_id, raised = $raised",
+        )
+        .bind(("tx_digest", tx_digest.to_string()))
+        .bind(("event_seq", purchase.event_seq))
+        .bind(("launchpad_id", launchpad_id.to_string()))
+        .bind(("raised", raised))
+        .await?
+        .check()?;
+    }
+    Ok(())
+}
+
+/// Attribute every stored purchase whose BalanceUpdate is stored but which
+/// isn't attributed yet, e.g. ones stored before purchases were.
+pub async fn attribute_stored_purchases(db: &Surreal<Any>) -> Result<()> {
+    let digests: Vec<String> = db
+        .query("SELECT VALUE tx_digest FROM token_purchases WHERE launchpad_id = NONE")
+        .await?
+        .take(0)?;
+    if digests.is_empty() {
+        return Ok(());
+    }
+    for (tx_digest, launchpad_id) in tx_launchpads(db, digests).await? {
+        attribute_purchases(db, &tx_digest, &launchpad_id).await?;
+    }
+    Ok(())
+}
+
+/// An address on a launchpad's whitelist.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct WhitelistEntry {
+    pub address: String,
+    /// When the address was last put on the whitelist, in milliseconds.
+    pub added_at: u64,
+}
+
+#[derive(Deserialize)]
+struct WhitelistUpdate {
+    address: String,
+    whitelisted: bool,
+    timestamp: u64,
+}
+
+// Addresses left on a launchpad's whitelist by its updates, optionally only
+// `address`
+async fn whitelisted(
+    db: &Surreal<Any>,
+    launchpad_id: &str,
+    address: Option<&str>,
+) -> Result<Vec<WhitelistEntry>> {
+    let condition = if address.is_some() {
+        "AND address = $address"
+    } else {
+        ""
+    };
+    let updates: Vec<WhitelistUpdate> = db
+        .query(format!(
+            "SELECT address, whitelisted, timestamp FROM whitelist_updates \
+             WHERE launchpad_id = $launchpad_id {} ORDER BY timestamp ASC, id ASC",
+            condition
+        ))
+        .bind(("launchpad_id", launchpad_id.to_string()))
+        .bind(("address", address.map(str::to_string)))
+        .await?
+        .take(0)?;
+    let mut added_at: HashMap<String, u64> = HashMap::new();
+    for update in updates {
+        if !update.whitelisted {
+            added_at.remove(&update.address);
+        } else if !added_at.contains_key(&update.address) {
+            added_at.insert(update.address, update.timestamp);
+        }
+    }
+    Ok(added_at
+        .into_iter()
+        .map(|(address, added_at)| WhitelistEntry { address, added_at })
+        .collect())
+}
+
+/// Addresses whitelisted on a launchpad, oldest first.
+pub async fn whitelist(db: &Surreal<Any>, launchpad_id: &str) -> Result<Vec<WhitelistEntry>> {
+    let mut entries = whitelisted(db, launchpad_id, None).await?;
+    entries.sort_by(|a, b| {
+        a.added_at
+            .cmp(&b.added_at)
+            .then_with(|| a.address.cmp(&b.address))
+    });
+    Ok(entries)
+}
+
+/// `address`'s whitelist entry on a launchpad, if it is whitelisted.
+pub async fn whitelist_entry(
+    db: &Surreal<Any>,
+    launchpad_id: &str,
+    address: &str,
+) -> Result<Option<WhitelistEntry>> {
+    let entries = whitelisted(db, launchpad_id, Some(address)).await?;
+    Ok(entries.into_iter().next())
+}
+
+#[derive(Deserialize)]
+struct PriceRow {
+    new_price: u64,
+    timestamp: u64,
+    tx_digest: String,
+}
+
+#[derive(Deserialize)]
+struct InitialPrice {
+    launchpad_id: String,
+    initial_price: u64,
+}
+
+/// Prices of a set of launchpads over time, for valuing trades in bulk.
+#[derive(Debug, Default)]
+pub struct PriceHistory {
+    // (timestamp, price), oldest first
+    prices: HashMap<String, Vec<(u64, u64)>>,
+    initial: HashMap<String, u64>,
+}
+
+impl PriceHistory {
+    pub async fn load(db: &Surreal<Any>, launchpad_ids: Vec<String>) -> Result<Self> {
+        let mut response = db
+            .query(
+                "SELECT tx_digest, launchpad_id FROM balance_updates WHERE launchpad_id INSIDE $ids",
+            )
+            .query(
+                "SELECT new_price, timestamp, tx_digest FROM price_updates WHERE tx_digest INSIDE \
+                 (SELECT VALUE tx_digest FROM balance_updates WHERE launchpad_id INSIDE $ids) \
+                 ORDER BY timestamp ASC",
+            )
+            .query(
+                "SELECT launchpad_id, initial_price FROM launchpads WHERE launchpad_id INSIDE $ids",
+            )
+            .bind(("ids", launchpad_ids))
+            .await?;
+        let links: Vec<TxLaunchpad> = response.take(0)?;
+        let rows: Vec<PriceRow> = response.take(1)?;
+        let initial: Vec<InitialPrice> = response.take(2)?;
+
+        let launchpad_of: HashMap<String, String> = links
+            .into_iter()
+            .map(|link| (link.tx_digest, link.launchpad_id))
+            .collect();
+        let mut prices: HashMap<String, Vec<(u64, u64)>> = HashMap::new();
+        for row in rows {
+            if let Some(launchpad_id) = launchpad_of.get(&row.tx_digest) {
+                prices
+                    .entry(launchpad_id.clone())
+                    .or_default()
+                    .push((row.timestamp, row.new_price));
+            }
+        }
+        Ok(Self {
+            prices,
+            initial: initial
+                .into_iter()
+                .map(|i| (i.launchpad_id, i.initial_price))
+                .collect(),
+        })
+    }
+
+    /// Same as [`price_before`]: prices set in a trade's own transaction
+    /// apply after it.
+    pub fn before(&self, launchpad_id: &str, timestamp: u64) -> Option<u64> {
+        let prices = self.prices.get(launchpad_id).map_or(&[][..], Vec::as_slice);
+        let before = prices.partition_point(|(t, _)| *t < timestamp);
+        match before.checked_sub(1) {
+            Some(i) => Some(prices[i].1),
+            None => self.initial.get(launchpad_id).copied(),
+        }
+    }
+
+    pub fn latest(&self, launchpad_id: &str) -> Option<u64> {
+        self.prices
+            .get(launchpad_id)
+            .and_then(|prices| prices.last())
+            .map(|(_, price)| *price)
+    }
+}
@@ -0,0 +1,249 @@
+//! Context of the transactions that emitted events.
+//!
+//! Events only carry their transaction digest. The first time a digest is
+//! seen its transaction block is fetched through a [`TransactionSource`],
+//! the node by default, and kept in `transactions_meta`, which every event
+//! row from that transaction links to through its `transaction` field. The
+//! ingestion pipeline fetches the blocks of a whole batch in one request, and
+//! rows stored while the node couldn't be reached are linked later by
+//! [`link_unlinked`]. The sender is only kept here.
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::str::FromStr;
+use sui_sdk::rpc_types::{
+    SuiCommand, SuiExecutionStatus, SuiTransactionBlockDataAPI, SuiTransactionBlockEffectsAPI,
+    SuiTransactionBlockKind, SuiTransactionBlockResponse, SuiTransactionBlockResponseOptions,
+};
+use sui_sdk::types::digests::TransactionDigest;
+use sui_sdk::SuiClient;
+use surrealdb::{engine::any::Any, Surreal};
+use tracing::warn;
+use utoipa::ToSchema;
+
+use crate::events;
+
+// Most digests the node accepts in one `sui_multiGetTransactionBlocks`
+pub const MAX_BATCH: usize = 50;
+
+/// What a transaction block says about the transaction.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct TransactionMeta {
+    pub tx_digest: String,
+    pub sender: String,
+    /// `success` or `failure`.
+    pub status: String,
+    /// Why the transaction failed, if it did.
+    pub error: Option<String>,
+    /// Computation plus storage cost less the storage rebate, in MIST.
+    pub gas_used: i64,
+    /// First Move call of the transaction, as `package::module::function`.
+    pub move_call: Option<String>,
+    pub checkpoint: Option<u64>,
+    /// Checkpoint timestamp, in milliseconds.
+    pub timestamp: Option<u64>,
+}
+
+/// Where transaction blocks are looked up.
+#[async_trait]
+pub trait TransactionSource: Send + Sync {
+    async fn transaction(&self, tx_digest: &str) -> Result<TransactionMeta>;
+
+    /// Up to [`MAX_BATCH`] transactions at once, in any order. Sources that
+    /// can't batch look each one up.
+    async fn transactions(&self, tx_digests: &[String]) -> Result<Vec<TransactionMeta>> {
+        let mut metas = Vec::with_capacity(tx_digests.len());
+        for tx_digest in tx_digests {
+            metas.push(self.transaction(tx_digest).await?);
+        }
+        Ok(metas)
+    }
+}
+
+fn options() -> SuiTransactionBlockResponseOptions {
+    SuiTransactionBlockResponseOptions::new()
+        .with_input()
+        .with_effects()
+}
+
+#[async_trait]
+impl TransactionSource for SuiClient {
+    async fn transaction(&self, tx_digest: &str) -> Result<TransactionMeta> {
+        let digest = TransactionDigest::from_str(tx_digest)?;
+        let response = self
+            .read_api()
+            .get_transaction_with_options(digest, options())
+            .await?;
+        meta(response)
+    }
+
+    async fn transactions(&self, tx_digests: &[String]) -> Result<Vec<TransactionMeta>> {
+        let digests = tx_digests
+            .iter()
+            .map(|d| TransactionDigest::from_str(d))
+            .collect::<Result<Vec<_>, _>>()?;
+        let responses = self
+            .read_api()
+            .multi_get_transactions_with_options(digests, options())
+            .await?;
+        responses.into_iter().map(meta).collect()
+    }
+}
+
+// What the indexer keeps of a transaction block
+fn meta(response: SuiTransactionBlockResponse) -> Result<TransactionMeta> {
+    let tx_digest = response.digest.to_string();
+    let data = response
+        .transaction
+        .context("transaction block has no input")?
+        .data;
+    let effects = response
+        .effects
+        .context("transaction block has no effects")?;
+
+    let move_call = match data.transaction() {
+        SuiTransactionBlockKind::ProgrammableTransaction(programmable) => programmable
+            .commands
+            .iter()
+            .find_map(|command| match command {
+                SuiCommand::MoveCall(call) => Some(format!(
+                    "{}::{}::{}",
+                    call.package, call.module, call.function
+                )),
+                _ => None,
+            }),
+        _ => None,
+    };
+    let (status, error) = match effects.status() {
+        SuiExecutionStatus::Success => ("success", None),
+        SuiExecutionStatus::Failure { error } => ("failure", Some(error.clone())),
+    };
+    Ok(TransactionMeta {
+        tx_digest,
+        sender: data.sender().to_string(),
+        status: status.to_string(),
+        error,
+        gas_used: effects.gas_cost_summary().net_gas_usage(),
+        move_call,
+        checkpoint: response.checkpoint,
+        timestamp: response.timestamp_ms,
+    })
+}
+
+/// The stored context of `tx_digest`, if it has been fetched.
+pub async fn transaction(db: &Surreal<Any>, tx_digest: &str) -> Result<Option<TransactionMeta>> {
+    let meta = db
+        .query("SELECT * OMIT id FROM type::thing('transactions_meta', $tx_digest)")
+        .bind(("tx_digest", tx_digest.to_string()))
+        .await?
+        .take(0)?;
+    Ok(meta)
+}
+
+/// Context of `tx_digest`, fetched from `source` and stored unless it
+/// already is.
+pub async fn context(
+    db: &Surreal<Any>,
+    source: &dyn TransactionSource,
+    tx_digest: &str,
+) -> Result<TransactionMeta> {
+    if let Some(meta) = transaction(db, tx_digest).await? {
+        return Ok(meta);
+    }
+    let meta = source.transaction(tx_digest).await?;
+    store(db, &meta).await?;
+    Ok(meta)
+}
+
+async fn store(db: &Surreal<Any>, meta: &TransactionMeta) -> Result<()> {
+    db.query("UPSERT type::thing('transactions_meta', $tx_digest) CONTENT $meta")
+        .bind(("tx_digest", meta.tx_digest.clone()))
+        .bind(("meta", meta.clone()))
+        .await?
+        .check()?;
+    Ok(())
+}
+
+/// Fetch and store the transactions of `tx_digests` that aren't stored yet,
+/// [`MAX_BATCH`] per request. Returns how many were fetched.
+pub async fn prefetch(
+    db: &Surreal<Any>,
+    source: &dyn TransactionSource,
+    tx_digests: Vec<String>,
+) -> Result<usize> {
+    let stored: Vec<String> = db
+        .query("SELECT VALUE tx_digest FROM transactions_meta WHERE tx_digest INSIDE $digests")
+        .bind(("digests", tx_digests.clone()))
+        .await?
+        .take(0)?;
+    let stored: HashSet<String> = stored.into_iter().collect();
+    let mut missing: Vec<String> = tx_digests
+        .into_iter()
+        .filter(|d| !stored.contains(d))
+        .collect();
+    missing.sort();
+    missing.dedup();
+
+    let mut fetched = 0;
+    for chunk in missing.chunks(MAX_BATCH) {
+        for meta in source.transactions(chunk).await? {
+            store(db, &meta).await?;
+            fetched += 1;
+        }
+    }
+    Ok(fetched)
+}
+
+/// Link event rows stored without their transaction, e.g. while the node
+/// was unreachable, fetching up to `limit` of their transactions. Rows
+/// without a timestamp of their own get the checkpoint's. A request that
+/// fails is logged and left for the next pass. Returns how many
+/// transactions were fetched.
+pub async fn link_unlinked(
+    db: &Surreal<Any>,
+    source: &dyn TransactionSource,
+    limit: usize,
+) -> Result<usize> {
+    let mut digests = Vec::new();
+    for table in events::TABLES {
+        let unlinked: Vec<String> = db
+            .query(format!(
+                "SELECT VALUE tx_digest FROM {} WHERE transaction = NONE LIMIT {}",
+                table, limit
+            ))
+            .await?
+            .take(0)?;
+        digests.extend(unlinked);
+    }
+    digests.sort();
+    digests.dedup();
+    digests.truncate(limit);
+    if digests.is_empty() {
+        return Ok(0);
+    }
+
+    let mut fetched = 0;
+    for chunk in digests.chunks(MAX_BATCH) {
+        match prefetch(db, source, chunk.to_vec()).await {
+            Ok(count) => fetched += count,
+            Err(e) => warn!(error = %e, "Failed to look up transactions of unlinked rows"),
+        }
+    }
+    for table in events::TABLES {
+        db.query(format!(
+            "UPDATE {0} SET transaction = type::thing('transactions_meta', tx_digest) \
+             WHERE transaction = NONE AND tx_digest INSIDE \
+             (SELECT VALUE tx_digest FROM transactions_meta WHERE tx_digest INSIDE $digests); \
+             UPDATE {0} SET timestamp = transaction.timestamp \
+             WHERE timestamp = NONE AND transaction.timestamp != NONE \
+             AND tx_digest INSIDE $digests",
+            table
+        ))
+        .bind(("digests", digests.clone()))
+        .await?
+        .check()?;
+    }
+    Ok(fetched)
+}
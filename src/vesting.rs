@@ -0,0 +1,293 @@
+//! Vesting positions reconstructed from purchases and claims.
+//!
+//! Tokens bought on a vesting launchpad unlock once the launchpad's vesting
+//! duration has passed since the purchase. LaunchpadCreated doesn't carry
+//! the vesting terms given to `create_launchpad`, so they are read from the
+//! launchpad object through a [`VestingSource`] when the launchpad is
+//! created, and kept in `launchpad_vesting`. Launchpads without vesting, or
+//! whose terms aren't known yet, have no positions. Claims are linked to a
+//! launchpad through the BalanceUpdate of their transaction.
+
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use sui_sdk::rpc_types::{SuiObjectDataOptions, SuiParsedData};
+use sui_sdk::types::base_types::ObjectID;
+use sui_sdk::SuiClient;
+use surrealdb::{engine::any::Any, Surreal};
+use tracing::warn;
+use utoipa::ToSchema;
+
+use crate::storage::{self, LAUNCHPAD_TXS};
+
+/// Vesting terms a launchpad was created with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct VestingTerms {
+    pub enabled: bool,
+    /// Time from a purchase until its tokens unlock, in milliseconds.
+    pub duration_ms: u64,
+}
+
+/// Where launchpads' vesting terms are looked up.
+#[async_trait]
+pub trait VestingSource: Send + Sync {
+    async fn terms(&self, launchpad_id: &str) -> Result<VestingTerms>;
+}
+
+// `u64` fields of Move objects are rendered as strings
+fn number(fields: &Value, name: &str) -> Result<u64> {
+    match &fields[name] {
+        Value::String(s) => Ok(s.parse()?),
+        Value::Number(n) => n.as_u64().with_context(|| format!("{} out of range", name)),
+        _ => bail!("launchpad object has no {}", name),
+    }
+}
+
+#[async_trait]
+impl VestingSource for SuiClient {
+    async fn terms(&self, launchpad_id: &str) -> Result<VestingTerms> {
+        let id = ObjectID::from_hex_literal(launchpad_id)?;
+        let response = self
+            .read_api()
+            .get_object_with_options(id, SuiObjectDataOptions::new().with_content())
+            .await?;
+        let content = response
+            .data
+            .and_then(|data| data.content)
+            .context("launchpad object has no content")?;
+        let SuiParsedData::MoveObject(object) = content else {
+            bail!("launchpad {} is not a Move object", launchpad_id);
+        };
+        let fields = object.fields.to_json_value();
+        // Named after `create_launchpad`'s parameters. The package source
+        // isn't in this repository and no launchpad object has been captured
+        // to check them against; a missing field fails here rather than
+        // reading as no vesting.
+        Ok(VestingTerms {
+            enabled: fields["vesting_enabled"]
+                .as_bool()
+                .context("launchpad object has no vesting_enabled")?,
+            duration_ms: number(&fields, "vesting_duration")?,
+        })
+    }
+}
+
+/// Record the vesting terms of `launchpad_id`.
+pub async fn store_terms(db: &Surreal<Any>, launchpad_id: &str, terms: VestingTerms) -> Result<()> {
+    db.query("UPSERT type::thing('launchpad_vesting', $launchpad_id) CONTENT $terms")
+        .bind(("launchpad_id", launchpad_id.to_string()))
+        .bind(("terms", terms))
+        .await?
+        .check()?;
+    Ok(())
+}
+
+#[derive(Deserialize)]
+struct StoredTerms {
+    launchpad_id: String,
+    enabled: bool,
+    duration_ms: u64,
+}
+
+/// Stored vesting terms of each of `launchpad_ids` that has them.
+pub async fn terms(
+    db: &Surreal<Any>,
+    launchpad_ids: Vec<String>,
+) -> Result<HashMap<String, VestingTerms>> {
+    let stored: Vec<StoredTerms> = db
+        .query(
+            "SELECT meta::id(id) AS launchpad_id, enabled, duration_ms FROM launchpad_vesting \
+             WHERE meta::id(id) INSIDE $ids",
+        )
+        .bind(("ids", launchpad_ids))
+        .await?
+        .take(0)?;
+    Ok(stored
+        .into_iter()
+        .map(|t| {
+            let terms = VestingTerms {
+                enabled: t.enabled,
+                duration_ms: t.duration_ms,
+            };
+            (t.launchpad_id, terms)
+        })
+        .collect())
+}
+
+/// Fetch the terms of every stored launchpad that doesn't have them yet,
+/// e.g. ones created while no source was configured. Failed lookups are
+/// left for the next run.
+pub async fn fetch_missing_terms(db: &Surreal<Any>, source: &dyn VestingSource) -> Result<usize> {
+    let missing: Vec<String> = db
+        .query(
+            "SELECT VALUE launchpad_id FROM launchpads \
+             WHERE launchpad_id NOT INSIDE (SELECT VALUE meta::id(id) FROM launchpad_vesting)",
+        )
+        .await?
+        .take(0)?;
+    let mut fetched = 0;
+    for launchpad_id in missing {
+        match source.terms(&launchpad_id).await {
+            Ok(terms) => {
+                store_terms(db, &launchpad_id, terms).await?;
+                fetched += 1;
+            }
+            Err(e) => {
+                warn!(%launchpad_id, error = %e, "Failed to look up vesting terms")
+            }
+        }
+    }
+    Ok(fetched)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct VestingClaim {
+    pub wallet: String,
+    pub amount: u64,
+    /// Milliseconds since the epoch.
+    pub timestamp: u64,
+    pub tx_digest: String,
+}
+
+/// A wallet's vesting in one launchpad.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct VestingPosition {
+    pub wallet: String,
+    pub launchpad_id: String,
+    /// Tokens bought, all of which vest.
+    pub allocated: u64,
+    pub claimed: u64,
+    /// Allocated but not yet claimed.
+    pub remaining: u64,
+    /// Unlocked so far.
+    pub unlocked: u64,
+    /// Unlocked but not yet claimed.
+    pub claimable: u64,
+    /// When the next purchase unlocks, if any is still locked.
+    pub next_unlock_at: Option<u64>,
+    /// Tokens unlocking at `next_unlock_at`.
+    pub next_unlock_amount: u64,
+    /// When the last purchase unlocks.
+    pub fully_vested_at: Option<u64>,
+    /// Oldest first.
+    pub claims: Vec<VestingClaim>,
+}
+
+#[derive(Deserialize)]
+struct Purchase {
+    amount: u64,
+    timestamp: u64,
+    tx_digest: String,
+}
+
+#[derive(Deserialize)]
+struct Claim {
+    user: String,
+    amount: u64,
+    timestamp: u64,
+    tx_digest: String,
+}
+
+impl From<Claim> for VestingClaim {
+    fn from(claim: Claim) -> Self {
+        Self {
+            wallet: claim.user,
+            amount: claim.amount,
+            timestamp: claim.timestamp,
+            tx_digest: claim.tx_digest,
+        }
+    }
+}
+
+/// Vesting positions of `wallet` as of `now`, in the launchpads it bought
+/// from that vest.
+pub async fn positions(db: &Surreal<Any>, wallet: &str, now: u64) -> Result<Vec<VestingPosition>> {
+    let mut response = db
+        .query(
+            "SELECT amount, timestamp, tx_digest FROM token_purchases \
+             WHERE buyer = $wallet ORDER BY timestamp ASC",
+        )
+        .query(
+            "SELECT user, amount, timestamp, tx_digest FROM vesting_claims \
+             WHERE user = $wallet ORDER BY timestamp ASC",
+        )
+        .bind(("wallet", wallet.to_string()))
+        .await?;
+    let purchases: Vec<Purchase> = response.take(0)?;
+    let claims: Vec<Claim> = response.take(1)?;
+
+    let digests: HashSet<String> = purchases
+        .iter()
+        .map(|p| p.tx_digest.clone())
+        .chain(claims.iter().map(|c| c.tx_digest.clone()))
+        .collect();
+    let launchpad_of = storage::tx_launchpads(db, digests.into_iter().collect()).await?;
+    let ids: HashSet<String> = launchpad_of.values().cloned().collect();
+    let terms = terms(db, ids.into_iter().collect()).await?;
+
+    let mut positions: BTreeMap<String, VestingPosition> = BTreeMap::new();
+    for purchase in &purchases {
+        let Some(id) = launchpad_of.get(&purchase.tx_digest) else {
+            continue;
+        };
+        let Some(duration) = terms
+            .get(id)
+            .filter(|terms| terms.enabled)
+            .map(|terms| terms.duration_ms)
+        else {
+            continue;
+        };
+        let position = positions
+            .entry(id.clone())
+            .or_insert_with(|| VestingPosition {
+                wallet: wallet.to_string(),
+                launchpad_id: id.clone(),
+                ..VestingPosition::default()
+            });
+        let unlock_at = purchase.timestamp.saturating_add(duration);
+        position.allocated += purchase.amount;
+        position.fully_vested_at = position.fully_vested_at.max(Some(unlock_at));
+        if unlock_at <= now {
+            position.unlocked += purchase.amount;
+        } else if position.next_unlock_at.is_none_or(|next| unlock_at < next) {
+            position.next_unlock_at = Some(unlock_at);
+            position.next_unlock_amount = purchase.amount;
+        } else if position.next_unlock_at == Some(unlock_at) {
+            position.next_unlock_amount += purchase.amount;
+        }
+    }
+
+    for claim in claims {
+        let id = launchpad_of.get(&claim.tx_digest);
+        if let Some(position) = id.and_then(|id| positions.get_mut(id)) {
+            position.claimed += claim.amount;
+            position.claims.push(claim.into());
+        }
+    }
+
+    Ok(positions
+        .into_values()
+        .map(|mut position| {
+            position.remaining = position.allocated.saturating_sub(position.claimed);
+            position.claimable = position.unlocked.saturating_sub(position.claimed);
+            position
+        })
+        .collect())
+}
+
+/// Claims made in a launchpad, newest first. Only claims whose transaction
+/// has a BalanceUpdate can be found this way.
+pub async fn launchpad_claims(db: &Surreal<Any>, launchpad_id: &str) -> Result<Vec<VestingClaim>> {
+    let claims: Vec<Claim> = db
+        .query(format!(
+            "SELECT user, amount, timestamp, tx_digest FROM vesting_claims WHERE {} \
+             ORDER BY timestamp DESC",
+            LAUNCHPAD_TXS
+        ))
+        .bind(("launchpad_id", launchpad_id.to_string()))
+        .await?
+        .take(0)?;
+    Ok(claims.into_iter().map(VestingClaim::from).collect())
+}
@@ -0,0 +1,656 @@
+//! Push delivery of indexed events to subscriber URLs.
+//!
+//! Every stored event that matches a subscription's filter gets a row in
+//! `webhook_deliveries` and is POSTed to the subscriber as JSON, signed with
+//! HMAC-SHA256 over the body using the subscription's secret and sent in the
+//! `X-Webhook-Signature: sha256=<hex>` header. Failed attempts are retried
+//! with exponential backoff until `max_attempts`, after which the delivery is
+//! dead-lettered, as is a delivery whose subscription was removed before an
+//! attempt. Deliveries wait in a bounded queue for one of the senders; those
+//! that don't fit, are due for a retry or were still pending when the
+//! process stopped are picked up from the database.
+//!
+//! The secret is only returned when the subscription is created. Targets
+//! that resolve to private, loopback or link-local addresses are refused,
+//! both when subscribing and by the resolver every attempt connects through,
+//! and redirects are not followed, unless `WEBHOOK_ALLOW_PRIVATE_TARGETS` is
+//! set.
+
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::Sha256;
+use std::collections::HashSet;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use surrealdb::{engine::any::Any, Surreal};
+use tokio::sync::mpsc;
+use tracing::{debug, error, info, warn};
+use utoipa::ToSchema;
+
+use crate::decoding::EventMetadata;
+use crate::env_or;
+use crate::events::LaunchpadEvent;
+use crate::handlers::EventHandler;
+
+pub const SIGNATURE_HEADER: &str = "X-Webhook-Signature";
+pub const DELIVERY_HEADER: &str = "X-Webhook-Delivery";
+pub const EVENT_HEADER: &str = "X-Webhook-Event";
+
+#[derive(Debug, Clone)]
+pub struct WebhookConfig {
+    pub max_attempts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub request_timeout: Duration,
+    // Requests in flight at once, across all subscribers
+    pub concurrency: usize,
+    // Deliveries waiting for a sender; the rest wait in the database
+    pub queue_size: usize,
+    // How often deliveries due for an attempt are looked up
+    pub retry_poll_interval: Duration,
+    // How long the handler reuses the subscriptions it last read
+    pub subscription_ttl: Duration,
+    // Allow targets on private networks, e.g. subscribers in the same cluster
+    pub allow_private_targets: bool,
+}
+
+impl WebhookConfig {
+    pub fn from_env() -> Self {
+        Self {
+            max_attempts: env_or("WEBHOOK_MAX_ATTEMPTS", 8),
+            initial_backoff: Duration::from_millis(env_or("WEBHOOK_INITIAL_BACKOFF_MS", 1000)),
+            max_backoff: Duration::from_millis(env_or("WEBHOOK_MAX_BACKOFF_MS", 300_000)),
+            request_timeout: Duration::from_millis(env_or("WEBHOOK_TIMEOUT_MS", 10_000)),
+            concurrency: env_or("WEBHOOK_CONCURRENCY", 16),
+            queue_size: env_or("WEBHOOK_QUEUE_SIZE", 1024),
+            retry_poll_interval: Duration::from_millis(env_or("WEBHOOK_RETRY_POLL_MS", 1000)),
+            subscription_ttl: Duration::from_millis(env_or("WEBHOOK_SUBSCRIPTION_TTL_MS", 10_000)),
+            allow_private_targets: env_or("WEBHOOK_ALLOW_PRIVATE_TARGETS", false),
+        }
+    }
+
+    // Wait before the attempt following `attempts` failed ones
+    fn backoff(&self, attempts: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempts.saturating_sub(1));
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+}
+
+/// A subscriber and the events it wants. Empty or missing filters match
+/// everything.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct Subscription {
+    pub id: String,
+    pub url: String,
+    pub event_types: Vec<String>,
+    pub launchpad_id: Option<String>,
+    pub wallet: Option<String>,
+}
+
+/// A new subscription with the secret its payloads are signed with, which
+/// is not shown again.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct CreatedSubscription {
+    #[serde(flatten)]
+    pub subscription: Subscription,
+    pub secret: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct NewSubscription {
+    pub url: String,
+    // Generated when not given
+    #[serde(default)]
+    pub secret: Option<String>,
+    #[serde(default)]
+    pub event_types: Vec<String>,
+    #[serde(default)]
+    pub launchpad_id: Option<String>,
+    #[serde(default)]
+    pub wallet: Option<String>,
+}
+
+impl Subscription {
+    /// Whether `event` passes the filters. The launchpad is the one resolved
+    /// for the event's transaction, so trades match their launchpad too.
+    pub fn matches(&self, event: &LaunchpadEvent, metadata: &EventMetadata) -> bool {
+        (self.event_types.is_empty() || self.event_types.iter().any(|t| t == event.name()))
+            && self
+                .launchpad_id
+                .as_deref()
+                .is_none_or(|id| metadata.launchpad_id.as_deref() == Some(id))
+            && self
+                .wallet
+                .as_deref()
+                .is_none_or(|wallet| event.wallets().iter().any(|w| w == wallet))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryStatus {
+    Pending,
+    Delivered,
+    DeadLetter,
+}
+
+/// One row of the delivery log.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Delivery {
+    pub id: String,
+    pub subscription_id: String,
+    pub event_type: String,
+    pub tx_digest: String,
+    pub event_seq: u64,
+    // Exact body sent, so every retry carries the same signature
+    pub payload: String,
+    pub status: DeliveryStatus,
+    pub attempts: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+    pub created_at: i64,
+    pub updated_at: i64,
+    // When the next attempt is due, while pending
+    #[serde(default)]
+    pub next_attempt_at: i64,
+}
+
+const SUBSCRIPTION_FIELDS: &str = "meta::id(id) AS id, url, event_types, launchpad_id, wallet";
+const DELIVERY_FIELDS: &str = "meta::id(id) AS id, subscription_id, event_type, tx_digest, \
+    event_seq, payload, status, attempts, last_error, created_at, updated_at, next_attempt_at";
+
+// Bumped whenever this process changes the subscriptions, so handlers reload
+// them at once rather than when their copy expires
+static SUBSCRIPTION_CHANGES: AtomicU64 = AtomicU64::new(0);
+
+impl NewSubscription {
+    pub async fn validate(&self, config: &WebhookConfig) -> Result<()> {
+        check_target(&self.url, config.allow_private_targets).await
+    }
+}
+
+// Refuse URLs that aren't http(s) or whose host resolves to an address on a
+// private network
+async fn check_target(url: &str, allow_private: bool) -> Result<()> {
+    let parsed = reqwest::Url::parse(url)?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        bail!("webhook URL must be http or https: {}", url);
+    }
+    if allow_private {
+        return Ok(());
+    }
+    let Some(host) = parsed.host_str() else {
+        bail!("webhook URL has no host: {}", url);
+    };
+    // IPv6 literals keep their brackets
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    let port = parsed.port_or_known_default().unwrap_or(80);
+    let addresses: Vec<IpAddr> = match host.parse() {
+        Ok(ip) => vec![ip],
+        Err(_) => tokio::net::lookup_host((host, port))
+            .await?
+            .map(|address| address.ip())
+            .collect(),
+    };
+    if addresses.is_empty() {
+        bail!("webhook host does not resolve: {}", url);
+    }
+    if let Some(ip) = addresses.into_iter().find(|ip| is_internal(*ip)) {
+        bail!(
+            "webhook URL resolves to a non-public address {}: {}",
+            ip,
+            url
+        );
+    }
+    Ok(())
+}
+
+fn is_internal(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_internal_v4(ip),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(mapped) => is_internal_v4(mapped),
+            None => {
+                let first = ip.segments()[0];
+                ip.is_loopback()
+                    || ip.is_unspecified()
+                    // Unique local, fc00::/7
+                    || first & 0xfe00 == 0xfc00
+                    // Link-local, fe80::/10
+                    || first & 0xffc0 == 0xfe80
+            }
+        },
+    }
+}
+
+fn is_internal_v4(ip: Ipv4Addr) -> bool {
+    let [first, second, ..] = ip.octets();
+    ip.is_private()
+        || ip.is_loopback()
+        || ip.is_link_local()
+        || ip.is_unspecified()
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        // Carrier-grade NAT, 100.64.0.0/10
+        || (first == 100 && second & 0xc0 == 64)
+}
+
+// Resolver of the delivery client. Refusing non-public addresses here means
+// the address a request connects to is the one that was checked, so a host
+// can't rebind to an internal address after passing the check.
+struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let addresses: Vec<SocketAddr> =
+                tokio::net::lookup_host((name.as_str(), 0)).await?.collect();
+            if let Some(address) = addresses.iter().find(|a| is_internal(a.ip())) {
+                return Err(format!(
+                    "{} resolves to a non-public address {}",
+                    name.as_str(),
+                    address.ip()
+                )
+                .into());
+            }
+            Ok(Box::new(addresses.into_iter()) as Addrs)
+        })
+    }
+}
+
+pub async fn subscribe(
+    db: &Surreal<Any>,
+    new: NewSubscription,
+    config: &WebhookConfig,
+) -> Result<CreatedSubscription> {
+    new.validate(config).await?;
+    let mut response = db
+        .query(
+            "LET $created = CREATE ONLY webhook_subscriptions SET url = $url, \
+             secret = $secret ?? rand::string(32), event_types = $event_types, \
+             launchpad_id = $launchpad_id, wallet = $wallet, created_at = time::now()",
+        )
+        .query(format!("SELECT {} FROM $created.id", SUBSCRIPTION_FIELDS))
+        .query("RETURN $created.secret")
+        .bind(("url", new.url))
+        .bind(("secret", new.secret))
+        .bind(("event_types", new.event_types))
+        .bind(("launchpad_id", new.launchpad_id))
+        .bind(("wallet", new.wallet))
+        .await?;
+    let created: Option<Subscription> = response.take(1)?;
+    let secret: Option<String> = response.take(2)?;
+    match (created, secret) {
+        (Some(subscription), Some(secret)) => {
+            SUBSCRIPTION_CHANGES.fetch_add(1, Ordering::Release);
+            info!(id = %subscription.id, url = %subscription.url, "Added webhook subscription");
+            Ok(CreatedSubscription {
+                subscription,
+                secret,
+            })
+        }
+        _ => bail!("webhook subscription was not created"),
+    }
+}
+
+pub async fn subscriptions(db: &Surreal<Any>) -> Result<Vec<Subscription>> {
+    let subscriptions = db
+        .query(format!(
+            "SELECT {} FROM webhook_subscriptions ORDER BY created_at",
+            SUBSCRIPTION_FIELDS
+        ))
+        .await?
+        .take(0)?;
+    Ok(subscriptions)
+}
+
+// Returns whether the subscription existed
+pub async fn unsubscribe(db: &Surreal<Any>, id: &str) -> Result<bool> {
+    let deleted: Vec<String> = db
+        .query("DELETE type::thing('webhook_subscriptions', $id) RETURN VALUE meta::id($before.id)")
+        .bind(("id", id.to_string()))
+        .await?
+        .take(0)?;
+    if !deleted.is_empty() {
+        SUBSCRIPTION_CHANGES.fetch_add(1, Ordering::Release);
+    }
+    Ok(!deleted.is_empty())
+}
+
+pub async fn deliveries(db: &Surreal<Any>, subscription_id: &str) -> Result<Vec<Delivery>> {
+    let deliveries = db
+        .query(format!(
+            "SELECT {} FROM webhook_deliveries WHERE subscription_id = $subscription_id \
+             ORDER BY created_at",
+            DELIVERY_FIELDS
+        ))
+        .bind(("subscription_id", subscription_id.to_string()))
+        .await?
+        .take(0)?;
+    Ok(deliveries)
+}
+
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC takes any key");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+struct Shared {
+    db: Surreal<Any>,
+    client: reqwest::Client,
+    config: WebhookConfig,
+    queue: mpsc::Sender<Delivery>,
+    // Ids of the deliveries in the queue or being sent
+    queued: Mutex<HashSet<String>>,
+}
+
+// Subscriptions as last read from the database
+struct Cached {
+    loaded: Instant,
+    changes: u64,
+    subscriptions: Arc<Vec<Subscription>>,
+}
+
+/// Event handler queueing a delivery per matching subscription.
+pub struct Webhooks {
+    shared: Arc<Shared>,
+    cache: Mutex<Option<Cached>>,
+}
+
+impl Webhooks {
+    /// Start delivering, resuming whatever was still pending in `db`.
+    pub async fn start(db: Surreal<Any>, config: WebhookConfig) -> Result<Self> {
+        // A redirect could point anywhere, so it counts as a failure
+        let mut client = reqwest::Client::builder()
+            .timeout(config.request_timeout)
+            .redirect(reqwest::redirect::Policy::none());
+        if !config.allow_private_targets {
+            client = client.dns_resolver(Arc::new(PublicResolver));
+        }
+        let (queue, deliveries) = mpsc::channel(config.queue_size.max(1));
+        let shared = Arc::new(Shared {
+            db,
+            client: client.build()?,
+            queue,
+            queued: Mutex::default(),
+            config,
+        });
+
+        let deliveries = Arc::new(tokio::sync::Mutex::new(deliveries));
+        for _ in 0..shared.config.concurrency.max(1) {
+            tokio::spawn(send_deliveries(shared.clone(), deliveries.clone()));
+        }
+        // The first pass picks up what the last run left pending
+        tokio::spawn(queue_due_deliveries(shared.clone()));
+
+        Ok(Self {
+            shared,
+            cache: Mutex::default(),
+        })
+    }
+
+    // Subscriptions to match events against, read again once this process
+    // changed them or the cached copy expired
+    async fn subscriptions(&self) -> Result<Arc<Vec<Subscription>>> {
+        let changes = SUBSCRIPTION_CHANGES.load(Ordering::Acquire);
+        if let Some(cached) = self.cache.lock().unwrap().as_ref() {
+            if cached.changes == changes
+                && cached.loaded.elapsed() < self.shared.config.subscription_ttl
+            {
+                return Ok(cached.subscriptions.clone());
+            }
+        }
+        let subscriptions = Arc::new(subscriptions(&self.shared.db).await?);
+        *self.cache.lock().unwrap() = Some(Cached {
+            loaded: Instant::now(),
+            changes,
+            subscriptions: subscriptions.clone(),
+        });
+        Ok(subscriptions)
+    }
+}
+
+#[async_trait]
+impl EventHandler for Webhooks {
+    fn name(&self) -> &str {
+        "webhooks"
+    }
+
+    async fn handle(&self, event: &LaunchpadEvent, metadata: &EventMetadata) -> Result<()> {
+        for subscription in self.subscriptions().await?.iter() {
+            if !subscription.matches(event, metadata) {
+                continue;
+            }
+            // One delivery per subscription and event, so replaying events
+            // doesn't notify subscribers twice
+            let id = format!(
+                "{}_{}_{}",
+                subscription.id, metadata.tx_digest, metadata.event_seq
+            );
+            let payload = json!({
+                "delivery_id": id,
+                "event_type": event.name(),
+                "launchpad_id": metadata.launchpad_id,
+                "metadata": metadata,
+                "event": event.fields()?,
+            })
+            .to_string();
+            let now = Utc::now().timestamp_millis();
+            let delivery = Delivery {
+                id,
+                subscription_id: subscription.id.clone(),
+                event_type: event.name().to_string(),
+                tx_digest: metadata.tx_digest.clone(),
+                event_seq: metadata.event_seq,
+                payload,
+                status: DeliveryStatus::Pending,
+                attempts: 0,
+                last_error: None,
+                created_at: now,
+                updated_at: now,
+                next_attempt_at: now,
+            };
+
+            let existing: Vec<String> = self
+                .shared
+                .db
+                .query("SELECT VALUE meta::id(id) FROM type::thing('webhook_deliveries', $id)")
+                .bind(("id", delivery.id.clone()))
+                .await?
+                .take(0)?;
+            if !existing.is_empty() {
+                debug!(delivery = %delivery.id, "Webhook delivery already queued");
+                continue;
+            }
+            self.shared.save(&delivery).await?;
+            self.shared.enqueue(delivery);
+        }
+        Ok(())
+    }
+}
+
+// Where a delivery goes and how it is signed
+#[derive(Deserialize)]
+struct Target {
+    url: String,
+    secret: String,
+}
+
+impl Shared {
+    async fn save(&self, delivery: &Delivery) -> Result<()> {
+        let mut row = serde_json::to_value(delivery)?;
+        if let Value::Object(row) = &mut row {
+            row.remove("id");
+        }
+        self.db
+            .query("UPSERT type::thing('webhook_deliveries', $id) CONTENT $row")
+            .bind(("id", delivery.id.clone()))
+            .bind(("row", row))
+            .await?
+            .check()?;
+        Ok(())
+    }
+
+    // Hand `delivery` to a sender unless it is queued already. A delivery
+    // that doesn't fit stays pending in the database for the next poll.
+    fn enqueue(&self, delivery: Delivery) {
+        let mut queued = self.queued.lock().unwrap();
+        if queued.contains(&delivery.id) {
+            return;
+        }
+        let id = delivery.id.clone();
+        match self.queue.try_send(delivery) {
+            Ok(()) => {
+                queued.insert(id);
+            }
+            Err(_) => debug!(delivery = %id, "Webhook queue full, delivery left for the next poll"),
+        }
+    }
+
+    // Queue the pending deliveries whose attempt is due, as many as fit
+    async fn queue_due(&self) -> Result<()> {
+        let free = self.queue.capacity();
+        if free == 0 {
+            return Ok(());
+        }
+        // Queued deliveries are still pending, so leave room to skip them
+        let limit = free + self.queued.lock().unwrap().len();
+        let due: Vec<Delivery> = self
+            .db
+            .query(format!(
+                "SELECT {} FROM webhook_deliveries WHERE status = 'pending' \
+                 AND next_attempt_at <= $now ORDER BY next_attempt_at LIMIT $limit",
+                DELIVERY_FIELDS
+            ))
+            .bind(("now", Utc::now().timestamp_millis()))
+            .bind(("limit", limit))
+            .await?
+            .take(0)?;
+        for delivery in due {
+            self.enqueue(delivery);
+        }
+        Ok(())
+    }
+
+    // URL and secret of the subscription, if it still exists
+    async fn target(&self, subscription_id: &str) -> Result<Option<Target>> {
+        let targets: Vec<Target> = self
+            .db
+            .query("SELECT url, secret FROM type::thing('webhook_subscriptions', $id)")
+            .bind(("id", subscription_id.to_string()))
+            .await?
+            .take(0)?;
+        Ok(targets.into_iter().next())
+    }
+
+    async fn post(&self, target: &Target, delivery: &Delivery) -> Result<()> {
+        let response = self
+            .client
+            .post(&target.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(
+                SIGNATURE_HEADER,
+                sign(&target.secret, delivery.payload.as_bytes()),
+            )
+            .header(DELIVERY_HEADER, &delivery.id)
+            .header(EVENT_HEADER, &delivery.event_type)
+            .body(delivery.payload.clone())
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            bail!("subscriber returned {}", response.status());
+        }
+        Ok(())
+    }
+
+    // Make one attempt at `delivery` and record the outcome
+    async fn attempt(&self, mut delivery: Delivery) {
+        // The subscription may have been removed since the delivery was
+        // queued or last tried
+        let target = match self.target(&delivery.subscription_id).await {
+            Ok(target) => target,
+            Err(e) => {
+                // Still due, so the next poll queues it again
+                error!(delivery = %delivery.id, error = %e, "Failed to look up webhook subscription");
+                return;
+            }
+        };
+        let now = Utc::now().timestamp_millis();
+        delivery.updated_at = now;
+        match target {
+            None => {
+                delivery.status = DeliveryStatus::DeadLetter;
+                delivery.last_error = Some("subscription removed".to_string());
+            }
+            Some(target) => {
+                let result = self.post(&target, &delivery).await;
+                delivery.attempts += 1;
+                match result {
+                    Ok(()) => {
+                        delivery.status = DeliveryStatus::Delivered;
+                        delivery.last_error = None;
+                        debug!(delivery = %delivery.id, attempts = delivery.attempts, "Delivered webhook");
+                    }
+                    Err(e) => {
+                        delivery.last_error = Some(e.to_string());
+                        if delivery.attempts >= self.config.max_attempts {
+                            delivery.status = DeliveryStatus::DeadLetter;
+                            warn!(
+                                delivery = %delivery.id,
+                                url = %target.url,
+                                attempts = delivery.attempts,
+                                error = %e,
+                                "Webhook delivery dead-lettered"
+                            );
+                        } else {
+                            let backoff = self.config.backoff(delivery.attempts);
+                            delivery.next_attempt_at = now + backoff.as_millis() as i64;
+                        }
+                    }
+                }
+            }
+        }
+
+        if let Err(e) = self.save(&delivery).await {
+            error!(delivery = %delivery.id, error = %e, "Failed to update webhook delivery");
+        }
+    }
+}
+
+// Sender taking deliveries off the queue one at a time
+async fn send_deliveries(
+    shared: Arc<Shared>,
+    deliveries: Arc<tokio::sync::Mutex<mpsc::Receiver<Delivery>>>,
+) {
+    loop {
+        let Some(delivery) = deliveries.lock().await.recv().await else {
+            return;
+        };
+        let id = delivery.id.clone();
+        shared.attempt(delivery).await;
+        // Only once saved, so the poll can't queue the old row again
+        shared.queued.lock().unwrap().remove(&id);
+    }
+}
+
+// Queue deliveries due for a retry, left over from the last run or that
+// didn't fit in the queue
+async fn queue_due_deliveries(shared: Arc<Shared>) {
+    let mut ticks = tokio::time::interval(shared.config.retry_poll_interval);
+    loop {
+        ticks.tick().await;
+        if let Err(e) = shared.queue_due().await {
+            error!(error = %e, "Failed to look up due webhook deliveries");
+        }
+    }
+}
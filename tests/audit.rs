@@ -0,0 +1,233 @@
+mod common;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use indexer_new::audit::{self, AdminAction, AdminHistory, Cursor};
+use indexer_new::auth::{self, API_KEY_HEADER};
+use indexer_new::transactions::{TransactionMeta, TransactionSource};
+use indexer_new::Indexer;
+
+// Every fixture is sent by the original admin, who hands the role over in
+// the AdminTransferred fixture at 1734030009000
+const ADMIN: &str = "0x5102ff6c5c12c899ca75c40dbbb1faa261e5d825f0185178b58e0c4c0ef8ab00";
+const NEW_ADMIN: &str = "0x2e3b4f0a6c1d9e8b7a6f5e4d3c2b1a0f9e8d7c6b5a4f3e2d1c0b9a8f7e6d5c4b";
+
+const PAGE: usize = 100;
+
+// Node on which every transaction was sent by the original admin
+struct SentByAdmin;
+
+#[async_trait]
+impl TransactionSource for SentByAdmin {
+    async fn transaction(&self, tx_digest: &str) -> Result<TransactionMeta> {
+        Ok(TransactionMeta {
+            tx_digest: tx_digest.to_string(),
+            sender: ADMIN.to_string(),
+            status: "success".to_string(),
+            error: None,
+            gas_used: 1_000_000,
+            move_call: None,
+            checkpoint: None,
+            timestamp: None,
+        })
+    }
+}
+
+async fn indexed(events: Vec<sui_sdk::rpc_types::SuiEvent>) -> Indexer {
+    let indexer = common::indexer().await.with_transaction_source(SentByAdmin);
+    for event in events {
+        indexer.handle_event(event).await.expect("handle_event");
+    }
+    indexer
+}
+
+async fn audited_indexer() -> Indexer {
+    // The old admin pauses the pool again after handing over the role
+    let mut late_pause = common::event("PoolPaused", 1);
+    late_pause.timestamp_ms = Some(1_734_030_010_000);
+    indexed(vec![
+        common::fixture("LiquidityDeployed"),
+        common::fixture("PoolPaused"),
+        common::fixture("PoolUnpaused"),
+        common::fixture("FeeUpdated"),
+        common::fixture("AdminTransferred"),
+        late_pause,
+        // Not a privileged action
+        common::fixture("TokensPurchased"),
+    ])
+    .await
+}
+
+#[tokio::test]
+async fn audit_trail_combines_privileged_actions() {
+    let indexer = audited_indexer().await;
+    let history = AdminHistory::load(indexer.db(), None)
+        .await
+        .expect("history");
+
+    let entries = audit::audit_trail(indexer.db(), &history, false, None, PAGE)
+        .await
+        .expect("audit");
+
+    let actions: Vec<AdminAction> = entries.iter().map(|e| e.action).collect();
+    assert_eq!(
+        actions,
+        vec![
+            AdminAction::PoolPaused,
+            AdminAction::AdminTransferred,
+            AdminAction::FeeUpdated,
+            AdminAction::PoolUnpaused,
+            AdminAction::PoolPaused,
+            AdminAction::LiquidityDeployed,
+        ]
+    );
+    assert!(entries.iter().all(|e| e.sender.as_deref() == Some(ADMIN)));
+    assert_eq!(entries[1].admin.as_deref(), Some(ADMIN));
+    assert_eq!(entries[1].details["new_admin"], NEW_ADMIN);
+    assert_eq!(
+        entries[5].launchpad_id.as_deref(),
+        Some(common::LAUNCHPAD_ID)
+    );
+    assert_eq!(entries[5].by_admin, Some(true));
+}
+
+#[tokio::test]
+async fn actions_by_a_former_admin_are_flagged() {
+    let indexer = audited_indexer().await;
+    let history = AdminHistory::load(indexer.db(), None)
+        .await
+        .expect("history");
+
+    let flagged = audit::audit_trail(indexer.db(), &history, true, None, PAGE)
+        .await
+        .expect("audit");
+
+    assert_eq!(flagged.len(), 1);
+    assert_eq!(flagged[0].action, AdminAction::PoolPaused);
+    assert_eq!(flagged[0].timestamp, Some(1_734_030_010_000));
+    assert_eq!(flagged[0].admin.as_deref(), Some(NEW_ADMIN));
+    assert_eq!(flagged[0].by_admin, Some(false));
+}
+
+#[tokio::test]
+async fn configured_admin_applies_without_transfers() {
+    let indexer = indexed(vec![common::fixture("FeeUpdated")]).await;
+
+    let unknown = AdminHistory::load(indexer.db(), None)
+        .await
+        .expect("history");
+    let entries = audit::audit_trail(indexer.db(), &unknown, false, None, PAGE)
+        .await
+        .expect("audit");
+    assert_eq!(entries[0].by_admin, None);
+
+    let configured = AdminHistory::load(indexer.db(), Some(NEW_ADMIN.to_string()))
+        .await
+        .expect("history");
+    let flagged = audit::audit_trail(indexer.db(), &configured, true, None, PAGE)
+        .await
+        .expect("audit");
+    assert_eq!(flagged.len(), 1);
+    assert_eq!(flagged[0].action, AdminAction::FeeUpdated);
+}
+
+#[tokio::test]
+async fn audit_trail_is_paged_newest_first() {
+    let indexer = audited_indexer().await;
+    let history = AdminHistory::load(indexer.db(), None)
+        .await
+        .expect("history");
+
+    let mut actions = Vec::new();
+    let mut before: Option<Cursor> = None;
+    loop {
+        let page = audit::audit_trail(indexer.db(), &history, false, before.as_ref(), 4)
+            .await
+            .expect("audit");
+        actions.extend(page.iter().map(|e| e.action));
+        match page.last() {
+            Some(last) if page.len() == 4 => before = Some(last.cursor.parse().expect("cursor")),
+            _ => break,
+        }
+    }
+
+    let all = audit::audit_trail(indexer.db(), &history, false, None, PAGE)
+        .await
+        .expect("audit");
+    assert_eq!(actions, all.iter().map(|e| e.action).collect::<Vec<_>>());
+    assert_eq!(actions.len(), 6);
+}
+
+#[tokio::test]
+async fn actions_of_unknown_transactions_have_no_sender() {
+    // Indexed without looking transactions up
+    let indexer = common::indexed(vec![common::fixture("PoolPaused")]).await;
+    let history = AdminHistory::load(indexer.db(), Some(NEW_ADMIN.to_string()))
+        .await
+        .expect("history");
+
+    let entries = audit::audit_trail(indexer.db(), &history, false, None, PAGE)
+        .await
+        .expect("audit");
+    assert_eq!(entries[0].sender, None);
+    assert_eq!(entries[0].by_admin, None);
+
+    let flagged = audit::audit_trail(indexer.db(), &history, true, None, PAGE)
+        .await
+        .expect("audit");
+    assert!(flagged.is_empty());
+}
+
+#[tokio::test]
+async fn audit_trail_is_served_over_http() {
+    let indexer = audited_indexer().await;
+    let (url, _shutdown) = common::serve(&indexer).await;
+    let (_, key) = auth::create_key(indexer.db(), "ops", None, true)
+        .await
+        .expect("key");
+
+    let flagged: serde_json::Value = reqwest::Client::new()
+        .get(format!("{}/admin/audit?flagged=true", url))
+        .header(API_KEY_HEADER, &key)
+        .send()
+        .await
+        .expect("request")
+        .json()
+        .await
+        .expect("json");
+    assert_eq!(flagged.as_array().map(Vec::len), Some(1));
+    assert_eq!(flagged[0]["action"], "pool_paused");
+    assert_eq!(flagged[0]["sender"], ADMIN);
+
+    let client = reqwest::Client::new();
+    let first: serde_json::Value = client
+        .get(format!("{}/admin/audit?limit=2", url))
+        .header(API_KEY_HEADER, &key)
+        .send()
+        .await
+        .expect("request")
+        .json()
+        .await
+        .expect("json");
+    assert_eq!(first.as_array().map(Vec::len), Some(2));
+    let next: serde_json::Value = client
+        .get(format!("{}/admin/audit", url))
+        .query(&[("before", first[1]["cursor"].as_str().unwrap())])
+        .header(API_KEY_HEADER, &key)
+        .send()
+        .await
+        .expect("request")
+        .json()
+        .await
+        .expect("json");
+    assert_eq!(next.as_array().map(Vec::len), Some(4));
+    assert_eq!(next[0]["action"], "fee_updated");
+
+    let malformed = client
+        .get(format!("{}/admin/audit?before=yesterday", url))
+        .header(API_KEY_HEADER, &key)
+        .send()
+        .await
+        .expect("request");
+    assert_eq!(malformed.status(), 400);
+}
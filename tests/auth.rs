@@ -0,0 +1,195 @@
+mod common;
+
+use indexer_new::api;
+use indexer_new::auth::{self, RateLimitConfig, API_KEY_HEADER};
+use indexer_new::Indexer;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::sync::watch;
+
+async fn serve(indexer: &Indexer, anonymous_per_minute: u32) -> (String, watch::Sender<bool>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind API");
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let (shutdown, signal) = watch::channel(false);
+    let limits = RateLimitConfig {
+        anonymous_per_minute,
+        key_per_minute: 600,
+        failed_key_per_minute: 2,
+    };
+    tokio::spawn(api::serve_with_limits(
+        listener,
+        indexer.clone(),
+        signal,
+        limits,
+    ));
+    (url, shutdown)
+}
+
+async fn status(request: reqwest::RequestBuilder) -> u16 {
+    request.send().await.expect("request").status().as_u16()
+}
+
+#[tokio::test]
+async fn anonymous_clients_share_the_unauthenticated_tier() {
+    let indexer = common::indexer().await;
+    let (url, _shutdown) = serve(&indexer, 2).await;
+    let client = reqwest::Client::new();
+
+    let response = client
+        .get(format!("{}/launchpads", url))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.headers()["x-ratelimit-limit"], "2");
+    assert_eq!(response.headers()["x-ratelimit-remaining"], "1");
+    assert_eq!(status(client.get(format!("{}/graphql", url))).await, 200);
+
+    let limited = client
+        .get(format!("{}/launchpads", url))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(limited.status(), 429);
+    assert!(
+        limited.headers()["retry-after"]
+            .to_str()
+            .unwrap()
+            .parse::<u64>()
+            .unwrap()
+            > 0
+    );
+
+    // Probes are never limited
+    assert_eq!(status(client.get(format!("{}/healthz", url))).await, 200);
+}
+
+#[tokio::test]
+async fn keys_have_their_own_limit_and_count_usage() {
+    let indexer = common::indexer().await;
+    let (url, _shutdown) = serve(&indexer, 1).await;
+    let (key, secret) = auth::create_key(indexer.db(), "partner", Some(3), false)
+        .await
+        .unwrap();
+    let client = reqwest::Client::new();
+
+    assert_eq!(status(client.get(format!("{}/launchpads", url))).await, 200);
+    assert_eq!(status(client.get(format!("{}/launchpads", url))).await, 429);
+
+    let keyed = || {
+        client
+            .get(format!("{}/launchpads", url))
+            .header(API_KEY_HEADER, &secret)
+    };
+    for _ in 0..3 {
+        assert_eq!(status(keyed()).await, 200);
+    }
+    assert_eq!(status(keyed()).await, 429);
+
+    // Usage is recorded in the background
+    for _ in 0..100 {
+        let keys = auth::keys(indexer.db()).await.unwrap();
+        let stored = keys.iter().find(|k| k.id == key.id).unwrap();
+        if stored.requests == 3 && stored.rejected == 1 {
+            assert!(stored.last_used_at.is_some());
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    panic!("usage was not recorded");
+}
+
+#[tokio::test]
+async fn keys_are_accepted_as_bearer_tokens_and_query_parameters() {
+    let indexer = common::indexer().await;
+    let (url, _shutdown) = serve(&indexer, 0).await;
+    let (_, secret) = auth::create_key(indexer.db(), "browser", None, false)
+        .await
+        .unwrap();
+    let client = reqwest::Client::new();
+
+    assert_eq!(status(client.get(format!("{}/launchpads", url))).await, 401);
+    let bearer = client
+        .get(format!("{}/launchpads", url))
+        .bearer_auth(&secret);
+    assert_eq!(status(bearer).await, 200);
+    let param = client.get(format!("{}/launchpads?api_key={}", url, secret));
+    assert_eq!(status(param).await, 200);
+}
+
+#[tokio::test]
+async fn revoked_and_unknown_keys_are_rejected() {
+    let indexer = common::indexer().await;
+    let (url, _shutdown) = serve(&indexer, 60).await;
+    let (key, secret) = auth::create_key(indexer.db(), "old", None, false)
+        .await
+        .unwrap();
+    let client = reqwest::Client::new();
+    let request = |secret: &str| {
+        client
+            .get(format!("{}/launchpads", url))
+            .header(API_KEY_HEADER, secret)
+    };
+
+    assert_eq!(status(request(&secret)).await, 200);
+    assert!(auth::revoke_key(indexer.db(), &key.id).await.unwrap());
+    assert!(!auth::revoke_key(indexer.db(), &key.id).await.unwrap());
+    assert_eq!(status(request(&secret)).await, 401);
+    assert_eq!(status(request("lpk_unknown")).await, 401);
+}
+
+#[tokio::test]
+async fn guessing_keys_is_throttled_per_address() {
+    let indexer = common::indexer().await;
+    let (url, _shutdown) = serve(&indexer, 60).await;
+    let (_, secret) = auth::create_key(indexer.db(), "partner", None, false)
+        .await
+        .unwrap();
+    let client = reqwest::Client::new();
+    let request = |secret: &str| {
+        client
+            .get(format!("{}/launchpads", url))
+            .header(API_KEY_HEADER, secret)
+    };
+
+    assert_eq!(status(request("lpk_guess1")).await, 401);
+    assert_eq!(status(request("lpk_guess2")).await, 401);
+    // Not even looked up any more, however good the key
+    assert_eq!(status(request("lpk_guess3")).await, 429);
+    assert_eq!(status(request(&secret)).await, 429);
+    // Requests without a key are charged to their own tier
+    assert_eq!(status(client.get(format!("{}/launchpads", url))).await, 200);
+}
+
+#[tokio::test]
+async fn management_routes_need_an_admin_key() {
+    let indexer = common::indexer().await;
+    // Anonymous clients are allowed elsewhere, but never here
+    let (url, _shutdown) = serve(&indexer, 60).await;
+    let (_, reader) = auth::create_key(indexer.db(), "frontend", None, false)
+        .await
+        .unwrap();
+    let (admin, secret) = auth::create_key(indexer.db(), "ops", None, true)
+        .await
+        .unwrap();
+    assert!(admin.admin);
+    let client = reqwest::Client::new();
+
+    for path in ["/admin/audit", "/webhooks", "/webhooks/unknown/deliveries"] {
+        let route = format!("{}{}", url, path);
+        assert_eq!(status(client.get(&route)).await, 401, "{}", path);
+        let keyed = client.get(&route).header(API_KEY_HEADER, &reader);
+        assert_eq!(status(keyed).await, 403, "{}", path);
+        let admin = client.get(&route).header(API_KEY_HEADER, &secret);
+        assert_eq!(status(admin).await, 200, "{}", path);
+    }
+    let delete = client.delete(format!("{}/webhooks/unknown", url));
+    assert_eq!(status(delete).await, 401);
+
+    // Other routes still take anonymous clients and ordinary keys
+    assert_eq!(status(client.get(format!("{}/launchpads", url))).await, 200);
+    let keyed = client
+        .get(format!("{}/launchpads", url))
+        .header(API_KEY_HEADER, &reader);
+    assert_eq!(status(keyed).await, 200);
+}
@@ -0,0 +1,268 @@
+use indexer_new::events::{
+    AdminTransferred, BalanceUpdate, FeeUpdated, LaunchpadCreated, LaunchpadEvent,
+    LiquidityDeployed, LiquidityMoved, PoolPaused, PoolUnpaused, PriceUpdate, TokensPurchased,
+    TokensTransferred, VestingClaimed, WhitelistUpdated,
+};
+use proptest::prelude::*;
+use sui_sdk::types::base_types::{ObjectID, SuiAddress};
+
+// Hand-rolled BCS writer for the launchpad module's event structs, so the
+// bytes come from the Move layout (fields in declaration order, `address` and
+// `ID` as 32 raw bytes, `String` as a ULEB128 length plus UTF-8 bytes, `u64`
+// little endian) rather than from the same serde derive the decoders use
+#[derive(Default)]
+struct MoveBytes(Vec<u8>);
+
+impl MoveBytes {
+    fn address(mut self, value: &[u8; 32]) -> Self {
+        self.0.extend_from_slice(value);
+        self
+    }
+
+    fn string(mut self, value: &str) -> Self {
+        let mut len = value.len();
+        loop {
+            let byte = (len & 0x7f) as u8;
+            len >>= 7;
+            if len == 0 {
+                self.0.push(byte);
+                break;
+            }
+            self.0.push(byte | 0x80);
+        }
+        self.0.extend_from_slice(value.as_bytes());
+        self
+    }
+
+    fn bool(mut self, value: bool) -> Self {
+        self.0.push(value as u8);
+        self
+    }
+
+    fn u64(mut self, value: u64) -> Self {
+        self.0.extend_from_slice(&value.to_le_bytes());
+        self
+    }
+}
+
+fn decode(name: &str, bytes: MoveBytes) -> LaunchpadEvent {
+    LaunchpadEvent::decode(name, &bytes.0)
+        .expect("decodes")
+        .expect("known event")
+}
+
+fn amount() -> impl Strategy<Value = u64> {
+    prop_oneof![Just(0), Just(u64::MAX), any::<u64>()]
+}
+
+// Raw bytes of an `address` or `ID`
+fn address() -> impl Strategy<Value = [u8; 32]> {
+    prop_oneof![Just([0; 32]), Just([0xff; 32]), any::<[u8; 32]>()]
+}
+
+// Anything from empty to a few KB of multi-byte UTF-8, which also exercises
+// multi-byte ULEB128 lengths
+fn text() -> impl Strategy<Value = String> {
+    prop_oneof![Just(String::new()), "\\PC{0,64}", "\\PC{128,2048}"]
+}
+
+#[test]
+fn unregistered_names_are_not_decoded() {
+    let bytes = MoveBytes::default().address(&[1; 32]).u64(1).u64(2);
+    assert_eq!(
+        LaunchpadEvent::decode("TokensBurned", &bytes.0).unwrap(),
+        None
+    );
+}
+
+proptest! {
+    #[test]
+    fn tokens_purchased(buyer in address(), amount in amount(), timestamp in amount()) {
+        let bytes = MoveBytes::default().address(&buyer).u64(amount).u64(timestamp);
+        let buyer = SuiAddress::new(buyer);
+        prop_assert_eq!(
+            decode("TokensPurchased", bytes),
+            LaunchpadEvent::TokensPurchased(TokensPurchased { buyer, amount, timestamp })
+        );
+    }
+
+    #[test]
+    fn tokens_transferred(from in address(), to in address(), amount in amount(), timestamp in amount()) {
+        let bytes = MoveBytes::default().address(&from).address(&to).u64(amount).u64(timestamp);
+        let (from, to) = (SuiAddress::new(from), SuiAddress::new(to));
+        prop_assert_eq!(
+            decode("TokensTransferred", bytes),
+            LaunchpadEvent::TokensTransferred(TokensTransferred { from, to, amount, timestamp })
+        );
+    }
+
+    #[test]
+    fn price_update(new_price in amount(), tokens_sold in amount(), timestamp in amount()) {
+        let bytes = MoveBytes::default().u64(new_price).u64(tokens_sold).u64(timestamp);
+        prop_assert_eq!(
+            decode("PriceUpdate", bytes),
+            LaunchpadEvent::PriceUpdate(PriceUpdate { new_price, tokens_sold, timestamp })
+        );
+    }
+
+    #[test]
+    fn liquidity_deployed(launchpad_id in address(), sui_amount in amount(), timestamp in amount()) {
+        let bytes = MoveBytes::default().address(&launchpad_id).u64(sui_amount).u64(timestamp);
+        let launchpad_id = ObjectID::new(launchpad_id);
+        prop_assert_eq!(
+            decode("LiquidityDeployed", bytes),
+            LaunchpadEvent::LiquidityDeployed(LiquidityDeployed { launchpad_id, sui_amount, timestamp })
+        );
+    }
+
+    #[test]
+    fn pool_paused(launchpad_id in address(), timestamp in amount()) {
+        let bytes = MoveBytes::default().address(&launchpad_id).u64(timestamp);
+        let launchpad_id = ObjectID::new(launchpad_id);
+        prop_assert_eq!(
+            decode("PoolPaused", bytes),
+            LaunchpadEvent::PoolPaused(PoolPaused { launchpad_id, timestamp })
+        );
+    }
+
+    #[test]
+    fn pool_unpaused(launchpad_id in address(), timestamp in amount()) {
+        let bytes = MoveBytes::default().address(&launchpad_id).u64(timestamp);
+        let launchpad_id = ObjectID::new(launchpad_id);
+        prop_assert_eq!(
+            decode("PoolUnpaused", bytes),
+            LaunchpadEvent::PoolUnpaused(PoolUnpaused { launchpad_id, timestamp })
+        );
+    }
+
+    #[test]
+    fn launchpad_created(
+        launchpad_id in address(),
+        creator in address(),
+        name in text(),
+        description in text(),
+        token_supply in amount(),
+        initial_price in amount(),
+        price_increment in amount(),
+        website_url in text(),
+        timestamp in amount(),
+    ) {
+        let bytes = MoveBytes::default()
+            .address(&launchpad_id)
+            .address(&creator)
+            .string(&name)
+            .string(&description)
+            .u64(token_supply)
+            .u64(initial_price)
+            .u64(price_increment)
+            .string(&website_url)
+            .u64(timestamp);
+        let (launchpad_id, creator) = (ObjectID::new(launchpad_id), SuiAddress::new(creator));
+        prop_assert_eq!(
+            decode("LaunchpadCreated", bytes),
+            LaunchpadEvent::LaunchpadCreated(LaunchpadCreated {
+                launchpad_id,
+                creator,
+                name,
+                description,
+                token_supply,
+                initial_price,
+                price_increment,
+                website_url,
+                timestamp,
+            })
+        );
+    }
+
+    #[test]
+    fn vesting_claimed(user in address(), amount in amount(), timestamp in amount()) {
+        let bytes = MoveBytes::default().address(&user).u64(amount).u64(timestamp);
+        let user = SuiAddress::new(user);
+        prop_assert_eq!(
+            decode("VestingClaimed", bytes),
+            LaunchpadEvent::VestingClaimed(VestingClaimed { user, amount, timestamp })
+        );
+    }
+
+    #[test]
+    fn fee_updated(previous_fee in amount(), new_fee in amount()) {
+        let bytes = MoveBytes::default().u64(previous_fee).u64(new_fee);
+        prop_assert_eq!(
+            decode("FeeUpdated", bytes),
+            LaunchpadEvent::FeeUpdated(FeeUpdated { previous_fee, new_fee })
+        );
+    }
+
+    #[test]
+    fn admin_transferred(previous_admin in address(), new_admin in address()) {
+        let bytes = MoveBytes::default().address(&previous_admin).address(&new_admin);
+        let (previous_admin, new_admin) = (SuiAddress::new(previous_admin), SuiAddress::new(new_admin));
+        prop_assert_eq!(
+            decode("AdminTransferred", bytes),
+            LaunchpadEvent::AdminTransferred(AdminTransferred { previous_admin, new_admin })
+        );
+    }
+
+    #[test]
+    fn balance_update(launchpad_id in address(), holder in address(), balance in amount(), timestamp in amount()) {
+        let bytes = MoveBytes::default().address(&launchpad_id).address(&holder).u64(balance).u64(timestamp);
+        let (launchpad_id, holder) = (ObjectID::new(launchpad_id), SuiAddress::new(holder));
+        prop_assert_eq!(
+            decode("BalanceUpdate", bytes),
+            LaunchpadEvent::BalanceUpdate(BalanceUpdate { launchpad_id, holder, balance, timestamp })
+        );
+    }
+
+    #[test]
+    fn whitelist_updated(
+        launchpad_id in address(),
+        address in address(),
+        whitelisted in any::<bool>(),
+        timestamp in amount(),
+    ) {
+        let bytes = MoveBytes::default()
+            .address(&launchpad_id)
+            .address(&address)
+            .bool(whitelisted)
+            .u64(timestamp);
+        let (launchpad_id, address) = (ObjectID::new(launchpad_id), SuiAddress::new(address));
+        prop_assert_eq!(
+            decode("WhitelistUpdated", bytes),
+            LaunchpadEvent::WhitelistUpdated(WhitelistUpdated { launchpad_id, address, whitelisted, timestamp })
+        );
+    }
+
+    #[test]
+    fn liquidity_moved(
+        launchpad_id in address(),
+        recipient in address(),
+        sui_amount in amount(),
+        timestamp in amount(),
+    ) {
+        let bytes = MoveBytes::default()
+            .address(&launchpad_id)
+            .address(&recipient)
+            .u64(sui_amount)
+            .u64(timestamp);
+        let (launchpad_id, recipient) = (ObjectID::new(launchpad_id), SuiAddress::new(recipient));
+        prop_assert_eq!(
+            decode("LiquidityMoved", bytes),
+            LaunchpadEvent::LiquidityMoved(LiquidityMoved { launchpad_id, recipient, sui_amount, timestamp })
+        );
+    }
+
+    // Trailing or missing bytes must be rejected rather than silently decoded
+    #[test]
+    fn truncated_input_is_rejected(amount in amount(), timestamp in amount(), cut in 1usize..16) {
+        let MoveBytes(mut bytes) = MoveBytes::default().address(&[1; 32]).u64(amount).u64(timestamp);
+        bytes.truncate(bytes.len() - cut);
+        prop_assert!(LaunchpadEvent::decode("TokensPurchased", &bytes).is_err());
+    }
+
+    #[test]
+    fn trailing_bytes_are_rejected(amount in amount(), timestamp in amount(), extra in 1u8..=255) {
+        let MoveBytes(mut bytes) = MoveBytes::default().address(&[1; 32]).u64(amount).u64(timestamp);
+        bytes.push(extra);
+        prop_assert!(LaunchpadEvent::decode("TokensPurchased", &bytes).is_err());
+    }
+}
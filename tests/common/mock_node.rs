@@ -0,0 +1,227 @@
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        State,
+    },
+    response::Response,
+    routing::get,
+    Json, Router,
+};
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use sui_sdk::rpc_types::{EventPage, SuiEvent};
+use sui_sdk::types::event::EventID;
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+
+// In-process stand-in for a Sui full node. Serves JSON-RPC over HTTP and
+// WebSocket on the same address and answers from a script set up by the
+// test, so ingestion can be driven deterministically.
+
+const SUBSCRIPTION_ID: u64 = 1;
+
+// Reply to one `suix_queryEvents` call
+pub enum QueryReply {
+    Page {
+        events: Vec<SuiEvent>,
+        has_next_page: bool,
+    },
+    Error(&'static str),
+}
+
+// Step of the `suix_subscribeEvent` stream
+pub enum StreamStep {
+    Event(SuiEvent),
+    // Drop the connection without a close handshake
+    Disconnect,
+}
+
+#[derive(Default)]
+struct Script {
+    query_replies: VecDeque<QueryReply>,
+    queried_cursors: Vec<Option<EventID>>,
+    // `None` rejects subscriptions
+    subscription: Option<Vec<StreamStep>>,
+    latest_checkpoint: u64,
+}
+
+pub struct MockNode {
+    addr: SocketAddr,
+    script: Arc<Mutex<Script>>,
+    server: JoinHandle<()>,
+}
+
+impl MockNode {
+    pub async fn start() -> Self {
+        let script = Arc::new(Mutex::new(Script::default()));
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("bind mock node");
+        let addr = listener.local_addr().expect("mock node address");
+        let router = Router::new()
+            .route("/", get(websocket).post(http))
+            .with_state(script.clone());
+        let server = tokio::spawn(async move {
+            axum::serve(listener, router).await.expect("mock node");
+        });
+        Self {
+            addr,
+            script,
+            server,
+        }
+    }
+
+    pub fn http_url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    pub fn ws_url(&self) -> String {
+        format!("ws://{}", self.addr)
+    }
+
+    // Queue the reply for the next `suix_queryEvents` call. Once the queue
+    // is empty the node answers with an empty, final page.
+    pub fn reply_to_query(&self, reply: QueryReply) {
+        self.script.lock().unwrap().query_replies.push_back(reply);
+    }
+
+    // Accept the next subscription and play `steps` on it
+    pub fn accept_subscription(&self, steps: Vec<StreamStep>) {
+        self.script.lock().unwrap().subscription = Some(steps);
+    }
+
+    // Answer `sui_getLatestCheckpointSequenceNumber` with `checkpoint`
+    pub fn set_latest_checkpoint(&self, checkpoint: u64) {
+        self.script.lock().unwrap().latest_checkpoint = checkpoint;
+    }
+
+    // Cursor passed to each `suix_queryEvents` call so far
+    pub fn queried_cursors(&self) -> Vec<Option<EventID>> {
+        self.script.lock().unwrap().queried_cursors.clone()
+    }
+}
+
+impl Drop for MockNode {
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
+
+async fn http(State(script): State<Arc<Mutex<Script>>>, Json(request): Json<Value>) -> Json<Value> {
+    Json(respond(&script, &request))
+}
+
+async fn websocket(
+    State(script): State<Arc<Mutex<Script>>>,
+    upgrade: WebSocketUpgrade,
+) -> Response {
+    upgrade.on_upgrade(move |socket| websocket_session(socket, script))
+}
+
+async fn websocket_session(mut socket: WebSocket, script: Arc<Mutex<Script>>) {
+    while let Some(Ok(message)) = socket.recv().await {
+        let Message::Text(text) = message else {
+            continue;
+        };
+        let Ok(request) = serde_json::from_str::<Value>(&text) else {
+            continue;
+        };
+
+        if request["method"] != "suix_subscribeEvent" {
+            let reply = respond(&script, &request);
+            if send(&mut socket, reply).await.is_err() {
+                return;
+            }
+            continue;
+        }
+
+        let steps = script.lock().unwrap().subscription.take();
+        let Some(steps) = steps else {
+            let _ = send(&mut socket, error(&request["id"], "subscriptions rejected")).await;
+            continue;
+        };
+        let accepted = json!({ "jsonrpc": "2.0", "id": request["id"], "result": SUBSCRIPTION_ID });
+        if send(&mut socket, accepted).await.is_err() {
+            return;
+        }
+        for step in steps {
+            match step {
+                StreamStep::Event(event) => {
+                    let notification = json!({
+                        "jsonrpc": "2.0",
+                        "method": "suix_subscribeEvent",
+                        "params": { "subscription": SUBSCRIPTION_ID, "result": event },
+                    });
+                    if send(&mut socket, notification).await.is_err() {
+                        return;
+                    }
+                }
+                StreamStep::Disconnect => return,
+            }
+        }
+    }
+}
+
+async fn send(socket: &mut WebSocket, message: Value) -> Result<(), axum::Error> {
+    socket.send(Message::Text(message.to_string())).await
+}
+
+fn respond(script: &Mutex<Script>, request: &Value) -> Value {
+    let id = &request["id"];
+    match request["method"].as_str() {
+        Some("rpc.discover") => result(id, discover()),
+        Some("suix_queryEvents") => {
+            let cursor: Option<EventID> =
+                serde_json::from_value(request["params"][1].clone()).expect("cursor param");
+            let mut script = script.lock().unwrap();
+            script.queried_cursors.push(cursor);
+            match script.query_replies.pop_front() {
+                Some(QueryReply::Error(message)) => error(id, message),
+                Some(QueryReply::Page {
+                    events,
+                    has_next_page,
+                }) => result(id, page(events, cursor, has_next_page)),
+                None => result(id, page(Vec::new(), cursor, false)),
+            }
+        }
+        Some("sui_getLatestCheckpointSequenceNumber") => {
+            // BigInt values travel as decimal strings
+            let latest = script.lock().unwrap().latest_checkpoint;
+            result(id, json!(latest.to_string()))
+        }
+        Some(method) => error(id, &format!("{} is not mocked", method)),
+        None => error(id, "missing method"),
+    }
+}
+
+fn discover() -> Value {
+    json!({
+        "openrpc": "1.2.6",
+        "info": { "title": "Mock Sui node", "version": "1.40.0" },
+        "methods": [
+            { "name": "suix_queryEvents" },
+            { "name": "suix_subscribeEvent" },
+            { "name": "sui_getLatestCheckpointSequenceNumber" },
+        ],
+    })
+}
+
+fn page(events: Vec<SuiEvent>, cursor: Option<EventID>, has_next_page: bool) -> Value {
+    let next_cursor = events.last().map(|event| event.id).or(cursor);
+    serde_json::to_value(EventPage {
+        data: events,
+        next_cursor,
+        has_next_page,
+    })
+    .expect("serialize page")
+}
+
+fn result(id: &Value, result: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "result": result })
+}
+
+fn error(id: &Value, message: &str) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": -32000, "message": message } })
+}
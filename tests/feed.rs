@@ -0,0 +1,249 @@
+mod common;
+
+use futures::{SinkExt, Stream, StreamExt};
+use indexer_new::feed::{FeedFilter, FeedItem};
+use indexer_new::Indexer;
+use serde_json::Value;
+use std::time::Duration;
+use tokio_tungstenite::tungstenite::Message;
+
+async fn next(items: &mut (impl Stream<Item = FeedItem> + Unpin)) -> FeedItem {
+    tokio::time::timeout(Duration::from_secs(5), items.next())
+        .await
+        .expect("feed item in time")
+        .expect("feed still open")
+}
+
+fn event_type(item: &FeedItem) -> &str {
+    match item {
+        FeedItem::Event(event) => event.event_type,
+        FeedItem::Gap { .. } => "gap",
+    }
+}
+
+#[tokio::test]
+async fn subscribers_only_see_their_launchpad() {
+    let indexer = common::indexer().await;
+    let filter = FeedFilter {
+        launchpad_id: Some(common::LAUNCHPAD_ID.to_string()),
+        wallet: None,
+    };
+    let mut items = Box::pin(indexer.feed().subscribe(filter, None));
+
+    for name in ["LaunchpadCreated", "PriceUpdate", "PoolPaused"] {
+        indexer
+            .handle_event(common::fixture(name))
+            .await
+            .expect("handle_event");
+    }
+
+    assert_eq!(event_type(&next(&mut items).await), "LaunchpadCreated");
+    assert_eq!(event_type(&next(&mut items).await), "PoolPaused");
+}
+
+#[tokio::test]
+async fn trades_reach_subscribers_of_their_launchpad() {
+    let indexer = common::indexer().await;
+    let filter = FeedFilter {
+        launchpad_id: Some(common::LAUNCHPAD_ID.to_string()),
+        wallet: None,
+    };
+    let mut items = Box::pin(indexer.feed().subscribe(filter, None));
+
+    // Only the BalanceUpdate of the purchase's transaction names the launchpad
+    for event in [
+        common::event("TokensPurchased", 7),
+        common::fixture("BalanceUpdate"),
+        common::traded("TokensPurchased", 1, 1_000),
+    ] {
+        indexer.handle_event(event).await.expect("handle_event");
+    }
+
+    assert_eq!(event_type(&next(&mut items).await), "BalanceUpdate");
+    match next(&mut items).await {
+        FeedItem::Event(event) => {
+            assert_eq!(event.event_type, "TokensPurchased");
+            assert_eq!(event.metadata.event_seq, 1);
+            assert_eq!(event.launchpad_id.as_deref(), Some(common::LAUNCHPAD_ID));
+        }
+        other => panic!("expected the purchase, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn subscribers_can_follow_a_wallet() {
+    let indexer = common::indexer().await;
+    let filter = FeedFilter {
+        launchpad_id: None,
+        wallet: Some(common::CREATOR.to_string()),
+    };
+    let mut items = Box::pin(indexer.feed().subscribe(filter, None));
+
+    for name in ["TokensPurchased", "TokensTransferred", "LaunchpadCreated"] {
+        indexer
+            .handle_event(common::fixture(name))
+            .await
+            .expect("handle_event");
+    }
+
+    // The creator received the transfer and created the launchpad
+    assert_eq!(event_type(&next(&mut items).await), "TokensTransferred");
+    assert_eq!(event_type(&next(&mut items).await), "LaunchpadCreated");
+}
+
+#[tokio::test]
+async fn reconnecting_clients_resume_after_their_last_event() {
+    let indexer = common::indexer().await;
+    let mut live = Box::pin(indexer.feed().subscribe(FeedFilter::default(), None));
+    for seq in 0..3 {
+        indexer
+            .handle_event(common::event("TokensPurchased", seq))
+            .await
+            .expect("handle_event");
+    }
+    let first = match next(&mut live).await {
+        FeedItem::Event(event) => event,
+        gap => panic!("unexpected {:?}", gap),
+    };
+
+    let mut resumed = Box::pin(
+        indexer
+            .feed()
+            .subscribe(FeedFilter::default(), Some(first.id)),
+    );
+    for seq in [1, 2] {
+        match next(&mut resumed).await {
+            FeedItem::Event(event) => assert_eq!(event.metadata.event_seq, seq),
+            gap => panic!("unexpected {:?}", gap),
+        }
+    }
+}
+
+// Index `count` purchases, returning the feed id of the first
+async fn publish_purchases(indexer: &Indexer, count: u64) -> u64 {
+    let mut live = Box::pin(indexer.feed().subscribe(FeedFilter::default(), None));
+    for seq in 0..count {
+        indexer
+            .handle_event(common::event("TokensPurchased", seq))
+            .await
+            .expect("handle_event");
+    }
+    match next(&mut live).await {
+        FeedItem::Event(event) => event.id,
+        gap => panic!("unexpected {:?}", gap),
+    }
+}
+
+#[tokio::test]
+async fn clients_resume_from_stored_events_after_a_restart() {
+    let indexer = common::indexer().await;
+    let first = publish_purchases(&indexer, 3).await;
+
+    let restarted = Indexer::with_db(common::PACKAGE_ID, indexer.db().clone())
+        .await
+        .expect("restart");
+    let mut resumed = Box::pin(
+        restarted
+            .feed()
+            .subscribe(FeedFilter::default(), Some(first)),
+    );
+    for seq in [1, 2] {
+        match next(&mut resumed).await {
+            FeedItem::Event(event) => assert_eq!(event.metadata.event_seq, seq),
+            gap => panic!("unexpected {:?}", gap),
+        }
+    }
+
+    // Ids carry on after the stored ones
+    restarted
+        .handle_event(common::event("TokensPurchased", 3))
+        .await
+        .expect("handle_event");
+    match next(&mut resumed).await {
+        FeedItem::Event(event) => assert_eq!(event.id, first + 3),
+        gap => panic!("unexpected {:?}", gap),
+    }
+}
+
+#[tokio::test]
+async fn resuming_past_missing_events_reports_a_gap() {
+    let indexer = common::indexer().await;
+    let first = publish_purchases(&indexer, 3).await;
+    indexer
+        .db()
+        .query("DELETE feed_events WHERE seq = $seq")
+        .bind(("seq", first + 1))
+        .await
+        .expect("delete");
+
+    let restarted = Indexer::with_db(common::PACKAGE_ID, indexer.db().clone())
+        .await
+        .expect("restart");
+    let mut items = Box::pin(
+        restarted
+            .feed()
+            .subscribe(FeedFilter::default(), Some(first)),
+    );
+    assert_eq!(next(&mut items).await, FeedItem::Gap { after: first });
+    match next(&mut items).await {
+        FeedItem::Event(event) => assert_eq!(event.metadata.event_seq, 2),
+        gap => panic!("unexpected {:?}", gap),
+    }
+}
+
+#[tokio::test]
+async fn websocket_clients_receive_stored_events() {
+    let indexer = common::indexer().await;
+    let (url, _shutdown) = common::serve(&indexer).await;
+    let url = format!(
+        "{}/feed/ws?wallet={}",
+        url.replace("http://", "ws://"),
+        common::BUYER
+    );
+    let (mut socket, _) = tokio_tungstenite::connect_async(url)
+        .await
+        .expect("connect");
+
+    indexer
+        .handle_event(common::fixture("TokensPurchased"))
+        .await
+        .expect("handle_event");
+
+    let message = tokio::time::timeout(Duration::from_secs(5), socket.next())
+        .await
+        .expect("message in time")
+        .expect("socket open")
+        .expect("message");
+    let message: Value = serde_json::from_str(message.to_text().unwrap()).unwrap();
+    assert_eq!(message["type"], "event");
+    assert_eq!(message["event"]["event_type"], "TokensPurchased");
+    assert_eq!(message["event"]["event"]["buyer"], common::BUYER);
+    socket.send(Message::Close(None)).await.unwrap();
+}
+
+#[tokio::test]
+async fn sse_clients_receive_events_with_ids() {
+    let indexer = common::indexer().await;
+    let (url, _shutdown) = common::serve(&indexer).await;
+    let mut response = reqwest::get(format!("{}/feed/sse", url))
+        .await
+        .expect("connect");
+    assert_eq!(response.headers()["content-type"], "text/event-stream");
+
+    indexer
+        .handle_event(common::fixture("PriceUpdate"))
+        .await
+        .expect("handle_event");
+
+    let mut body = String::new();
+    while !body.contains("\n\n") || !body.contains("event: event") {
+        let chunk = tokio::time::timeout(Duration::from_secs(5), response.chunk())
+            .await
+            .expect("chunk in time")
+            .expect("read")
+            .expect("stream open");
+        body.push_str(std::str::from_utf8(&chunk).unwrap());
+    }
+    assert!(body.contains("\nid: ") || body.starts_with("id: "));
+    assert!(body.contains("\"event_type\":\"PriceUpdate\""));
+}
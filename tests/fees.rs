@@ -0,0 +1,99 @@
+mod common;
+
+use indexer_new::fees::{self, FeePeriod, FeeSchedule, Period, Revenue};
+
+// The FeeUpdated fixture raises the fee from 0.1 to 0.2 SUI at 1734030008000
+const UPDATED_AT: u64 = 1_734_030_008_000;
+
+// One launchpad created at 1734030006000, before the update, and one after
+async fn fee_indexer() -> indexer_new::Indexer {
+    let mut later = common::event("LaunchpadCreated", 1);
+    later.timestamp_ms = Some(UPDATED_AT + 2_000);
+    common::indexed(vec![
+        common::fixture("LaunchpadCreated"),
+        common::fixture("FeeUpdated"),
+        later,
+    ])
+    .await
+}
+
+#[tokio::test]
+async fn schedule_starts_from_the_first_previous_fee() {
+    let indexer = fee_indexer().await;
+
+    let schedule = FeeSchedule::load(indexer.db(), 1).await.expect("schedule");
+
+    assert_eq!(
+        schedule.periods()[0],
+        FeePeriod {
+            fee: 100_000_000,
+            effective_from: None,
+            effective_until: Some(UPDATED_AT),
+            tx_digest: None,
+        }
+    );
+    assert_eq!(schedule.periods()[1].fee, 200_000_000);
+    assert_eq!(schedule.periods()[1].effective_from, Some(UPDATED_AT));
+    assert_eq!(schedule.fee_at(UPDATED_AT - 1), 100_000_000);
+    assert_eq!(schedule.fee_at(UPDATED_AT), 200_000_000);
+}
+
+#[tokio::test]
+async fn schedule_without_updates_uses_the_initial_fee() {
+    let indexer = common::indexer().await;
+
+    let schedule = FeeSchedule::load(indexer.db(), 7).await.expect("schedule");
+
+    assert_eq!(schedule.periods().len(), 1);
+    assert_eq!(schedule.fee_at(0), 7);
+}
+
+#[tokio::test]
+async fn revenue_charges_the_fee_in_effect_at_creation() {
+    let indexer = fee_indexer().await;
+    let schedule = FeeSchedule::load(indexer.db(), 1).await.expect("schedule");
+
+    let weekly = fees::revenue(indexer.db(), &schedule, Period::Week, None, None)
+        .await
+        .expect("revenue");
+    assert_eq!(
+        weekly,
+        vec![Revenue {
+            // Monday 2024-12-09
+            start: 1_733_702_400_000,
+            launchpads: 2,
+            fees: 300_000_000,
+        }]
+    );
+
+    let after = fees::revenue(indexer.db(), &schedule, Period::Day, Some(UPDATED_AT), None)
+        .await
+        .expect("revenue");
+    assert_eq!(after.len(), 1);
+    assert_eq!(after[0].launchpads, 1);
+    assert_eq!(after[0].fees, 200_000_000);
+}
+
+#[tokio::test]
+async fn fees_are_served_over_http() {
+    let indexer = fee_indexer().await;
+    let (url, _shutdown) = common::serve(&indexer).await;
+
+    let schedule: serde_json::Value = reqwest::get(format!("{}/fees/schedule", url))
+        .await
+        .expect("request")
+        .json()
+        .await
+        .expect("json");
+    assert_eq!(schedule[1]["effective_from"], UPDATED_AT);
+
+    let revenue: serde_json::Value = reqwest::get(format!("{}/fees/revenue?period=month", url))
+        .await
+        .expect("request")
+        .json()
+        .await
+        .expect("json");
+    // 2024-12-01
+    assert_eq!(revenue[0]["start"], 1_733_011_200_000u64);
+    assert_eq!(revenue[0]["fees"], 300_000_000);
+}
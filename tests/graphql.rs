@@ -0,0 +1,280 @@
+mod common;
+
+use indexer_new::Indexer;
+use indexer_new::{graphql, storage};
+use serde_json::{json, Value};
+
+async fn query(indexer: &Indexer, query: &str) -> Value {
+    let response = graphql::schema(indexer.db().clone()).execute(query).await;
+    assert!(response.errors.is_empty(), "{:?}", response.errors);
+    response.data.into_json().unwrap()
+}
+
+#[tokio::test]
+async fn launchpad_with_purchases_prices_and_holders() {
+    let indexer = common::indexed(vec![
+        common::fixture("LaunchpadCreated"),
+        common::fixture("BalanceUpdate"),
+        common::traded("TokensPurchased", 1, 1_000),
+        common::traded("PriceUpdate", 2, 1_000),
+        // Not traded on the launchpad
+        common::fixture("PriceUpdate"),
+    ])
+    .await;
+
+    let data = query(
+        &indexer,
+        &format!(
+            r#"{{
+                launchpad(id: "{}") {{
+                    name
+                    purchases {{ edges {{ node {{ buyer amount launchpad {{ name }} }} }} }}
+                    priceHistory {{ edges {{ node {{ newPrice }} }} }}
+                    holders {{ edges {{ node {{ address balance }} }} }}
+                }}
+            }}"#,
+            common::LAUNCHPAD_ID
+        ),
+    )
+    .await;
+
+    assert_eq!(
+        data,
+        json!({
+            "launchpad": {
+                "name": "Test Token",
+                "purchases": { "edges": [{ "node": {
+                    "buyer": common::BUYER,
+                    "amount": 5000,
+                    "launchpad": { "name": "Test Token" },
+                } }] },
+                "priceHistory": { "edges": [{ "node": { "newPrice": 105000 } }] },
+                "holders": { "edges": [{ "node": { "address": common::BUYER, "balance": 3800 } }] },
+            }
+        })
+    );
+}
+
+#[tokio::test]
+async fn purchases_are_paged_newest_first() {
+    let purchases = (0..3).map(|seq| common::traded("TokensPurchased", seq, 1_000 + seq));
+    let indexer = common::indexed(purchases.collect()).await;
+    let page = |after: &str| {
+        format!(
+            r#"{{ purchases(first: 2{}) {{
+                edges {{ node {{ timestamp }} }}
+                pageInfo {{ hasNextPage endCursor }}
+            }} }}"#,
+            after
+        )
+    };
+
+    let first = query(&indexer, &page("")).await;
+    let timestamps: Vec<&Value> = first["purchases"]["edges"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|edge| &edge["node"]["timestamp"])
+        .collect();
+    assert_eq!(timestamps, [&json!(1_002), &json!(1_001)]);
+    assert_eq!(first["purchases"]["pageInfo"]["hasNextPage"], true);
+
+    let cursor = first["purchases"]["pageInfo"]["endCursor"]
+        .as_str()
+        .unwrap();
+    let second = query(&indexer, &page(&format!(", after: \"{}\"", cursor))).await;
+    assert_eq!(
+        second["purchases"]["edges"],
+        json!([{ "node": { "timestamp": 1_000 } }])
+    );
+    assert_eq!(second["purchases"]["pageInfo"]["hasNextPage"], false);
+}
+
+#[tokio::test]
+async fn purchases_can_be_filtered() {
+    let indexer = common::indexed(vec![
+        common::traded("TokensPurchased", 0, 1_000),
+        common::traded("TokensPurchased", 1, 2_000),
+        common::fixture("TokensPurchased"),
+    ])
+    .await;
+
+    let data = query(
+        &indexer,
+        &format!(
+            r#"{{
+                byBuyer: purchases(buyer: "{}") {{ edges {{ node {{ timestamp }} }} }}
+                onLaunchpad: purchases(launchpadId: "{}", since: 1500) {{ edges {{ node {{ timestamp }} }} }}
+                nobody: purchases(buyer: "0x0") {{ edges {{ node {{ timestamp }} }} }}
+            }}"#,
+            common::BUYER,
+            common::LAUNCHPAD_ID
+        ),
+    )
+    .await;
+
+    assert_eq!(data["byBuyer"]["edges"].as_array().unwrap().len(), 3);
+    assert_eq!(
+        data["onLaunchpad"]["edges"],
+        json!([{ "node": { "timestamp": 2_000 } }])
+    );
+    assert_eq!(data["nobody"]["edges"], json!([]));
+}
+
+#[tokio::test]
+async fn holders_are_paged_by_their_latest_balance() {
+    let indexer = common::indexer().await;
+    let db = indexer.db();
+    for (holder, balance, timestamp) in [
+        ("0xa", 100, 1_000),
+        ("0xb", 300, 1_000),
+        ("0xc", 200, 1_000),
+        ("0xd", 5, 1_000),
+        // Stored before the older update it supersedes
+        ("0xa", 400, 2_000),
+        ("0xa", 100, 1_500),
+    ] {
+        db.query(
+            "CREATE balance_updates SET launchpad_id = $launchpad_id, holder = $holder, \
+             balance = $balance, timestamp = $timestamp, tx_digest = 'tx'",
+        )
+        .bind(("launchpad_id", common::LAUNCHPAD_ID))
+        .bind(("holder", holder))
+        .bind(("balance", balance))
+        .bind(("timestamp", timestamp))
+        .await
+        .expect("balance update");
+    }
+    storage::fill_holder_balances(db).await.expect("fill");
+    let page = |after: &str| {
+        format!(
+            r#"{{ holders(launchpadId: "{}", first: 2, minBalance: 10{}) {{
+                edges {{ node {{ address balance }} }}
+                pageInfo {{ hasNextPage endCursor }}
+            }} }}"#,
+            common::LAUNCHPAD_ID,
+            after
+        )
+    };
+
+    let first = query(&indexer, &page("")).await;
+    let holders = &first["holders"];
+    assert_eq!(
+        holders["edges"],
+        json!([
+            { "node": { "address": "0xa", "balance": 400 } },
+            { "node": { "address": "0xb", "balance": 300 } },
+        ])
+    );
+    assert_eq!(holders["pageInfo"]["hasNextPage"], true);
+
+    let cursor = holders["pageInfo"]["endCursor"].as_str().unwrap();
+    let second = query(&indexer, &page(&format!(", after: \"{}\"", cursor))).await;
+    assert_eq!(
+        second["holders"]["edges"],
+        json!([{ "node": { "address": "0xc", "balance": 200 } }])
+    );
+    assert_eq!(second["holders"]["pageInfo"]["hasNextPage"], false);
+}
+
+#[tokio::test]
+async fn holders_link_back_to_their_purchases() {
+    let indexer = common::indexed(vec![
+        common::fixture("LaunchpadCreated"),
+        common::fixture("BalanceUpdate"),
+        common::traded("TokensPurchased", 1, 1_000),
+    ])
+    .await;
+
+    let data = query(
+        &indexer,
+        &format!(
+            r#"{{
+                holder(launchpadId: "{}", address: "{}") {{
+                    balance
+                    launchpad {{ creator }}
+                    purchases {{ edges {{ node {{ amount }} }} }}
+                }}
+            }}"#,
+            common::LAUNCHPAD_ID,
+            common::BUYER
+        ),
+    )
+    .await;
+
+    assert_eq!(
+        data,
+        json!({ "holder": {
+            "balance": 3800,
+            "launchpad": { "creator": common::CREATOR },
+            "purchases": { "edges": [{ "node": { "amount": 5000 } }] },
+        } })
+    );
+}
+
+#[tokio::test]
+async fn overly_deep_or_costly_queries_are_rejected() {
+    let indexer = common::indexer().await;
+    let schema = graphql::schema(indexer.db().clone());
+
+    // Purchase -> launchpad -> purchases, nested until it passes the depth limit
+    let mut nested = "buyer".to_string();
+    for _ in 0..3 {
+        nested = format!(
+            "launchpad {{ purchases {{ edges {{ node {{ {} }} }} }} }}",
+            nested
+        );
+    }
+    let deep = schema
+        .execute(format!(
+            "{{ purchases {{ edges {{ node {{ {} }} }} }} }}",
+            nested
+        ))
+        .await;
+    assert_eq!(deep.errors.len(), 1);
+    assert!(
+        deep.errors[0].message.contains("nested too deep"),
+        "{:?}",
+        deep.errors
+    );
+
+    // Shallow, but 100 launchpads of 100 purchases each
+    let costly = schema
+        .execute(
+            "{ launchpads(first: 100) { edges { node { \
+               purchases(first: 100) { edges { node { buyer amount } } } } } } }",
+        )
+        .await;
+    assert_eq!(costly.errors.len(), 1);
+    assert!(
+        costly.errors[0].message.contains("too complex"),
+        "{:?}",
+        costly.errors
+    );
+}
+
+#[tokio::test]
+async fn launchpads_of_a_page_are_loaded_together() {
+    let purchases = (0..5).map(|seq| common::traded("TokensPurchased", seq + 1, 1_000 + seq));
+    let indexer = common::indexed(
+        [
+            common::fixture("LaunchpadCreated"),
+            common::fixture("BalanceUpdate"),
+        ]
+        .into_iter()
+        .chain(purchases)
+        .collect(),
+    )
+    .await;
+
+    let data = query(
+        &indexer,
+        "{ purchases { edges { node { amount launchpad { name } } } } }",
+    )
+    .await;
+    let edges = data["purchases"]["edges"].as_array().unwrap();
+    assert_eq!(edges.len(), 5);
+    assert!(edges
+        .iter()
+        .all(|edge| edge["node"]["launchpad"]["name"] == "Test Token"));
+}
@@ -0,0 +1,265 @@
+mod common;
+
+use serde_json::json;
+
+// Index the fixture `name` into a fresh database and return the rows of `table`
+async fn index(name: &str, table: &str) -> (Vec<serde_json::Value>, String) {
+    let indexer = common::indexer().await;
+    let event = common::fixture(name);
+    let tx_digest = event.id.tx_digest.to_string();
+    indexer.handle_event(event).await.expect("handle_event");
+    (common::rows(&indexer, table).await, tx_digest)
+}
+
+#[tokio::test]
+async fn tokens_purchased_is_stored() {
+    let (rows, tx_digest) = index("TokensPurchased", "token_purchases").await;
+    assert_eq!(
+        rows,
+        vec![json!({
+            "buyer": "0x7d20dcdb2bca4f508ea9613994683eb4e76e9c4ed371169677c1be02aaf0b58e",
+            "amount": 5000,
+            "timestamp": 1734030000000u64,
+            "tx_digest": tx_digest,
+        })]
+    );
+}
+
+#[tokio::test]
+async fn tokens_transferred_is_stored() {
+    let (rows, tx_digest) = index("TokensTransferred", "token_transfers").await;
+    assert_eq!(
+        rows,
+        vec![json!({
+            "from": "0x7d20dcdb2bca4f508ea9613994683eb4e76e9c4ed371169677c1be02aaf0b58e",
+            "to": "0x5102ff6c5c12c899ca75c40dbbb1faa261e5d825f0185178b58e0c4c0ef8ab00",
+            "amount": 1200,
+            "timestamp": 1734030001000u64,
+            "tx_digest": tx_digest,
+        })]
+    );
+}
+
+#[tokio::test]
+async fn price_update_is_stored() {
+    let (rows, tx_digest) = index("PriceUpdate", "price_updates").await;
+    assert_eq!(
+        rows,
+        vec![json!({
+            "new_price": 105000,
+            "tokens_sold": 5000,
+            "timestamp": 1734030002000u64,
+            "tx_digest": tx_digest,
+        })]
+    );
+}
+
+#[tokio::test]
+async fn liquidity_deployed_is_stored() {
+    let (rows, tx_digest) = index("LiquidityDeployed", "liquidity_deployments").await;
+    assert_eq!(
+        rows,
+        vec![json!({
+            "launchpad_id": "0xa50ccfcd28f779f33f75b533dc5ac0fe77eba0cd62220da19d20173711e8a560",
+            "recipient": "0x5102ff6c5c12c899ca75c40dbbb1faa261e5d825f0185178b58e0c4c0ef8ab00",
+            "sui_amount": 1000000000,
+            "timestamp": 1734030003000u64,
+            "tx_digest": tx_digest,
+        })]
+    );
+}
+
+#[tokio::test]
+async fn pool_paused_is_stored() {
+    let (rows, tx_digest) = index("PoolPaused", "pool_pauses").await;
+    assert_eq!(
+        rows,
+        vec![json!({
+            "launchpad_id": "0xa50ccfcd28f779f33f75b533dc5ac0fe77eba0cd62220da19d20173711e8a560",
+            "timestamp": 1734030004000u64,
+            "tx_digest": tx_digest,
+        })]
+    );
+}
+
+#[tokio::test]
+async fn pool_unpaused_is_stored() {
+    let (rows, tx_digest) = index("PoolUnpaused", "pool_unpauses").await;
+    assert_eq!(
+        rows,
+        vec![json!({
+            "launchpad_id": "0xa50ccfcd28f779f33f75b533dc5ac0fe77eba0cd62220da19d20173711e8a560",
+            "timestamp": 1734030005000u64,
+            "tx_digest": tx_digest,
+        })]
+    );
+}
+
+#[tokio::test]
+async fn launchpad_created_is_stored() {
+    let (rows, tx_digest) = index("LaunchpadCreated", "launchpads").await;
+    assert_eq!(
+        rows,
+        vec![json!({
+            "launchpad_id": "0xa50ccfcd28f779f33f75b533dc5ac0fe77eba0cd62220da19d20173711e8a560",
+            "creator": "0x5102ff6c5c12c899ca75c40dbbb1faa261e5d825f0185178b58e0c4c0ef8ab00",
+            "name": "Test Token",
+            "description": "A test launchpad",
+            "token_supply": 1000000000,
+            "initial_price": 100000,
+            "price_increment": 1000,
+            "website_url": "https://example.com",
+            "timestamp": 1734030006000u64,
+            "tx_digest": tx_digest,
+        })]
+    );
+}
+
+#[tokio::test]
+async fn vesting_claimed_is_stored() {
+    let (rows, tx_digest) = index("VestingClaimed", "vesting_claims").await;
+    assert_eq!(
+        rows,
+        vec![json!({
+            "user": "0x7d20dcdb2bca4f508ea9613994683eb4e76e9c4ed371169677c1be02aaf0b58e",
+            "amount": 2500,
+            "timestamp": 1734030007000u64,
+            "tx_digest": tx_digest,
+        })]
+    );
+}
+
+#[tokio::test]
+async fn fee_updated_is_stored() {
+    let (rows, tx_digest) = index("FeeUpdated", "fee_updates").await;
+    assert_eq!(
+        rows,
+        vec![json!({
+            "previous_fee": 100000000,
+            "new_fee": 200000000,
+            "timestamp": 1734030008000u64,
+            "tx_digest": tx_digest,
+        })]
+    );
+}
+
+#[tokio::test]
+async fn admin_transferred_is_stored() {
+    let (rows, tx_digest) = index("AdminTransferred", "admin_transfers").await;
+    assert_eq!(
+        rows,
+        vec![json!({
+            "previous_admin": "0x5102ff6c5c12c899ca75c40dbbb1faa261e5d825f0185178b58e0c4c0ef8ab00",
+            "new_admin": "0x2e3b4f0a6c1d9e8b7a6f5e4d3c2b1a0f9e8d7c6b5a4f3e2d1c0b9a8f7e6d5c4b",
+            "timestamp": 1734030009000u64,
+            "tx_digest": tx_digest,
+        })]
+    );
+}
+
+#[tokio::test]
+async fn balance_update_is_stored() {
+    let (rows, tx_digest) = index("BalanceUpdate", "balance_updates").await;
+    assert_eq!(
+        rows,
+        vec![json!({
+            "launchpad_id": "0xa50ccfcd28f779f33f75b533dc5ac0fe77eba0cd62220da19d20173711e8a560",
+            "holder": "0x7d20dcdb2bca4f508ea9613994683eb4e76e9c4ed371169677c1be02aaf0b58e",
+            "balance": 3800,
+            "timestamp": 1734030010000u64,
+            "tx_digest": tx_digest,
+        })]
+    );
+}
+
+#[tokio::test]
+async fn whitelist_updated_is_stored() {
+    let (rows, tx_digest) = index("WhitelistUpdated", "whitelist_updates").await;
+    assert_eq!(
+        rows,
+        vec![json!({
+            "launchpad_id": "0xa50ccfcd28f779f33f75b533dc5ac0fe77eba0cd62220da19d20173711e8a560",
+            "address": "0x7d20dcdb2bca4f508ea9613994683eb4e76e9c4ed371169677c1be02aaf0b58e",
+            "whitelisted": true,
+            "timestamp": 1734030010000u64,
+            "tx_digest": tx_digest,
+        })]
+    );
+}
+
+#[tokio::test]
+async fn liquidity_moved_is_stored() {
+    let (rows, tx_digest) = index("LiquidityMoved", "liquidity_moves").await;
+    assert_eq!(
+        rows,
+        vec![json!({
+            "launchpad_id": "0xa50ccfcd28f779f33f75b533dc5ac0fe77eba0cd62220da19d20173711e8a560",
+            "recipient": "0x5102ff6c5c12c899ca75c40dbbb1faa261e5d825f0185178b58e0c4c0ef8ab00",
+            "sui_amount": 1000000000,
+            "timestamp": 1734030011000u64,
+            "tx_digest": tx_digest,
+        })]
+    );
+}
+
+#[tokio::test]
+async fn events_from_other_modules_are_ignored() {
+    let indexer = common::indexer().await;
+    let mut event = common::fixture("TokensPurchased");
+    event.type_ = format!("{}::other::TokensPurchased", common::PACKAGE_ID)
+        .parse()
+        .unwrap();
+    indexer.handle_event(event).await.expect("handle_event");
+    assert!(common::rows(&indexer, "token_purchases").await.is_empty());
+}
+
+#[tokio::test]
+async fn replayed_events_are_stored_once() {
+    let indexer = common::indexer().await;
+    for _ in 0..2 {
+        indexer
+            .handle_event(common::fixture("TokensPurchased"))
+            .await
+            .expect("handle_event");
+    }
+    indexer
+        .handle_event(common::event("TokensPurchased", 1))
+        .await
+        .expect("handle_event");
+
+    let ids: Vec<serde_json::Value> = indexer
+        .db()
+        .query("SELECT VALUE meta::id(id) FROM token_purchases ORDER BY id")
+        .await
+        .expect("query")
+        .take(0)
+        .expect("ids");
+    let tx_digest = common::fixture("TokensPurchased").id.tx_digest.to_string();
+    assert_eq!(ids, vec![json!([tx_digest, 0]), json!([tx_digest, 1])]);
+}
+
+#[tokio::test]
+async fn every_fixture_lands_in_its_own_table() {
+    let indexer = common::indexer().await;
+    for (name, table) in [
+        ("TokensPurchased", "token_purchases"),
+        ("TokensTransferred", "token_transfers"),
+        ("PriceUpdate", "price_updates"),
+        ("LiquidityDeployed", "liquidity_deployments"),
+        ("PoolPaused", "pool_pauses"),
+        ("PoolUnpaused", "pool_unpauses"),
+        ("LaunchpadCreated", "launchpads"),
+        ("VestingClaimed", "vesting_claims"),
+        ("FeeUpdated", "fee_updates"),
+        ("AdminTransferred", "admin_transfers"),
+        ("BalanceUpdate", "balance_updates"),
+        ("WhitelistUpdated", "whitelist_updates"),
+        ("LiquidityMoved", "liquidity_moves"),
+    ] {
+        indexer
+            .handle_event(common::fixture(name))
+            .await
+            .unwrap_or_else(|e| panic!("{}: {}", name, e));
+        assert_eq!(common::rows(&indexer, table).await.len(), 1, "{}", table);
+    }
+}
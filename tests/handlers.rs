@@ -0,0 +1,148 @@
+mod common;
+
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use indexer_new::decoding::EventMetadata;
+use indexer_new::events::LaunchpadEvent;
+use indexer_new::handlers::EventHandler;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+// Remembers every event it is handed
+#[derive(Clone, Default)]
+struct Recorder(Arc<Mutex<Vec<(String, EventMetadata)>>>);
+
+#[async_trait]
+impl EventHandler for Recorder {
+    fn name(&self) -> &str {
+        "recorder"
+    }
+
+    async fn handle(&self, event: &LaunchpadEvent, metadata: &EventMetadata) -> Result<()> {
+        self.0
+            .lock()
+            .unwrap()
+            .push((event.name().to_string(), metadata.clone()));
+        Ok(())
+    }
+}
+
+struct Failing;
+
+#[async_trait]
+impl EventHandler for Failing {
+    fn name(&self) -> &str {
+        "failing"
+    }
+
+    async fn handle(&self, _: &LaunchpadEvent, _: &EventMetadata) -> Result<()> {
+        bail!("downstream unavailable")
+    }
+}
+
+struct Panicking;
+
+#[async_trait]
+impl EventHandler for Panicking {
+    fn name(&self) -> &str {
+        "panicking"
+    }
+
+    async fn handle(&self, _: &LaunchpadEvent, _: &EventMetadata) -> Result<()> {
+        panic!("handler bug")
+    }
+}
+
+#[tokio::test]
+async fn handlers_receive_stored_events_with_metadata() {
+    let recorder = Recorder::default();
+    let indexer = common::indexer().await.with_handler(recorder.clone());
+    let event = common::fixture("TokensPurchased");
+    indexer
+        .handle_event(event.clone())
+        .await
+        .expect("handle_event");
+
+    let seen = recorder.0.lock().unwrap().clone();
+    assert_eq!(
+        seen,
+        vec![(
+            "TokensPurchased".to_string(),
+            EventMetadata {
+                tx_digest: event.id.tx_digest.to_string(),
+                event_seq: event.id.event_seq,
+                sender: event.sender.to_string(),
+                checkpoint: None,
+                timestamp: event.timestamp_ms,
+                launchpad_id: None,
+            }
+        )]
+    );
+}
+
+#[tokio::test]
+async fn failing_handlers_do_not_stop_ingestion_or_other_handlers() {
+    let recorder = Recorder::default();
+    let indexer = common::indexer()
+        .await
+        .with_handler(Failing)
+        .with_handler(Panicking)
+        .with_handler(recorder.clone());
+
+    indexer
+        .handle_event(common::event("TokensPurchased", 0))
+        .await
+        .expect("handle_event");
+    indexer
+        .handle_event(common::event("TokensPurchased", 1))
+        .await
+        .expect("handle_event");
+
+    assert_eq!(common::rows(&indexer, "token_purchases").await.len(), 2);
+    assert_eq!(recorder.0.lock().unwrap().len(), 2);
+}
+
+// Takes a while over every event
+struct Slow;
+
+#[async_trait]
+impl EventHandler for Slow {
+    fn name(&self) -> &str {
+        "slow"
+    }
+
+    async fn handle(&self, _: &LaunchpadEvent, _: &EventMetadata) -> Result<()> {
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn handlers_run_concurrently() {
+    let indexer = common::indexer()
+        .await
+        .with_handler(Slow)
+        .with_handler(Slow)
+        .with_handler(Slow);
+
+    let started = Instant::now();
+    indexer
+        .handle_event(common::event("TokensPurchased", 0))
+        .await
+        .expect("handle_event");
+
+    assert!(started.elapsed() < Duration::from_millis(1200));
+}
+
+#[tokio::test]
+async fn handlers_are_not_called_for_ignored_events() {
+    let recorder = Recorder::default();
+    let indexer = common::indexer().await.with_handler(recorder.clone());
+    let mut event = common::fixture("TokensPurchased");
+    event.type_ = format!("{}::other::TokensPurchased", common::PACKAGE_ID)
+        .parse()
+        .unwrap();
+    indexer.handle_event(event).await.expect("handle_event");
+
+    assert!(recorder.0.lock().unwrap().is_empty());
+}
@@ -0,0 +1,275 @@
+mod common;
+
+use common::mock_node::{MockNode, QueryReply, StreamStep};
+use indexer_new::{storage, Indexer, PipelineConfig};
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+
+// Indexer running against a mock node until `stop` is called
+struct Running {
+    shutdown: watch::Sender<bool>,
+    handle: JoinHandle<anyhow::Result<()>>,
+}
+
+impl Running {
+    fn start(indexer: &Indexer, node: &MockNode) -> Self {
+        Self::start_with_config(indexer, node, PipelineConfig::from_env())
+    }
+
+    fn start_with_config(indexer: &Indexer, node: &MockNode, config: PipelineConfig) -> Self {
+        let (shutdown, signal) = watch::channel(false);
+        let indexer = indexer.clone();
+        let rpc_url = node.http_url();
+        let ws_url = node.ws_url();
+        let handle = tokio::spawn(async move {
+            indexer
+                .start_with_config(&rpc_url, Some(&ws_url), config, signal)
+                .await
+        });
+        Self { shutdown, handle }
+    }
+
+    async fn stop(self) {
+        self.shutdown.send(true).unwrap();
+        tokio::time::timeout(Duration::from_secs(10), self.handle)
+            .await
+            .expect("indexer stops")
+            .expect("indexer task")
+            .expect("indexer result");
+    }
+}
+
+async fn wait_for_queries(node: &MockNode, count: usize) {
+    for _ in 0..100 {
+        if node.queried_cursors().len() >= count {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    panic!("timed out waiting for {} queries", count);
+}
+
+#[tokio::test]
+async fn rejected_subscription_falls_back_to_polling() {
+    let node = MockNode::start().await;
+    node.reply_to_query(QueryReply::Page {
+        events: vec![
+            common::event("TokensPurchased", 0),
+            common::event("PriceUpdate", 0),
+        ],
+        has_next_page: false,
+    });
+
+    let indexer = common::indexer().await;
+    let running = Running::start(&indexer, &node);
+    common::wait_for_rows(&indexer, "token_purchases", 1).await;
+    common::wait_for_rows(&indexer, "price_updates", 1).await;
+    running.stop().await;
+}
+
+#[tokio::test]
+async fn polling_continues_after_rpc_errors() {
+    let node = MockNode::start().await;
+    node.reply_to_query(QueryReply::Error("node overloaded"));
+    node.reply_to_query(QueryReply::Page {
+        events: vec![common::event("TokensPurchased", 0)],
+        has_next_page: false,
+    });
+
+    let indexer = common::indexer().await;
+    let running = Running::start(&indexer, &node);
+    common::wait_for_rows(&indexer, "token_purchases", 1).await;
+    running.stop().await;
+
+    // The failed query is retried from the same position
+    assert_eq!(node.queried_cursors()[..2], [None, None]);
+}
+
+#[tokio::test]
+async fn polling_follows_next_cursor() {
+    let first = common::event("TokensPurchased", 0);
+    let node = MockNode::start().await;
+    node.reply_to_query(QueryReply::Page {
+        events: vec![first.clone()],
+        has_next_page: true,
+    });
+    node.reply_to_query(QueryReply::Page {
+        events: vec![common::event("TokensPurchased", 1)],
+        has_next_page: false,
+    });
+
+    let indexer = common::indexer().await;
+    let running = Running::start(&indexer, &node);
+    common::wait_for_rows(&indexer, "token_purchases", 2).await;
+    running.stop().await;
+
+    assert_eq!(node.queried_cursors()[..2], [None, Some(first.id)]);
+}
+
+#[tokio::test]
+async fn restart_resumes_from_saved_cursor() {
+    let last = common::event("TokensPurchased", 1);
+    let node = MockNode::start().await;
+    node.reply_to_query(QueryReply::Page {
+        events: vec![common::event("TokensPurchased", 0), last.clone()],
+        has_next_page: false,
+    });
+
+    let indexer = common::indexer().await;
+    let running = Running::start(&indexer, &node);
+    common::wait_for_rows(&indexer, "token_purchases", 2).await;
+    running.stop().await;
+
+    let restarted = MockNode::start().await;
+    let running = Running::start(&indexer, &restarted);
+    wait_for_queries(&restarted, 1).await;
+    running.stop().await;
+
+    assert_eq!(restarted.queried_cursors()[0], Some(last.id));
+    assert_eq!(common::rows(&indexer, "token_purchases").await.len(), 2);
+}
+
+#[tokio::test]
+async fn subscribed_restart_backfills_from_saved_cursor() {
+    let last = common::event("TokensPurchased", 1);
+    let node = MockNode::start().await;
+    node.reply_to_query(QueryReply::Page {
+        events: vec![common::event("TokensPurchased", 0), last.clone()],
+        has_next_page: false,
+    });
+
+    let indexer = common::indexer().await;
+    let running = Running::start(&indexer, &node);
+    common::wait_for_rows(&indexer, "token_purchases", 2).await;
+    running.stop().await;
+
+    // Committed while the indexer was down, so only a query returns it
+    let missed = common::event("TokensPurchased", 2);
+    let restarted = MockNode::start().await;
+    restarted.reply_to_query(QueryReply::Page {
+        events: vec![missed.clone()],
+        has_next_page: false,
+    });
+    restarted.accept_subscription(vec![StreamStep::Event(common::event("TokensPurchased", 3))]);
+    let running = Running::start(&indexer, &restarted);
+    common::wait_for_rows(&indexer, "token_purchases", 4).await;
+    running.stop().await;
+
+    // Backfilled up to the last page, then once more after subscribing
+    assert_eq!(
+        restarted.queried_cursors()[..2],
+        [Some(last.id), Some(missed.id)]
+    );
+}
+
+#[tokio::test]
+async fn subscribed_events_are_indexed() {
+    let node = MockNode::start().await;
+    node.accept_subscription(vec![
+        StreamStep::Event(common::event("LaunchpadCreated", 0)),
+        StreamStep::Event(common::event("TokensPurchased", 0)),
+    ]);
+
+    let indexer = common::indexer().await;
+    let running = Running::start(&indexer, &node);
+    common::wait_for_rows(&indexer, "launchpads", 1).await;
+    common::wait_for_rows(&indexer, "token_purchases", 1).await;
+    running.stop().await;
+
+    assert!(node.queried_cursors().is_empty());
+}
+
+#[tokio::test]
+async fn disconnect_falls_back_to_polling_after_last_streamed_event() {
+    let streamed = common::event("TokensPurchased", 0);
+    let node = MockNode::start().await;
+    node.accept_subscription(vec![
+        StreamStep::Event(streamed.clone()),
+        StreamStep::Disconnect,
+    ]);
+    node.reply_to_query(QueryReply::Page {
+        events: vec![common::event("TokensPurchased", 1)],
+        has_next_page: false,
+    });
+
+    let indexer = common::indexer().await;
+    let running = Running::start(&indexer, &node);
+    common::wait_for_rows(&indexer, "token_purchases", 2).await;
+    running.stop().await;
+
+    assert_eq!(node.queried_cursors()[0], Some(streamed.id));
+}
+
+// Wait until `/metrics` reports `line`, panicking after ten seconds
+async fn wait_for_metric(url: &str, line: &str) {
+    for _ in 0..100 {
+        let metrics = reqwest::get(format!("{}/metrics", url))
+            .await
+            .expect("request")
+            .text()
+            .await
+            .expect("metrics");
+        if metrics.lines().any(|l| l == line) {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    panic!("timed out waiting for {}", line);
+}
+
+#[tokio::test]
+async fn checkpoint_lag_is_measured_against_the_node() {
+    let node = MockNode::start().await;
+    node.set_latest_checkpoint(500);
+    node.reply_to_query(QueryReply::Page {
+        events: vec![common::event("TokensPurchased", 0)],
+        has_next_page: false,
+    });
+
+    let indexer = common::indexer().await;
+    let (url, _shutdown) = common::serve(&indexer).await;
+    let config = PipelineConfig {
+        checkpoint_poll_interval: Duration::from_millis(100),
+        ..PipelineConfig::from_env()
+    };
+    let running = Running::start_with_config(&indexer, &node, config);
+    common::wait_for_rows(&indexer, "token_purchases", 1).await;
+    wait_for_metric(&url, "indexer_latest_checkpoint 500").await;
+    // Caught up with nothing queued: the indexer is at the node's checkpoint
+    wait_for_metric(&url, "indexer_processed_checkpoint 500").await;
+    wait_for_metric(&url, "indexer_checkpoint_lag 0").await;
+
+    // The chain moves on while no new events arrive
+    node.set_latest_checkpoint(510);
+    wait_for_metric(&url, "indexer_latest_checkpoint 510").await;
+    wait_for_metric(&url, "indexer_checkpoint_lag 0").await;
+    running.stop().await;
+}
+
+#[tokio::test]
+async fn cursor_is_saved_while_running() {
+    let last = common::event("TokensPurchased", 1);
+    let node = MockNode::start().await;
+    node.reply_to_query(QueryReply::Page {
+        events: vec![common::event("TokensPurchased", 0), last.clone()],
+        has_next_page: false,
+    });
+
+    let indexer = common::indexer().await;
+    let running = Running::start(&indexer, &node);
+    common::wait_for_rows(&indexer, "token_purchases", 2).await;
+    // A crash from here on resumes after the persisted events
+    let mut saved = None;
+    for _ in 0..100 {
+        saved = storage::load_cursor(indexer.db())
+            .await
+            .expect("load_cursor");
+        if saved.is_some() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert_eq!(saved, Some(last.id));
+    running.stop().await;
+}
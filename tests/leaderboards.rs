@@ -0,0 +1,150 @@
+mod common;
+
+use chrono::Utc;
+use indexer_new::leaderboards::{self, LaunchpadOrder, WalletRank};
+use indexer_new::Indexer;
+use serde_json::json;
+
+const HOUR_MS: u64 = 60 * 60 * 1000;
+
+fn now() -> u64 {
+    Utc::now().timestamp_millis() as u64
+}
+
+async fn active() -> Indexer {
+    let now = now();
+    common::indexed(vec![
+        // Created long ago
+        common::fixture("LaunchpadCreated"),
+        common::fixture("BalanceUpdate"),
+        common::traded("TokensPurchased", 1, now - HOUR_MS),
+        common::traded("PriceUpdate", 2, now - HOUR_MS),
+        common::traded("TokensPurchased", 3, now - 72 * HOUR_MS),
+        common::traded("TokensTransferred", 4, now - 2 * HOUR_MS),
+        // Long ago, and in a transaction without a BalanceUpdate
+        common::fixture("TokensPurchased"),
+    ])
+    .await
+}
+
+#[tokio::test]
+async fn buyers_are_ranked_by_volume_within_the_window() {
+    let indexer = active().await;
+
+    let all_time = leaderboards::top_buyers(indexer.db(), 0).await.unwrap();
+    assert_eq!(all_time.len(), 1);
+    assert_eq!(all_time[0].wallet, common::BUYER);
+    assert_eq!((all_time[0].volume, all_time[0].purchases), (15_000, 3));
+
+    let day = leaderboards::top_buyers(indexer.db(), now() - 24 * HOUR_MS)
+        .await
+        .unwrap();
+    assert_eq!((day[0].volume, day[0].purchases), (5000, 1));
+}
+
+#[tokio::test]
+async fn launchpads_are_ranked_by_sui_raised_and_buyers() {
+    let indexer = active().await;
+
+    let raised = leaderboards::top_launchpads(indexer.db(), 0, LaunchpadOrder::Raised)
+        .await
+        .unwrap();
+    assert_eq!(raised.len(), 1);
+    assert_eq!(raised[0].launchpad_id, common::LAUNCHPAD_ID);
+    assert_eq!(raised[0].name.as_deref(), Some("Test Token"));
+    // Both attributed purchases came before any price change
+    assert_eq!(raised[0].sui_raised, 2 * 5000 * 100_000);
+    assert_eq!(raised[0].unique_buyers, 1);
+
+    let day =
+        leaderboards::top_launchpads(indexer.db(), now() - 24 * HOUR_MS, LaunchpadOrder::Buyers)
+            .await
+            .unwrap();
+    assert_eq!(day[0].sui_raised, 5000 * 100_000);
+}
+
+#[tokio::test]
+async fn purchases_count_once_their_balance_update_arrives() {
+    let indexer = common::indexed(vec![
+        common::fixture("LaunchpadCreated"),
+        common::traded("TokensPurchased", 1, now() - HOUR_MS),
+    ])
+    .await;
+    let before = leaderboards::top_launchpads(indexer.db(), 0, LaunchpadOrder::Raised)
+        .await
+        .unwrap();
+    assert!(before.is_empty());
+
+    indexer
+        .handle_event(common::fixture("BalanceUpdate"))
+        .await
+        .expect("handle_event");
+    let after = leaderboards::top_launchpads(indexer.db(), 0, LaunchpadOrder::Raised)
+        .await
+        .unwrap();
+    assert_eq!(after[0].sui_raised, 5000 * 100_000);
+    assert_eq!(after[0].unique_buyers, 1);
+}
+
+#[tokio::test]
+async fn wallets_are_ranked_by_event_count() {
+    let indexer = active().await;
+
+    let all_time = leaderboards::most_active_wallets(indexer.db(), 0)
+        .await
+        .unwrap();
+    assert_eq!(
+        all_time,
+        vec![
+            WalletRank {
+                rank: 1,
+                wallet: common::BUYER.to_string(),
+                events: 4,
+            },
+            WalletRank {
+                rank: 2,
+                wallet: common::CREATOR.to_string(),
+                events: 2,
+            },
+        ]
+    );
+}
+
+#[tokio::test]
+async fn leaderboards_are_served_over_http() {
+    let indexer = active().await;
+    let (url, _shutdown) = common::serve(&indexer).await;
+
+    let get = |path: &str| reqwest::get(format!("{}{}", url, path));
+    let wallets: serde_json::Value = get("/leaderboards/wallets?window=24h")
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(
+        wallets,
+        json!([
+            { "rank": 1, "wallet": common::BUYER, "events": 2 },
+            { "rank": 2, "wallet": common::CREATOR, "events": 1 },
+        ])
+    );
+
+    let buyers: serde_json::Value = get("/leaderboards/buyers?window=7d&limit=1")
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(buyers[0]["volume"], 10_000);
+
+    let launchpads = get("/leaderboards/launchpads?by=buyers").await.unwrap();
+    assert_eq!(launchpads.status(), 200);
+    assert_eq!(
+        get("/leaderboards/buyers?window=1y")
+            .await
+            .unwrap()
+            .status(),
+        400
+    );
+}
@@ -0,0 +1,178 @@
+mod common;
+
+use indexer_new::api;
+use serde_json::Value;
+
+async fn get(url: &str) -> (u16, Value) {
+    let response = reqwest::get(url).await.expect("request");
+    let status = response.status().as_u16();
+    (status, response.json().await.unwrap_or(Value::Null))
+}
+
+// `#/components/schemas/<name>` resolved against `spec`
+fn resolve<'a>(spec: &'a Value, schema: &'a Value) -> &'a Value {
+    match schema["$ref"].as_str() {
+        Some(reference) => {
+            let name = reference.trim_start_matches("#/components/schemas/");
+            &spec["components"]["schemas"][name]
+        }
+        None => schema,
+    }
+}
+
+// Assert `value` has the type and required properties `schema` documents
+fn assert_conforms(spec: &Value, schema: &Value, value: &Value, path: &str) {
+    let schema = resolve(spec, schema);
+    match schema["type"].as_str() {
+        Some("array") => {
+            let items = value
+                .as_array()
+                .unwrap_or_else(|| panic!("{}: not an array", path));
+            for item in items {
+                assert_conforms(spec, &schema["items"], item, path);
+            }
+        }
+        Some("object") => {
+            let object = value
+                .as_object()
+                .unwrap_or_else(|| panic!("{}: not an object", path));
+            for required in schema["required"].as_array().into_iter().flatten() {
+                let name = required.as_str().unwrap();
+                assert!(object.contains_key(name), "{}: missing {}", path, name);
+            }
+            for (name, property) in schema["properties"].as_object().into_iter().flatten() {
+                if let Some(field) = object.get(name) {
+                    assert_conforms(spec, property, field, &format!("{}.{}", path, name));
+                }
+            }
+        }
+        Some("integer") => assert!(value.is_u64() || value.is_i64(), "{}: not an integer", path),
+        Some("string") => assert!(value.is_string(), "{}: not a string", path),
+        _ => {}
+    }
+}
+
+#[tokio::test]
+async fn served_spec_matches_the_handlers() {
+    let indexer = common::indexer().await;
+    let (url, _shutdown) = common::serve(&indexer).await;
+
+    let (status, served) = get(&format!("{}/openapi.json", url)).await;
+    assert_eq!(status, 200);
+    assert_eq!(served, serde_json::to_value(api::openapi()).unwrap());
+
+    let docs = reqwest::get(format!("{}/docs", url)).await.expect("docs");
+    let page = docs.text().await.unwrap();
+    assert!(page.contains("/openapi.json"));
+    // Redoc comes from this server, not a CDN
+    assert!(page.contains(r#"src="/docs/redoc.standalone.js""#));
+    assert!(!page.contains("https://"));
+}
+
+#[test]
+fn spec_covers_every_route() {
+    let spec = serde_json::to_value(api::openapi()).unwrap();
+    for (path, method) in [
+        ("/launchpads", "get"),
+        ("/webhooks", "get"),
+        ("/webhooks", "post"),
+        ("/webhooks/{id}", "delete"),
+        ("/webhooks/{id}/deliveries", "get"),
+        ("/feed/ws", "get"),
+        ("/feed/sse", "get"),
+        ("/graphql", "get"),
+        ("/graphql", "post"),
+        ("/healthz", "get"),
+        ("/readyz", "get"),
+        ("/metrics", "get"),
+    ] {
+        assert!(
+            spec["paths"][path][method].is_object(),
+            "{} {} is not documented",
+            method,
+            path
+        );
+    }
+}
+
+// Every documented GET answers with a documented status and a body matching
+// the documented schema. The feed streams never finish, so they are left out.
+#[tokio::test]
+async fn responses_match_the_spec() {
+    let indexer = common::indexed(vec![
+        common::fixture("LaunchpadCreated"),
+        common::fixture("BalanceUpdate"),
+        common::traded("TokensPurchased", 1, 1_000),
+        common::traded("PriceUpdate", 2, 1_000),
+    ])
+    .await;
+    let (url, _shutdown) = common::serve(&indexer).await;
+    let spec = serde_json::to_value(api::openapi()).unwrap();
+
+    let paths = spec["paths"].as_object().unwrap();
+    assert!(!paths.is_empty());
+    for (path, operations) in paths {
+        let Some(operation) = operations.get("get") else {
+            continue;
+        };
+        if path.starts_with("/feed/") {
+            continue;
+        }
+        let concrete = path
+            .replace("{id}", common::LAUNCHPAD_ID)
+            .replace("{address}", common::BUYER);
+        assert!(!concrete.contains('{'), "unhandled parameter in {}", path);
+
+        let (status, body) = get(&format!("{}{}", url, concrete)).await;
+        let documented = &operation["responses"][status.to_string()];
+        assert!(
+            documented.is_object(),
+            "{} answered undocumented {}",
+            path,
+            status
+        );
+        if let Some(schema) = documented["content"]["application/json"].get("schema") {
+            assert_conforms(&spec, schema, &body, path);
+        }
+    }
+}
+
+#[tokio::test]
+async fn unknown_launchpads_are_not_found() {
+    let indexer = common::indexer().await;
+    let (url, _shutdown) = common::serve(&indexer).await;
+
+    let (status, body) = get(&format!("{}/launchpads/0x1", url)).await;
+    assert_eq!(status, 404);
+    assert_eq!(body["error"], "launchpad not found");
+}
+
+#[tokio::test]
+async fn candles_aggregate_prices_and_purchases() {
+    let indexer = common::indexed(vec![
+        common::fixture("BalanceUpdate"),
+        common::traded("PriceUpdate", 1, 60_000),
+        common::traded("TokensPurchased", 2, 60_500),
+        common::traded("TokensPurchased", 3, 130_000),
+    ])
+    .await;
+    let (url, _shutdown) = common::serve(&indexer).await;
+
+    let (status, body) = get(&format!(
+        "{}/launchpads/{}/candles?interval=1m",
+        url,
+        common::LAUNCHPAD_ID
+    ))
+    .await;
+    assert_eq!(status, 200);
+    assert_eq!(
+        body,
+        serde_json::json!([
+            { "start": 60_000, "open": 105000, "high": 105000, "low": 105000,
+              "close": 105000, "volume": 5000, "trades": 1 },
+            // No price change that minute: flat at the last price
+            { "start": 120_000, "open": 105000, "high": 105000, "low": 105000,
+              "close": 105000, "volume": 5000, "trades": 1 },
+        ])
+    );
+}
@@ -0,0 +1,202 @@
+mod common;
+
+use indexer_new::events::TokensPurchased;
+use indexer_new::pnl::{self, Basis, PnlTracker};
+use indexer_new::Indexer;
+
+async fn tracked() -> Indexer {
+    let indexer = common::indexer().await;
+    let tracker = PnlTracker::new(indexer.db().clone());
+    indexer.with_handler(tracker)
+}
+
+async fn handle_all(indexer: &Indexer, events: Vec<sui_sdk::rpc_types::SuiEvent>) {
+    for event in events {
+        indexer.handle_event(event).await.expect("handle_event");
+    }
+}
+
+// The initial price is 100000 and every PriceUpdate fixture sets 105000
+fn history() -> Vec<sui_sdk::rpc_types::SuiEvent> {
+    vec![
+        common::fixture("LaunchpadCreated"),
+        // Waits until the BalanceUpdate attributes it
+        common::traded("TokensPurchased", 1, 1_000),
+        common::fixture("BalanceUpdate"),
+        common::traded("PriceUpdate", 2, 1_000),
+        common::traded("TokensPurchased", 3, 2_000),
+        common::traded("TokensTransferred", 4, 3_000),
+    ]
+}
+
+async fn traded() -> Indexer {
+    let indexer = tracked().await;
+    handle_all(&indexer, history()).await;
+    indexer
+}
+
+#[tokio::test]
+async fn transfers_out_realize_pnl_under_both_methods() {
+    let indexer = traded().await;
+
+    let buyer = pnl::pnl(indexer.db(), common::BUYER, common::LAUNCHPAD_ID)
+        .await
+        .expect("pnl")
+        .expect("buyer position");
+
+    // Bought 5000 at 100000 and 5000 at 105000, sent 1200 at 105000
+    assert_eq!(buyer.quantity, 8800);
+    assert_eq!(buyer.current_price, Some(105_000));
+    assert_eq!(
+        buyer.fifo,
+        Basis {
+            cost_basis: 3800 * 100_000 + 5000 * 105_000,
+            realized: 1200 * 5000,
+            unrealized: Some(19_000_000),
+        }
+    );
+    assert_eq!(
+        buyer.average,
+        Basis {
+            cost_basis: 902_000_000,
+            realized: 3_000_000,
+            unrealized: Some(22_000_000),
+        }
+    );
+}
+
+#[tokio::test]
+async fn costs_beyond_what_the_database_stores_saturate() {
+    let indexer = traded().await;
+    // Costs about 10^20 at 105000, past i64::MAX
+    let mut whale = common::traded("TokensPurchased", 5, 4_000);
+    whale.bcs = bcs::to_bytes(&TokensPurchased {
+        buyer: common::BUYER.parse().unwrap(),
+        amount: 1_000_000_000_000_000,
+        timestamp: 4_000,
+    })
+    .expect("bcs");
+    handle_all(&indexer, vec![whale]).await;
+
+    let buyer = pnl::pnl(indexer.db(), common::BUYER, common::LAUNCHPAD_ID)
+        .await
+        .expect("pnl")
+        .expect("buyer position");
+    assert_eq!(buyer.quantity, 1_000_000_000_008_800);
+    assert_eq!(buyer.average.cost_basis, i64::MAX as u64);
+    assert_eq!(buyer.fifo.cost_basis, i64::MAX as u64);
+}
+
+#[tokio::test]
+async fn transfers_in_are_acquired_at_the_current_price() {
+    let indexer = traded().await;
+
+    let positions = pnl::wallet_pnl(indexer.db(), common::CREATOR)
+        .await
+        .expect("pnl");
+
+    assert_eq!(positions.len(), 1);
+    assert_eq!(positions[0].quantity, 1200);
+    assert_eq!(positions[0].fifo.cost_basis, 1200 * 105_000);
+    assert_eq!(positions[0].fifo.unrealized, Some(0));
+    assert_eq!(positions[0].average.realized, 0);
+}
+
+#[tokio::test]
+async fn positions_are_rebuilt_from_stored_trades_on_start() {
+    let live = traded().await;
+    // Stored by a run that stopped before the tracker saw any of it
+    let restarted = common::indexed(history()).await;
+    assert!(pnl::wallet_pnl(restarted.db(), common::BUYER)
+        .await
+        .expect("pnl")
+        .is_empty());
+
+    let tracker = PnlTracker::start(restarted.db().clone())
+        .await
+        .expect("start");
+    for wallet in [common::BUYER, common::CREATOR] {
+        assert_eq!(
+            pnl::wallet_pnl(restarted.db(), wallet).await.expect("pnl"),
+            pnl::wallet_pnl(live.db(), wallet).await.expect("pnl"),
+        );
+    }
+
+    // Starting again applies nothing twice
+    drop(tracker);
+    PnlTracker::start(restarted.db().clone())
+        .await
+        .expect("start");
+    let buyer = pnl::pnl(restarted.db(), common::BUYER, common::LAUNCHPAD_ID)
+        .await
+        .expect("pnl")
+        .expect("buyer position");
+    assert_eq!(buyer.quantity, 8800);
+}
+
+#[tokio::test]
+async fn trades_waiting_for_their_balance_update_survive_a_restart() {
+    // The purchase is stored, then the process stops before the BalanceUpdate
+    let indexer = common::indexed(vec![
+        common::fixture("LaunchpadCreated"),
+        common::traded("TokensPurchased", 1, 1_000),
+    ])
+    .await;
+
+    let tracker = PnlTracker::start(indexer.db().clone())
+        .await
+        .expect("start");
+    let indexer = indexer.with_handler(tracker);
+    handle_all(&indexer, vec![common::fixture("BalanceUpdate")]).await;
+
+    let buyer = pnl::pnl(indexer.db(), common::BUYER, common::LAUNCHPAD_ID)
+        .await
+        .expect("pnl")
+        .expect("buyer position");
+    assert_eq!(buyer.quantity, 5000);
+    assert_eq!(buyer.fifo.cost_basis, 5000 * 100_000);
+}
+
+#[tokio::test]
+async fn replayed_trades_are_applied_once() {
+    let indexer = traded().await;
+    handle_all(
+        &indexer,
+        vec![
+            common::traded("TokensPurchased", 3, 2_000),
+            common::traded("TokensTransferred", 4, 3_000),
+        ],
+    )
+    .await;
+
+    let buyer = pnl::pnl(indexer.db(), common::BUYER, common::LAUNCHPAD_ID)
+        .await
+        .expect("pnl")
+        .expect("buyer position");
+    assert_eq!(buyer.quantity, 8800);
+    assert_eq!(buyer.fifo.realized, 6_000_000);
+}
+
+#[tokio::test]
+async fn pnl_is_served_over_http() {
+    let indexer = traded().await;
+    let (url, _shutdown) = common::serve(&indexer).await;
+
+    let body: serde_json::Value = reqwest::get(format!(
+        "{}/wallets/{}/pnl/{}",
+        url,
+        common::BUYER,
+        common::LAUNCHPAD_ID
+    ))
+    .await
+    .expect("request")
+    .json()
+    .await
+    .expect("json");
+    assert_eq!(body["average"]["realized"], 3_000_000);
+
+    let missing = reqwest::get(format!("{}/wallets/0x1/pnl/{}", url, common::LAUNCHPAD_ID))
+        .await
+        .expect("request");
+    assert_eq!(missing.status(), 404);
+}
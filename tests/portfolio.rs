@@ -0,0 +1,88 @@
+mod common;
+
+use indexer_new::portfolio::{self, Position};
+
+async fn traded_indexer() -> indexer_new::Indexer {
+    common::indexed(vec![
+        common::fixture("LaunchpadCreated"),
+        common::fixture("BalanceUpdate"),
+        common::traded("TokensPurchased", 1, 1_000),
+        common::traded("PriceUpdate", 2, 1_000),
+        common::traded("TokensPurchased", 3, 2_000),
+        common::traded("TokensTransferred", 4, 3_000),
+        common::traded("VestingClaimed", 5, 4_000),
+        // In a transaction without a BalanceUpdate, so not attributable
+        common::fixture("TokensPurchased"),
+    ])
+    .await
+}
+
+#[tokio::test]
+async fn portfolio_combines_activity_per_launchpad() {
+    let indexer = traded_indexer().await;
+
+    let portfolio = portfolio::portfolio(indexer.db(), common::BUYER)
+        .await
+        .expect("portfolio");
+
+    assert_eq!(
+        portfolio.positions,
+        vec![Position {
+            launchpad_id: common::LAUNCHPAD_ID.to_string(),
+            launchpad_name: Some("Test Token".to_string()),
+            balance: 3800,
+            tokens_bought: 10_000,
+            // The first purchase at the initial price, the second after the
+            // price moved to 105000
+            spent: 5000 * 100_000 + 5000 * 105_000,
+            tokens_received: 0,
+            tokens_sent: 1200,
+            vesting_claimed: 2500,
+            latest_price: Some(105_000),
+            value: Some(3800 * 105_000),
+        }]
+    );
+    assert_eq!(portfolio.total_spent, 1_025_000_000);
+    assert_eq!(portfolio.total_value, 399_000_000);
+}
+
+#[tokio::test]
+async fn received_tokens_show_without_a_balance_update() {
+    let indexer = traded_indexer().await;
+
+    let portfolio = portfolio::portfolio(indexer.db(), common::CREATOR)
+        .await
+        .expect("portfolio");
+
+    assert_eq!(portfolio.positions.len(), 1);
+    let position = &portfolio.positions[0];
+    assert_eq!(position.tokens_received, 1200);
+    assert_eq!(position.balance, 0);
+    assert_eq!(position.value, Some(0));
+    assert_eq!(portfolio.total_spent, 0);
+}
+
+#[tokio::test]
+async fn portfolio_is_served_over_http() {
+    let indexer = traded_indexer().await;
+    let (url, _shutdown) = common::serve(&indexer).await;
+
+    let body: serde_json::Value =
+        reqwest::get(format!("{}/wallets/{}/portfolio", url, common::BUYER))
+            .await
+            .expect("request")
+            .json()
+            .await
+            .expect("json");
+    assert_eq!(body["wallet"], common::BUYER);
+    assert_eq!(body["positions"][0]["tokens_bought"], 10_000);
+    assert_eq!(body["total_value"], 399_000_000);
+
+    let empty: serde_json::Value = reqwest::get(format!("{}/wallets/0x1/portfolio", url))
+        .await
+        .expect("request")
+        .json()
+        .await
+        .expect("json");
+    assert_eq!(empty["positions"], serde_json::json!([]));
+}
@@ -0,0 +1,62 @@
+mod common;
+
+use indexer_new::capture::{self, Recorder, ReplaySummary};
+use std::path::PathBuf;
+
+fn capture_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("{}-{}.jsonl", name, std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+#[tokio::test]
+async fn recorded_events_replay_into_a_fresh_database() {
+    let path = capture_path("replay-roundtrip");
+    let mut recorder = Recorder::create(&path).await.unwrap();
+    for name in ["LaunchpadCreated", "TokensPurchased", "PriceUpdate"] {
+        recorder.record(&common::fixture(name)).await.unwrap();
+    }
+    recorder.flush().await.unwrap();
+
+    let indexer = common::indexer().await;
+    let summary = capture::replay(&indexer, &path).await.unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(
+        summary,
+        ReplaySummary {
+            events: 3,
+            failed: 0
+        }
+    );
+    assert_eq!(common::rows(&indexer, "launchpads").await.len(), 1);
+    assert_eq!(common::rows(&indexer, "token_purchases").await.len(), 1);
+    assert_eq!(common::rows(&indexer, "price_updates").await.len(), 1);
+}
+
+#[tokio::test]
+async fn replay_counts_events_that_fail_to_decode() {
+    let path = capture_path("replay-failures");
+    let mut recorder = Recorder::create(&path).await.unwrap();
+    let mut broken = common::fixture("TokensPurchased");
+    broken.bcs.truncate(3);
+    recorder.record(&broken).await.unwrap();
+    recorder
+        .record(&common::fixture("PriceUpdate"))
+        .await
+        .unwrap();
+    recorder.flush().await.unwrap();
+
+    let indexer = common::indexer().await;
+    let summary = capture::replay(&indexer, &path).await.unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(
+        summary,
+        ReplaySummary {
+            events: 2,
+            failed: 1
+        }
+    );
+    assert_eq!(common::rows(&indexer, "price_updates").await.len(), 1);
+}
@@ -0,0 +1,243 @@
+mod common;
+
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use indexer_new::decoding::EventMetadata;
+use indexer_new::events::LaunchpadEvent;
+use indexer_new::handlers::EventHandler;
+use indexer_new::transactions::{self, TransactionMeta, TransactionSource};
+use serde::Deserialize;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+const CHECKPOINT: u64 = 4242;
+
+// Answers every lookup with the same context and counts the lookups
+#[derive(Clone, Default)]
+struct Node(Arc<AtomicUsize>);
+
+#[async_trait]
+impl TransactionSource for Node {
+    async fn transaction(&self, tx_digest: &str) -> Result<TransactionMeta> {
+        self.0.fetch_add(1, Ordering::Relaxed);
+        Ok(TransactionMeta {
+            tx_digest: tx_digest.to_string(),
+            sender: common::BUYER.to_string(),
+            status: "success".to_string(),
+            error: None,
+            gas_used: 1_997_880,
+            move_call: Some(format!("{}::launchpad::buy_tokens", common::PACKAGE_ID)),
+            checkpoint: Some(CHECKPOINT),
+            timestamp: Some(1_734_030_000_000),
+        })
+    }
+}
+
+// Only answers batches, counting the requests
+#[derive(Clone, Default)]
+struct BatchingNode(Arc<AtomicUsize>);
+
+#[async_trait]
+impl TransactionSource for BatchingNode {
+    async fn transaction(&self, _: &str) -> Result<TransactionMeta> {
+        bail!("looked up on its own")
+    }
+
+    async fn transactions(&self, tx_digests: &[String]) -> Result<Vec<TransactionMeta>> {
+        self.0.fetch_add(1, Ordering::Relaxed);
+        let mut metas = Vec::new();
+        for tx_digest in tx_digests {
+            metas.push(Node::default().transaction(tx_digest).await?);
+        }
+        Ok(metas)
+    }
+}
+
+struct Unreachable;
+
+#[async_trait]
+impl TransactionSource for Unreachable {
+    async fn transaction(&self, _: &str) -> Result<TransactionMeta> {
+        bail!("node unavailable")
+    }
+}
+
+// Checkpoints handlers were given
+#[derive(Clone, Default)]
+struct Checkpoints(Arc<Mutex<Vec<Option<u64>>>>);
+
+#[async_trait]
+impl EventHandler for Checkpoints {
+    fn name(&self) -> &str {
+        "checkpoints"
+    }
+
+    async fn handle(&self, _: &LaunchpadEvent, metadata: &EventMetadata) -> Result<()> {
+        self.0.lock().unwrap().push(metadata.checkpoint);
+        Ok(())
+    }
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Linked {
+    sender: Option<String>,
+    gas_used: Option<i64>,
+}
+
+// `transaction` fields of the rows of `table`, followed through the link
+async fn linked(indexer: &indexer_new::Indexer, table: &str) -> Vec<Linked> {
+    indexer
+        .db()
+        .query(format!(
+            "SELECT transaction.sender AS sender, transaction.gas_used AS gas_used FROM {}",
+            table
+        ))
+        .await
+        .expect("query")
+        .take(0)
+        .expect("rows")
+}
+
+#[tokio::test]
+async fn transactions_are_fetched_once_and_linked() {
+    let node = Node::default();
+    let checkpoints = Checkpoints::default();
+    let indexer = common::indexer()
+        .await
+        .with_transaction_source(node.clone())
+        .with_handler(checkpoints.clone());
+    for event in [
+        common::fixture("BalanceUpdate"),
+        common::traded("TokensPurchased", 1, 1_000),
+        common::traded("PriceUpdate", 2, 1_000),
+    ] {
+        indexer.handle_event(event).await.expect("handle_event");
+    }
+
+    // All three events come from one transaction
+    assert_eq!(node.0.load(Ordering::Relaxed), 1);
+    assert_eq!(*checkpoints.0.lock().unwrap(), vec![Some(CHECKPOINT); 3]);
+    assert_eq!(
+        linked(&indexer, "token_purchases").await,
+        vec![Linked {
+            sender: Some(common::BUYER.to_string()),
+            gas_used: Some(1_997_880),
+        }]
+    );
+
+    let digest = common::fixture("BalanceUpdate").id.tx_digest.to_string();
+    let meta = transactions::transaction(indexer.db(), &digest)
+        .await
+        .expect("transaction")
+        .expect("stored context");
+    assert_eq!(meta.checkpoint, Some(CHECKPOINT));
+    assert_eq!(meta.status, "success");
+}
+
+#[tokio::test]
+async fn failed_lookups_still_store_the_event() {
+    let indexer = common::indexer().await.with_transaction_source(Unreachable);
+
+    indexer
+        .handle_event(common::fixture("TokensPurchased"))
+        .await
+        .expect("handle_event");
+
+    assert_eq!(
+        linked(&indexer, "token_purchases").await,
+        vec![Linked {
+            sender: None,
+            gas_used: None,
+        }]
+    );
+    let digest = common::fixture("TokensPurchased").id.tx_digest.to_string();
+    assert_eq!(
+        transactions::transaction(indexer.db(), &digest)
+            .await
+            .expect("transaction"),
+        None
+    );
+}
+
+#[tokio::test]
+async fn transactions_of_a_batch_are_looked_up_together() {
+    let node = BatchingNode::default();
+    let indexer = common::indexer().await;
+    let digests: Vec<String> = [
+        "TokensPurchased",
+        "PriceUpdate",
+        "BalanceUpdate",
+        "PriceUpdate",
+    ]
+    .iter()
+    .map(|name| common::fixture(name).id.tx_digest.to_string())
+    .collect();
+
+    let fetched = transactions::prefetch(indexer.db(), &node, digests.clone())
+        .await
+        .expect("prefetch");
+    assert_eq!(fetched, 3);
+    assert_eq!(node.0.load(Ordering::Relaxed), 1);
+
+    // Stored transactions aren't requested again
+    let fetched = transactions::prefetch(indexer.db(), &node, digests)
+        .await
+        .expect("prefetch");
+    assert_eq!(fetched, 0);
+    assert_eq!(node.0.load(Ordering::Relaxed), 1);
+}
+
+#[tokio::test]
+async fn rows_stored_without_their_transaction_are_linked_later() {
+    let indexer = common::indexer().await.with_transaction_source(Unreachable);
+    for name in ["TokensPurchased", "FeeUpdated"] {
+        indexer
+            .handle_event(common::fixture(name))
+            .await
+            .expect("handle_event");
+    }
+
+    let fetched = transactions::link_unlinked(indexer.db(), &BatchingNode::default(), 100)
+        .await
+        .expect("link_unlinked");
+    assert_eq!(fetched, 2);
+    for table in ["token_purchases", "fee_updates"] {
+        assert_eq!(
+            linked(&indexer, table).await,
+            vec![Linked {
+                sender: Some(common::BUYER.to_string()),
+                gas_used: Some(1_997_880),
+            }]
+        );
+    }
+
+    let fetched = transactions::link_unlinked(indexer.db(), &Unreachable, 100)
+        .await
+        .expect("link_unlinked");
+    assert_eq!(fetched, 0);
+}
+
+#[tokio::test]
+async fn transactions_are_served_over_http() {
+    let indexer = common::indexer()
+        .await
+        .with_transaction_source(Node::default());
+    let event = common::fixture("TokensPurchased");
+    let digest = event.id.tx_digest.to_string();
+    indexer.handle_event(event).await.expect("handle_event");
+    let (url, _shutdown) = common::serve(&indexer).await;
+
+    let body: serde_json::Value = reqwest::get(format!("{}/transactions/{}", url, digest))
+        .await
+        .expect("request")
+        .json()
+        .await
+        .expect("json");
+    assert_eq!(body["sender"], common::BUYER);
+    assert_eq!(body["checkpoint"], CHECKPOINT);
+
+    let missing = reqwest::get(format!("{}/transactions/unknown", url))
+        .await
+        .expect("request");
+    assert_eq!(missing.status(), 404);
+}
@@ -0,0 +1,168 @@
+mod common;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use indexer_new::vesting::{self, VestingSource, VestingTerms};
+use indexer_new::Indexer;
+
+const DAY_MS: u64 = 24 * 60 * 60 * 1000;
+
+// Source that gives every launchpad the same terms
+struct Terms(VestingTerms);
+
+#[async_trait]
+impl VestingSource for Terms {
+    async fn terms(&self, _launchpad_id: &str) -> Result<VestingTerms> {
+        Ok(self.0)
+    }
+}
+
+fn vesting_over(duration_ms: u64) -> Terms {
+    Terms(VestingTerms {
+        enabled: true,
+        duration_ms,
+    })
+}
+
+async fn indexed_with(source: Terms) -> Indexer {
+    let indexer = common::indexer().await.with_vesting_source(source);
+    for event in [
+        common::fixture("LaunchpadCreated"),
+        common::fixture("BalanceUpdate"),
+        common::traded("TokensPurchased", 1, 1_000),
+        common::traded("TokensPurchased", 2, 1_000 + 3 * DAY_MS),
+        common::traded("VestingClaimed", 3, 1_000 + 8 * DAY_MS),
+    ] {
+        indexer.handle_event(event).await.expect("handle_event");
+    }
+    indexer
+}
+
+// Launchpad vesting over the test scenarios' 7 days
+async fn vested_indexer() -> Indexer {
+    indexed_with(vesting_over(7 * DAY_MS)).await
+}
+
+#[tokio::test]
+async fn purchases_unlock_after_the_vesting_duration() {
+    let indexer = vested_indexer().await;
+    let now = 1_000 + 8 * DAY_MS;
+
+    let positions = vesting::positions(indexer.db(), common::BUYER, now)
+        .await
+        .expect("positions");
+
+    assert_eq!(positions.len(), 1);
+    let position = &positions[0];
+    assert_eq!(position.launchpad_id, common::LAUNCHPAD_ID);
+    assert_eq!(position.allocated, 10_000);
+    assert_eq!(position.claimed, 2500);
+    assert_eq!(position.remaining, 7500);
+    assert_eq!(position.unlocked, 5000);
+    assert_eq!(position.claimable, 2500);
+    assert_eq!(position.next_unlock_at, Some(1_000 + 10 * DAY_MS));
+    assert_eq!(position.next_unlock_amount, 5000);
+    assert_eq!(position.fully_vested_at, Some(1_000 + 10 * DAY_MS));
+    assert_eq!(position.claims.len(), 1);
+    assert_eq!(position.claims[0].amount, 2500);
+}
+
+#[tokio::test]
+async fn fully_vested_positions_have_no_next_unlock() {
+    let indexer = indexed_with(vesting_over(DAY_MS)).await;
+
+    let positions = vesting::positions(indexer.db(), common::BUYER, 1_000 + 5 * DAY_MS)
+        .await
+        .expect("positions");
+
+    assert_eq!(positions[0].unlocked, 10_000);
+    assert_eq!(positions[0].claimable, 7500);
+    assert_eq!(positions[0].next_unlock_at, None);
+    assert_eq!(positions[0].next_unlock_amount, 0);
+}
+
+#[tokio::test]
+async fn claims_without_a_launchpad_are_left_out() {
+    let indexer = vested_indexer().await;
+    // Emitted by a transaction with no BalanceUpdate
+    indexer
+        .handle_event(common::fixture("VestingClaimed"))
+        .await
+        .expect("handle_event");
+
+    let positions = vesting::positions(indexer.db(), common::BUYER, u64::MAX)
+        .await
+        .expect("positions");
+
+    assert_eq!(positions[0].claimed, 2500);
+    assert_eq!(positions[0].claims.len(), 1);
+}
+
+#[tokio::test]
+async fn launchpads_without_vesting_have_no_positions() {
+    let indexer = indexed_with(Terms(VestingTerms {
+        enabled: false,
+        duration_ms: 0,
+    }))
+    .await;
+
+    let positions = vesting::positions(indexer.db(), common::BUYER, u64::MAX)
+        .await
+        .expect("positions");
+
+    assert!(positions.is_empty());
+}
+
+#[tokio::test]
+async fn missing_terms_are_fetched_later() {
+    // Indexed before any source was configured
+    let indexer = common::indexed(vec![
+        common::fixture("LaunchpadCreated"),
+        common::fixture("BalanceUpdate"),
+        common::traded("TokensPurchased", 1, 1_000),
+    ])
+    .await;
+    let positions = vesting::positions(indexer.db(), common::BUYER, u64::MAX)
+        .await
+        .expect("positions");
+    assert!(positions.is_empty());
+
+    let fetched = vesting::fetch_missing_terms(indexer.db(), &vesting_over(DAY_MS))
+        .await
+        .expect("fetch_missing_terms");
+    assert_eq!(fetched, 1);
+    let positions = vesting::positions(indexer.db(), common::BUYER, u64::MAX)
+        .await
+        .expect("positions");
+    assert_eq!(positions[0].unlocked, 5000);
+    assert_eq!(positions[0].fully_vested_at, Some(1_000 + DAY_MS));
+}
+
+#[tokio::test]
+async fn vesting_is_served_over_http() {
+    let indexer = vested_indexer().await;
+    let (url, _shutdown) = common::serve(&indexer).await;
+
+    let positions: serde_json::Value =
+        reqwest::get(format!("{}/wallets/{}/vesting", url, common::BUYER))
+            .await
+            .expect("request")
+            .json()
+            .await
+            .expect("json");
+    assert_eq!(positions[0]["allocated"], 10_000);
+    assert_eq!(positions[0]["claims"][0]["amount"], 2500);
+
+    let claims: serde_json::Value = reqwest::get(format!(
+        "{}/launchpads/{}/vesting/claims",
+        url,
+        common::LAUNCHPAD_ID
+    ))
+    .await
+    .expect("request")
+    .json()
+    .await
+    .expect("json");
+    assert_eq!(claims.as_array().map(Vec::len), Some(1));
+    assert_eq!(claims[0]["wallet"], common::BUYER);
+}
@@ -0,0 +1,408 @@
+mod common;
+
+use axum::{
+    body::Bytes,
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    routing::post,
+    Router,
+};
+use indexer_new::auth::{self, API_KEY_HEADER};
+use indexer_new::webhooks::{
+    self, Delivery, DeliveryStatus, NewSubscription, WebhookConfig, Webhooks,
+};
+use indexer_new::Indexer;
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::net::TcpListener;
+
+#[derive(Default)]
+struct Inbox {
+    // Status codes for the next requests, 200 once exhausted
+    replies: VecDeque<u16>,
+    received: Vec<(HeaderMap, Bytes)>,
+}
+
+// Local HTTP endpoint standing in for a subscriber
+struct Receiver {
+    url: String,
+    inbox: Arc<Mutex<Inbox>>,
+}
+
+impl Receiver {
+    async fn start(replies: &[u16]) -> Self {
+        let inbox = Arc::new(Mutex::new(Inbox {
+            replies: replies.iter().copied().collect(),
+            ..Inbox::default()
+        }));
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("bind receiver");
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let router = Router::new()
+            .route("/hook", post(receive))
+            .with_state(inbox.clone());
+        tokio::spawn(async move { axum::serve(listener, router).await.expect("receiver") });
+        Self { url, inbox }
+    }
+
+    fn received(&self) -> Vec<(HeaderMap, Bytes)> {
+        self.inbox.lock().unwrap().received.clone()
+    }
+}
+
+async fn receive(
+    State(inbox): State<Arc<Mutex<Inbox>>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> StatusCode {
+    let mut inbox = inbox.lock().unwrap();
+    inbox.received.push((headers, body));
+    let status = inbox.replies.pop_front().unwrap_or(200);
+    StatusCode::from_u16(status).unwrap()
+}
+
+fn config() -> WebhookConfig {
+    WebhookConfig {
+        max_attempts: 3,
+        initial_backoff: Duration::from_millis(10),
+        max_backoff: Duration::from_millis(50),
+        request_timeout: Duration::from_secs(5),
+        concurrency: 4,
+        queue_size: 16,
+        retry_poll_interval: Duration::from_millis(20),
+        subscription_ttl: Duration::from_secs(60),
+        // The receivers listen on localhost
+        allow_private_targets: true,
+    }
+}
+
+async fn indexer_with_webhooks() -> Indexer {
+    let indexer = common::indexer().await;
+    let webhooks = Webhooks::start(indexer.db().clone(), config())
+        .await
+        .expect("webhooks");
+    indexer.with_handler(webhooks)
+}
+
+async fn subscribe(indexer: &Indexer, url: &str, event_types: &[&str]) -> String {
+    let created = webhooks::subscribe(
+        indexer.db(),
+        NewSubscription {
+            url: url.to_string(),
+            secret: Some("s3cret".to_string()),
+            event_types: event_types.iter().map(|t| t.to_string()).collect(),
+            ..NewSubscription::default()
+        },
+        &config(),
+    )
+    .await
+    .expect("subscribe");
+    created.subscription.id
+}
+
+// Wait until every delivery of the subscription has finished
+async fn settled_deliveries(
+    indexer: &Indexer,
+    subscription_id: &str,
+    count: usize,
+) -> Vec<Delivery> {
+    for _ in 0..100 {
+        let deliveries = webhooks::deliveries(indexer.db(), subscription_id)
+            .await
+            .expect("deliveries");
+        if deliveries.len() >= count
+            && deliveries
+                .iter()
+                .all(|d| d.status != DeliveryStatus::Pending)
+        {
+            return deliveries;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    panic!("timed out waiting for {} deliveries", count);
+}
+
+#[tokio::test]
+async fn matching_events_are_posted_with_a_signature() {
+    let receiver = Receiver::start(&[]).await;
+    let indexer = indexer_with_webhooks().await;
+    let id = subscribe(&indexer, &receiver.url, &["TokensPurchased"]).await;
+
+    let event = common::fixture("TokensPurchased");
+    indexer
+        .handle_event(event.clone())
+        .await
+        .expect("handle_event");
+    let deliveries = settled_deliveries(&indexer, &id, 1).await;
+    assert_eq!(deliveries[0].status, DeliveryStatus::Delivered);
+    assert_eq!(deliveries[0].attempts, 1);
+
+    let received = receiver.received();
+    assert_eq!(received.len(), 1);
+    let (headers, body) = &received[0];
+    assert_eq!(
+        headers[webhooks::SIGNATURE_HEADER].to_str().unwrap(),
+        webhooks::sign("s3cret", body)
+    );
+    assert_eq!(headers[webhooks::EVENT_HEADER], "TokensPurchased");
+
+    let payload: Value = serde_json::from_slice(body).unwrap();
+    assert_eq!(payload["event_type"], "TokensPurchased");
+    assert_eq!(payload["event"]["amount"], 5000);
+    assert_eq!(
+        payload["metadata"]["tx_digest"],
+        event.id.tx_digest.to_string()
+    );
+}
+
+#[tokio::test]
+async fn events_outside_the_filter_are_not_posted() {
+    let receiver = Receiver::start(&[]).await;
+    let indexer = indexer_with_webhooks().await;
+    let id = subscribe(&indexer, &receiver.url, &["PriceUpdate"]).await;
+
+    indexer
+        .handle_event(common::fixture("TokensPurchased"))
+        .await
+        .expect("handle_event");
+    indexer
+        .handle_event(common::fixture("PriceUpdate"))
+        .await
+        .expect("handle_event");
+
+    let deliveries = settled_deliveries(&indexer, &id, 1).await;
+    assert_eq!(deliveries.len(), 1);
+    assert_eq!(deliveries[0].event_type, "PriceUpdate");
+    assert_eq!(receiver.received().len(), 1);
+}
+
+#[tokio::test]
+async fn failed_deliveries_are_retried() {
+    let receiver = Receiver::start(&[500, 503]).await;
+    let indexer = indexer_with_webhooks().await;
+    let id = subscribe(&indexer, &receiver.url, &[]).await;
+
+    indexer
+        .handle_event(common::fixture("TokensPurchased"))
+        .await
+        .expect("handle_event");
+
+    let deliveries = settled_deliveries(&indexer, &id, 1).await;
+    assert_eq!(deliveries[0].status, DeliveryStatus::Delivered);
+    assert_eq!(deliveries[0].attempts, 3);
+
+    // Every attempt carries the same body and signature
+    let received = receiver.received();
+    assert_eq!(received.len(), 3);
+    assert!(received.iter().all(|(_, body)| body == &received[0].1));
+}
+
+#[tokio::test]
+async fn deliveries_are_dead_lettered_after_max_attempts() {
+    let receiver = Receiver::start(&[500, 500, 500, 500]).await;
+    let indexer = indexer_with_webhooks().await;
+    let id = subscribe(&indexer, &receiver.url, &[]).await;
+
+    indexer
+        .handle_event(common::fixture("TokensPurchased"))
+        .await
+        .expect("handle_event");
+
+    let deliveries = settled_deliveries(&indexer, &id, 1).await;
+    assert_eq!(deliveries[0].status, DeliveryStatus::DeadLetter);
+    assert_eq!(deliveries[0].attempts, 3);
+    assert!(deliveries[0].last_error.as_deref().unwrap().contains("500"));
+    assert_eq!(receiver.received().len(), 3);
+}
+
+#[tokio::test]
+async fn retries_stop_once_unsubscribed() {
+    let receiver = Receiver::start(&[500, 500, 500]).await;
+    let indexer = common::indexer().await;
+    let slow_retries = WebhookConfig {
+        initial_backoff: Duration::from_millis(500),
+        max_backoff: Duration::from_millis(500),
+        ..config()
+    };
+    let webhooks = Webhooks::start(indexer.db().clone(), slow_retries)
+        .await
+        .expect("webhooks");
+    let indexer = indexer.with_handler(webhooks);
+    let id = subscribe(&indexer, &receiver.url, &[]).await;
+
+    indexer
+        .handle_event(common::fixture("TokensPurchased"))
+        .await
+        .expect("handle_event");
+    for _ in 0..100 {
+        if !receiver.received().is_empty() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert!(webhooks::unsubscribe(indexer.db(), &id)
+        .await
+        .expect("unsubscribe"));
+
+    let deliveries = settled_deliveries(&indexer, &id, 1).await;
+    assert_eq!(deliveries[0].status, DeliveryStatus::DeadLetter);
+    assert_eq!(deliveries[0].attempts, 1);
+    assert_eq!(
+        deliveries[0].last_error.as_deref(),
+        Some("subscription removed")
+    );
+    assert_eq!(receiver.received().len(), 1);
+}
+
+#[tokio::test]
+async fn replayed_events_are_delivered_once() {
+    let receiver = Receiver::start(&[]).await;
+    let indexer = indexer_with_webhooks().await;
+    let id = subscribe(&indexer, &receiver.url, &[]).await;
+
+    let event = common::fixture("TokensPurchased");
+    indexer
+        .handle_event(event.clone())
+        .await
+        .expect("handle_event");
+    indexer.handle_event(event).await.expect("handle_event");
+
+    assert_eq!(settled_deliveries(&indexer, &id, 1).await.len(), 1);
+    assert_eq!(receiver.received().len(), 1);
+}
+
+#[tokio::test]
+async fn launchpad_filters_match_trades_through_their_balance_update() {
+    let receiver = Receiver::start(&[]).await;
+    let indexer = indexer_with_webhooks().await;
+    let created = webhooks::subscribe(
+        indexer.db(),
+        NewSubscription {
+            url: receiver.url.clone(),
+            event_types: vec!["TokensPurchased".to_string()],
+            launchpad_id: Some(common::LAUNCHPAD_ID.to_string()),
+            ..NewSubscription::default()
+        },
+        &config(),
+    )
+    .await
+    .expect("subscribe");
+    let id = created.subscription.id;
+
+    // TokensPurchased doesn't name its launchpad, the BalanceUpdate of the
+    // same transaction does
+    for event in [
+        common::fixture("BalanceUpdate"),
+        common::traded("TokensPurchased", 1, 1_000),
+        // Same purchase from a transaction with no BalanceUpdate
+        common::event("TokensPurchased", 2),
+    ] {
+        indexer.handle_event(event).await.expect("handle_event");
+    }
+
+    let deliveries = settled_deliveries(&indexer, &id, 1).await;
+    assert_eq!(deliveries.len(), 1);
+    assert_eq!(deliveries[0].event_seq, 1);
+    let received = receiver.received();
+    let payload: Value = serde_json::from_slice(&received[0].1).unwrap();
+    assert_eq!(payload["launchpad_id"], common::LAUNCHPAD_ID);
+    assert_eq!(payload["metadata"]["launchpad_id"], common::LAUNCHPAD_ID);
+}
+
+#[tokio::test]
+async fn private_targets_are_refused() {
+    let indexer = common::indexer().await;
+    let strict = WebhookConfig {
+        allow_private_targets: false,
+        ..config()
+    };
+    for url in [
+        "http://127.0.0.1:8080/hook",
+        "http://localhost/hook",
+        "http://10.1.2.3/hook",
+        "http://192.168.0.10/hook",
+        "http://169.254.169.254/latest/meta-data",
+        "http://[::1]/hook",
+        "http://[fe80::1]/hook",
+        "http://[::ffff:127.0.0.1]/hook",
+        "ftp://example.com/hook",
+    ] {
+        let new = NewSubscription {
+            url: url.to_string(),
+            ..NewSubscription::default()
+        };
+        assert!(
+            webhooks::subscribe(indexer.db(), new, &strict)
+                .await
+                .is_err(),
+            "{} was accepted",
+            url
+        );
+    }
+    assert!(webhooks::subscriptions(indexer.db())
+        .await
+        .expect("subscriptions")
+        .is_empty());
+}
+
+#[tokio::test]
+async fn webhook_routes_need_an_admin_key() {
+    let indexer = common::indexer().await;
+    let (url, _shutdown) = common::serve(&indexer).await;
+    let (_, admin) = auth::create_key(indexer.db(), "ops", None, true)
+        .await
+        .unwrap();
+    let (_, reader) = auth::create_key(indexer.db(), "frontend", None, false)
+        .await
+        .unwrap();
+    let client = reqwest::Client::new();
+    let hooks = format!("{}/webhooks", url);
+
+    let anonymous = client.get(&hooks).send().await.unwrap();
+    assert_eq!(anonymous.status(), 401);
+    let not_admin = client
+        .get(&hooks)
+        .header(API_KEY_HEADER, &reader)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(not_admin.status(), 403);
+
+    // Targets on private networks are refused over HTTP too
+    let private = client
+        .post(&hooks)
+        .header(API_KEY_HEADER, &admin)
+        .json(&json!({ "url": "http://127.0.0.1:9/hook" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(private.status(), 400);
+
+    // The secret is shown once, when the subscription is created
+    let created: Value = client
+        .post(&hooks)
+        .header(API_KEY_HEADER, &admin)
+        .json(&json!({ "url": "http://93.184.215.14/hook", "secret": "s3cret" }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(created["secret"], "s3cret");
+    let listed: Vec<Value> = client
+        .get(&hooks)
+        .header(API_KEY_HEADER, &admin)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed[0]["id"], created["id"]);
+    assert!(listed[0].get("secret").is_none());
+}
@@ -0,0 +1,151 @@
+mod common;
+
+use indexer_new::audit::{self, AdminAction, AdminHistory};
+use indexer_new::events::WhitelistUpdated;
+use indexer_new::storage::{self, WhitelistEntry};
+use sui_sdk::rpc_types::SuiEvent;
+use sui_sdk::types::base_types::ObjectID;
+
+const OTHER: &str = "0x3c5e9f1a7b2d4c6e8f0a1b3c5d7e9f2a4b6c8d0e1f3a5b7c9d2e4f6a8b0c1d3e";
+// Neither whitelisted nor a launchpad
+const STRANGER: &str = "0x9b1d3f5a7c9e2b4d6f8a0c1e3b5d7f9a2c4e6b8d0f1a3c5e7b9d2f4a6c8e0b1d";
+
+// `address` put on or taken off the fixtures' launchpad's whitelist
+fn update(event_seq: u64, address: &str, whitelisted: bool, timestamp: u64) -> SuiEvent {
+    let mut event = common::event("WhitelistUpdated", event_seq);
+    event.timestamp_ms = Some(timestamp);
+    event.bcs = bcs::to_bytes(&WhitelistUpdated {
+        launchpad_id: ObjectID::from_hex_literal(common::LAUNCHPAD_ID).unwrap(),
+        address: address.parse().unwrap(),
+        whitelisted,
+        timestamp,
+    })
+    .expect("bcs");
+    event
+}
+
+// The fixture adds BUYER at 1734030010000. BUYER is added again later, and
+// OTHER after that.
+async fn whitelisted_indexer() -> indexer_new::Indexer {
+    common::indexed(vec![
+        common::fixture("WhitelistUpdated"),
+        update(1, common::BUYER, true, 1_734_030_020_000),
+        update(2, OTHER, true, 1_734_030_030_000),
+    ])
+    .await
+}
+
+#[tokio::test]
+async fn whitelist_lists_each_address_once() {
+    let indexer = whitelisted_indexer().await;
+
+    let entries = storage::whitelist(indexer.db(), common::LAUNCHPAD_ID)
+        .await
+        .expect("whitelist");
+
+    assert_eq!(
+        entries,
+        vec![
+            WhitelistEntry {
+                address: common::BUYER.to_string(),
+                added_at: 1_734_030_010_000,
+            },
+            WhitelistEntry {
+                address: OTHER.to_string(),
+                added_at: 1_734_030_030_000,
+            },
+        ]
+    );
+    assert!(storage::whitelist(indexer.db(), STRANGER)
+        .await
+        .expect("whitelist")
+        .is_empty());
+}
+
+#[tokio::test]
+async fn removed_addresses_leave_the_whitelist() {
+    let indexer = whitelisted_indexer().await;
+    for event in [
+        update(3, common::BUYER, false, 1_734_030_040_000),
+        update(4, OTHER, false, 1_734_030_040_000),
+        update(5, OTHER, true, 1_734_030_050_000),
+    ] {
+        indexer.handle_event(event).await.expect("handle_event");
+    }
+
+    let entries = storage::whitelist(indexer.db(), common::LAUNCHPAD_ID)
+        .await
+        .expect("whitelist");
+    assert_eq!(
+        entries,
+        vec![WhitelistEntry {
+            address: OTHER.to_string(),
+            added_at: 1_734_030_050_000,
+        }]
+    );
+    assert_eq!(
+        storage::whitelist_entry(indexer.db(), common::LAUNCHPAD_ID, common::BUYER)
+            .await
+            .expect("whitelist entry"),
+        None
+    );
+}
+
+#[tokio::test]
+async fn whitelist_updates_are_audited() {
+    let indexer = whitelisted_indexer().await;
+    let history = AdminHistory::load(indexer.db(), None)
+        .await
+        .expect("history");
+
+    let entries = audit::audit_trail(indexer.db(), &history, false, None, 100)
+        .await
+        .expect("audit");
+
+    assert_eq!(entries.len(), 3);
+    assert!(entries
+        .iter()
+        .all(|e| e.action == AdminAction::WhitelistUpdated));
+    assert_eq!(entries[0].details["address"], OTHER);
+}
+
+#[tokio::test]
+async fn whitelist_status_is_served_over_http() {
+    let indexer = whitelisted_indexer().await;
+    let (url, _shutdown) = common::serve(&indexer).await;
+
+    let listed: serde_json::Value = reqwest::get(format!(
+        "{}/launchpads/{}/whitelist",
+        url,
+        common::LAUNCHPAD_ID
+    ))
+    .await
+    .expect("request")
+    .json()
+    .await
+    .expect("json");
+    assert_eq!(listed.as_array().map(Vec::len), Some(2));
+
+    let status = |address: &'static str| {
+        let url = format!(
+            "{}/launchpads/{}/whitelist/{}",
+            url,
+            common::LAUNCHPAD_ID,
+            address
+        );
+        async move {
+            reqwest::get(url)
+                .await
+                .expect("request")
+                .json::<serde_json::Value>()
+                .await
+                .expect("json")
+        }
+    };
+    let buyer = status(common::BUYER).await;
+    assert_eq!(buyer["whitelisted"], true);
+    assert_eq!(buyer["added_at"], 1_734_030_010_000u64);
+    let stranger = status(STRANGER).await;
+    assert_eq!(stranger["whitelisted"], false);
+    assert_eq!(stranger["added_at"], serde_json::Value::Null);
+}